@@ -1,11 +1,25 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
 use csv::Writer;
 use rust_xlsxwriter::{Workbook, Format};
-use rusqlite::{Connection, Result, params};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{Connection, OptionalExtension, Result, Row, params};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+use crate::heart_rate::{self, HeartRateRecording, HeartRateSample, HeartRateSummary, Timeline};
+use crate::lactate::{self, LactateAnalysis, PeriodMeasurement};
+use crate::migrations;
+use crate::norms::{self, Classification, Norm, NormMetric};
+use crate::performance::{AerobicEstimate, Vo2maxEquation};
+use crate::progress::TestResult;
+use crate::warnings::{EliminationRules, Warning};
+use crate::protocol::{
+    ProtocolParameters, TrackGeometry, STANDARD_PROTOCOL_ID, STANDARD_SEGMENTS_PER_LAP,
+    STANDARD_TRACK_LENGTH,
+};
+
 fn format_seconds_to_time(seconds: i32) -> String {
     let hours = seconds / 3600;
     let minutes = (seconds % 3600) / 60;
@@ -13,6 +27,554 @@ fn format_seconds_to_time(seconds: i32) -> String {
     format!("{:02}:{:02}:{:02}", hours, minutes, secs)
 }
 
+// Enums stored as TEXT. Each variant has the value stored in the database
+// (also used by serde) and the Spanish label written to the exports.
+macro_rules! text_enum {
+    ($name:ident, $kind:literal, { $($variant:ident => ($value:literal, $label:literal)),+ $(,)? }) => {
+        #[derive(Debug, ::serde::Serialize, ::serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum $name {
+            $($variant),+
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value),+
+                }
+            }
+
+            pub fn label(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $label),+
+                }
+            }
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = String;
+
+            fn from_str(value: &str) -> ::std::result::Result<Self, Self::Err> {
+                $name::ALL
+                    .iter()
+                    .copied()
+                    .find(|item| item.as_str() == value)
+                    .ok_or_else(|| format!("Unknown {}: {}", $kind, value))
+            }
+        }
+
+        impl ::rusqlite::types::ToSql for $name {
+            fn to_sql(&self) -> ::rusqlite::Result<::rusqlite::types::ToSqlOutput<'_>> {
+                Ok(self.as_str().into())
+            }
+        }
+
+        impl ::rusqlite::types::FromSql for $name {
+            fn column_result(
+                value: ::rusqlite::types::ValueRef<'_>,
+            ) -> ::rusqlite::types::FromSqlResult<Self> {
+                value
+                    .as_str()?
+                    .parse()
+                    .map_err(|e: String| ::rusqlite::types::FromSqlError::Other(e.into()))
+            }
+        }
+    };
+}
+pub(crate) use text_enum;
+
+text_enum!(EvaluationStatus, "evaluation status", {
+    Completed => ("completed", "Completada"),
+    VoluntaryStop => ("voluntary_stop", "Abandono voluntario"),
+    EliminatedByWarnings => ("eliminated_by_warnings", "Eliminado por avisos"),
+    Injury => ("injury", "Lesión"),
+    EquipmentFailure => ("equipment_failure", "Fallo de material"),
+    Disqualified => ("disqualified", "Descalificado"),
+    Cancelled => ("cancelled", "Cancelada"),
+});
+
+impl EvaluationStatus {
+    // Whether the test ran until the athlete could not keep the pace, so
+    // its result can be compared with other tests
+    pub fn is_valid_result(&self) -> bool {
+        matches!(
+            self,
+            EvaluationStatus::Completed
+                | EvaluationStatus::VoluntaryStop
+                | EvaluationStatus::EliminatedByWarnings
+        )
+    }
+}
+
+text_enum!(Sex, "sex", {
+    Female => ("female", "Femenino"),
+    Male => ("male", "Masculino"),
+});
+
+text_enum!(Discipline, "discipline", {
+    InlineSpeed => ("inline_speed", "Velocidad en línea"),
+    Quad => ("quad", "Patín tradicional"),
+    Artistic => ("artistic", "Artístico"),
+    Hockey => ("hockey", "Hockey"),
+});
+
+// Age in whole years on `date` of someone born on `birth_date` (YYYY-MM-DD).
+// None when the birth date is not valid or later than `date`.
+pub fn age_on(birth_date: &str, date: NaiveDate) -> Option<i32> {
+    let birth = NaiveDate::parse_from_str(birth_date, "%Y-%m-%d").ok()?;
+    let years = date.years_since(birth)?;
+    i32::try_from(years).ok()
+}
+
+// One row of the CSV/XLSX exports
+struct ExportRow {
+    id: i64,
+    session_id: Option<i64>,
+    athlete_id: i64,
+    name: String,
+    completed_periods: String,
+    total_time: i32,
+    date: String,
+    status: EvaluationStatus,
+    status_reason: String,
+    athlete_observations: String,
+    evaluation_observations: String,
+    observations_author: String,
+    total_distance: f32,
+    measured_distance: Option<f32>,
+    distance_flagged: bool,
+    config: TestConfiguration,
+    birth_date: String,
+    athlete_age: Option<i32>,
+    sex: Option<Sex>,
+    discipline: Option<Discipline>,
+    category: String,
+    club: String,
+    license_number: String,
+    measurements: Option<Anthropometrics>,
+    aerobic: Option<AerobicEstimate>,
+    classifications: Vec<Classification>,
+    heart_rate: Option<HeartRateSummary>,
+    lactate: Option<LactateAnalysis>,
+    warnings: Vec<Warning>,
+}
+
+pub(crate) enum ExportCell {
+    Number(f64),
+    Text(String),
+}
+
+impl std::fmt::Display for ExportCell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportCell::Number(value) => write!(f, "{}", value),
+            ExportCell::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+const EXPORT_HEADERS: [&str; 56] = [
+    "ID",
+    "Sesión ID",
+    "Atleta ID",
+    "Nombre del Atleta",
+    "Periodos Completados",
+    "Tiempo Total",
+    "Fecha",
+    "Estado",
+    "Motivo",
+    "Observaciones del Atleta",
+    "Observaciones de la Evaluación",
+    "Autor de las Observaciones",
+    "Distancia Total (m)",
+    "Distancia Medida (m)",
+    "Discrepancia de Distancia",
+    "Protocolo",
+    "Recuperación (s)",
+    "Periodo Inicial",
+    "Pista (m)",
+    "Segmentos por Vuelta",
+    "Fecha de Nacimiento",
+    "Edad en la Evaluación",
+    "Sexo",
+    "Disciplina",
+    "Categoría",
+    "Club",
+    "Licencia",
+    "Peso (kg)",
+    "Altura (cm)",
+    "IMC",
+    "Grasa Corporal (%)",
+    "Longitud de Pierna (cm)",
+    "Velocidad Final (km/h)",
+    "VAM (km/h)",
+    "VO2max Estimado (ml/kg/min)",
+    "Ecuación VO2max",
+    "Avisos para Eliminar",
+    "Percentil Distancia",
+    "Valoración Distancia",
+    "Percentil VAM",
+    "Valoración VAM",
+    "Percentil VO2max",
+    "Valoración VO2max",
+    "FC Máx (lpm)",
+    "FC Media (lpm)",
+    "FC Final (lpm)",
+    "Recuperación FC 1 min (lpm)",
+    "Recuperación FC 2 min (lpm)",
+    "FC por Periodo (media/máx)",
+    "Lactato Máx (mmol/L)",
+    "RPE Máx",
+    "Umbral 4 mmol (km/h)",
+    "Umbral Dmax (km/h)",
+    "Lactato en Dmax (mmol/L)",
+    "Lactato/RPE por Periodo",
+    "Avisos",
+];
+
+fn optional_number(value: Option<f64>) -> ExportCell {
+    value
+        .map(ExportCell::Number)
+        .unwrap_or_else(|| ExportCell::Text(String::new()))
+}
+
+// Widens an f32 without exposing its binary representation (22.4 would be
+// written as 22.399999618530273)
+pub(crate) fn decimal(value: f32, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value as f64 * factor).round() / factor
+}
+
+impl ExportRow {
+    fn cells(self) -> Vec<ExportCell> {
+        // Percentile and rating of every metric, in the order of NormMetric::ALL
+        let classifications = NormMetric::ALL.iter().flat_map(|metric| {
+            let classification = self.classifications.iter().find(|c| c.metric == *metric);
+            [
+                optional_number(classification.map(|c| c.percentile as f64)),
+                ExportCell::Text(classification.map(|c| c.rating.label()).unwrap_or_default().to_string()),
+            ]
+        });
+        let classifications: Vec<ExportCell> = classifications.collect();
+
+        let mut cells = vec![
+            ExportCell::Number(self.id as f64),
+            optional_number(self.session_id.map(|id| id as f64)),
+            ExportCell::Number(self.athlete_id as f64),
+            ExportCell::Text(self.name),
+            ExportCell::Text(self.completed_periods),
+            ExportCell::Text(format_seconds_to_time(self.total_time)),
+            ExportCell::Text(self.date),
+            ExportCell::Text(self.status.label().to_string()),
+            ExportCell::Text(self.status_reason),
+            ExportCell::Text(self.athlete_observations),
+            ExportCell::Text(self.evaluation_observations),
+            ExportCell::Text(self.observations_author),
+            ExportCell::Number(self.total_distance as f64),
+            optional_number(self.measured_distance.map(f64::from)),
+            ExportCell::Text(if self.distance_flagged { "Sí" } else { "" }.to_string()),
+            ExportCell::Text(format!("{} v{}", self.config.protocol_id, self.config.protocol_version)),
+            optional_number(self.config.recovery_time.map(f64::from)),
+            optional_number(self.config.start_period.map(f64::from)),
+            ExportCell::Number(self.config.track_length as f64),
+            ExportCell::Number(self.config.segments_per_lap as f64),
+            ExportCell::Text(self.birth_date),
+            optional_number(self.athlete_age.map(f64::from)),
+            ExportCell::Text(self.sex.map(|sex| sex.label()).unwrap_or_default().to_string()),
+            ExportCell::Text(self.discipline.map(|d| d.label()).unwrap_or_default().to_string()),
+            ExportCell::Text(self.category),
+            ExportCell::Text(self.club),
+            ExportCell::Text(self.license_number),
+            optional_number(self.measurements.map(|m| decimal(m.weight, 2))),
+            optional_number(self.measurements.map(|m| decimal(m.height, 2))),
+            optional_number(self.measurements.map(|m| decimal(m.bmi(), 1))),
+            optional_number(self.measurements.and_then(|m| m.body_fat).map(|v| decimal(v, 2))),
+            optional_number(self.measurements.and_then(|m| m.leg_length).map(|v| decimal(v, 2))),
+            optional_number(self.aerobic.map(|a| decimal(a.final_speed, 2))),
+            optional_number(self.aerobic.map(|a| decimal(a.mas, 2))),
+            optional_number(self.aerobic.map(|a| decimal(a.vo2max, 1))),
+            ExportCell::Text(self.config.vo2max_equation.label()),
+            ExportCell::Text(self.config.elimination_rules.map(|rules| rules.label()).unwrap_or_default()),
+        ];
+        cells.extend(classifications);
+
+        // Recovery in the order of heart_rate::RECOVERY_TIMES
+        let heart_rate = self.heart_rate.as_ref();
+        let recovery = |after: i32| {
+            heart_rate
+                .and_then(|hr| hr.recovery.iter().find(|r| r.after == after))
+                .map(|r| r.drop as f64)
+        };
+        cells.extend([
+            optional_number(heart_rate.map(|hr| hr.max as f64)),
+            optional_number(heart_rate.map(|hr| decimal(hr.mean, 1))),
+            optional_number(heart_rate.and_then(|hr| hr.end).map(f64::from)),
+            optional_number(recovery(heart_rate::RECOVERY_TIMES[0])),
+            optional_number(recovery(heart_rate::RECOVERY_TIMES[1])),
+            ExportCell::Text(
+                heart_rate
+                    .map(|hr| {
+                        hr.periods
+                            .iter()
+                            .map(|p| format!("P{}: {}/{}", p.period, p.mean, p.max))
+                            .collect::<Vec<_>>()
+                            .join("; ")
+                    })
+                    .unwrap_or_default(),
+            ),
+        ]);
+
+        let lactate = self.lactate.as_ref();
+        let thresholds = lactate.map(|l| l.thresholds);
+        let value = |value: Option<f32>| value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+        cells.extend([
+            optional_number(lactate.and_then(|l| l.peak_lactate).map(|v| decimal(v, 1))),
+            optional_number(lactate.and_then(|l| l.max_rpe).map(|v| decimal(v, 1))),
+            optional_number(thresholds.and_then(|t| t.fixed).map(|v| decimal(v, 2))),
+            optional_number(thresholds.and_then(|t| t.dmax).map(|v| decimal(v, 2))),
+            optional_number(thresholds.and_then(|t| t.dmax_lactate).map(|v| decimal(v, 2))),
+            ExportCell::Text(
+                lactate
+                    .map(|l| {
+                        l.periods
+                            .iter()
+                            .filter(|p| p.lactate.is_some() || p.rpe.is_some())
+                            .map(|p| format!("P{}: {}/{}", p.period, value(p.lactate), value(p.rpe)))
+                            .collect::<Vec<_>>()
+                            .join("; ")
+                    })
+                    .unwrap_or_default(),
+            ),
+        ]);
+
+        cells.push(ExportCell::Text(
+            self.warnings
+                .iter()
+                .map(|w| format!("P{}.{} ({} s)", w.period, w.segment, decimal(w.elapsed, 1)))
+                .collect::<Vec<_>>()
+                .join("; "),
+        ));
+        cells
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ExportFilter {
+    All,
+    Athlete(i64),
+    Session(i64),
+}
+
+fn query_export_rows(conn: &Connection, filter: ExportFilter) -> Result<Vec<ExportRow>> {
+    let (athlete_id, session_id) = match filter {
+        ExportFilter::All => (None, None),
+        ExportFilter::Athlete(id) => (Some(id), None),
+        ExportFilter::Session(id) => (None, Some(id)),
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT ae.id, ae.athlete_id, a.name, et.completed_periods, et.total_time, ae.date, ae.status,
+                ae.status_reason, a.observations, ae.observations, ae.observations_author,
+                et.total_distance, ae.session_id, et.recovery_time, et.start_period,
+                et.protocol_id, et.protocol_version, et.track_length, et.segments_per_lap,
+                et.vo2max_equation, et.elimination_rules, et.measured_distance, et.distance_flagged, a.birth_date,
+                ae.athlete_age, a.sex, a.discipline, a.category, a.club, a.license_number,
+                ae.weight, ae.height, ae.body_fat, ae.leg_length, et.final_speed, et.mas, et.vo2max,
+                a.age
+         FROM athlete_evaluations ae
+         JOIN athletes a ON ae.athlete_id = a.id
+         JOIN evaluation_templates et ON ae.template_id = et.id
+         WHERE (?1 IS NULL OR ae.athlete_id = ?1) AND (?2 IS NULL OR ae.session_id = ?2)
+         ORDER BY ae.date DESC",
+    )?;
+
+    let norms = load_norms(conn)?;
+    let rows = stmt.query_map([athlete_id, session_id], |row| {
+        let mut export_row = ExportRow {
+            id: row.get(0)?,
+            athlete_id: row.get(1)?,
+            name: row.get(2)?,
+            completed_periods: row.get(3)?,
+            total_time: row.get(4)?,
+            date: row.get(5)?,
+            status: row.get(6)?,
+            status_reason: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+            athlete_observations: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+            evaluation_observations: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
+            observations_author: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+            total_distance: row.get(11)?,
+            session_id: row.get(12)?,
+            config: config_from_row(row, 13)?,
+            measured_distance: row.get(21)?,
+            distance_flagged: row.get(22)?,
+            birth_date: row.get::<_, Option<String>>(23)?.unwrap_or_default(),
+            athlete_age: row.get(24)?,
+            sex: row.get(25)?,
+            discipline: row.get(26)?,
+            category: row.get::<_, Option<String>>(27)?.unwrap_or_default(),
+            club: row.get::<_, Option<String>>(28)?.unwrap_or_default(),
+            license_number: row.get::<_, Option<String>>(29)?.unwrap_or_default(),
+            measurements: measurements_from_row(row, 30)?,
+            aerobic: aerobic_from_row(row, 34)?,
+            classifications: Vec::new(),
+            heart_rate: None,
+            lactate: None,
+            warnings: Vec::new(),
+        };
+        // Evaluations saved without an age are classified with the athlete's
+        let age = export_row.athlete_age.map_or_else(|| row.get(37), Ok)?;
+        export_row.classifications = norms::classify(
+            &norms,
+            export_row.sex,
+            export_row.discipline,
+            age,
+            export_row.total_distance,
+            export_row.aerobic,
+        );
+        Ok(export_row)
+    })?;
+    let mut rows = rows.collect::<Result<Vec<_>>>()?;
+
+    // Per-evaluation data is loaded once for the whole export and grouped by
+    // evaluation id, instead of querying it row by row
+    let filter_params = [athlete_id, session_id];
+    let mut period_results = group_by_evaluation(
+        conn,
+        "SELECT pr.id, pr.evaluation_id, pr.period, pr.speed, pr.lap_time, pr.cumulative_distance,
+                pr.recorded_at, pr.completed
+         FROM period_results pr
+         JOIN athlete_evaluations ae ON pr.evaluation_id = ae.id
+         WHERE (?1 IS NULL OR ae.athlete_id = ?1) AND (?2 IS NULL OR ae.session_id = ?2)
+         ORDER BY pr.evaluation_id, pr.period",
+        filter_params,
+        |row| Ok((row.get(1)?, period_result_from_row(row)?)),
+    )?;
+    let mut heart_rate_samples = group_by_evaluation(
+        conn,
+        "SELECT s.evaluation_id, s.elapsed, s.heart_rate
+         FROM heart_rate_samples s
+         JOIN athlete_evaluations ae ON s.evaluation_id = ae.id
+         WHERE (?1 IS NULL OR ae.athlete_id = ?1) AND (?2 IS NULL OR ae.session_id = ?2)
+         ORDER BY s.evaluation_id, s.elapsed",
+        filter_params,
+        |row| Ok((row.get(0)?, HeartRateSample { elapsed: row.get(1)?, heart_rate: row.get(2)? })),
+    )?;
+    let mut measurements = group_by_evaluation(
+        conn,
+        "SELECT pm.evaluation_id, pm.period, pm.lactate, pm.rpe
+         FROM period_measurements pm
+         JOIN athlete_evaluations ae ON pm.evaluation_id = ae.id
+         WHERE (?1 IS NULL OR ae.athlete_id = ?1) AND (?2 IS NULL OR ae.session_id = ?2)
+         ORDER BY pm.evaluation_id, pm.period",
+        filter_params,
+        |row| Ok((row.get(0)?, PeriodMeasurement { period: row.get(1)?, lactate: row.get(2)?, rpe: row.get(3)? })),
+    )?;
+    let mut warnings = group_by_evaluation(
+        conn,
+        "SELECT w.evaluation_id, w.period, w.segment, w.elapsed, w.recorded_at
+         FROM evaluation_warnings w
+         JOIN athlete_evaluations ae ON w.evaluation_id = ae.id
+         WHERE (?1 IS NULL OR ae.athlete_id = ?1) AND (?2 IS NULL OR ae.session_id = ?2)
+         ORDER BY w.evaluation_id, w.period, w.segment",
+        filter_params,
+        |row| {
+            Ok((
+                row.get(0)?,
+                Warning { period: row.get(1)?, segment: row.get(2)?, elapsed: row.get(3)?, recorded_at: row.get(4)? },
+            ))
+        },
+    )?;
+
+    for export_row in &mut rows {
+        let period_results = period_results.remove(&export_row.id).unwrap_or_default();
+        let samples = heart_rate_samples.remove(&export_row.id).unwrap_or_default();
+        export_row.heart_rate = heart_rate::timeline(&period_results, &export_row.date, export_row.total_time)
+            .and_then(|timeline| heart_rate::summarize(&samples, &timeline));
+        export_row.lactate = measurements
+            .remove(&export_row.id)
+            .map(|measurements| lactate::analyze(export_row.id, &period_results, &measurements));
+        export_row.warnings = warnings.remove(&export_row.id).unwrap_or_default();
+    }
+    Ok(rows)
+}
+
+// Runs `sql`, whose rows `map` turns into (evaluation id, value) pairs, and
+// groups the values by evaluation keeping the query's order
+fn group_by_evaluation<T>(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+    mut map: impl FnMut(&Row) -> Result<(i64, T)>,
+) -> Result<HashMap<i64, Vec<T>>> {
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query(params)?;
+    let mut grouped: HashMap<i64, Vec<T>> = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let (evaluation_id, value) = map(row)?;
+        grouped.entry(evaluation_id).or_default().push(value);
+    }
+    Ok(grouped)
+}
+
+fn write_csv<P: AsRef<Path>>(path: P, rows: Vec<ExportRow>) -> Result<(), Box<dyn std::error::Error>> {
+    write_table_csv(path, &EXPORT_HEADERS, rows.into_iter().map(ExportRow::cells))
+}
+
+fn write_xlsx<P: AsRef<Path>>(path: P, rows: Vec<ExportRow>) -> Result<(), Box<dyn std::error::Error>> {
+    write_table_xlsx(path, &EXPORT_HEADERS, rows.into_iter().map(ExportRow::cells))
+}
+
+// Writers shared by every sheet the application exports
+pub(crate) fn write_table_csv<P: AsRef<Path>>(
+    path: P,
+    headers: &[&str],
+    rows: impl IntoIterator<Item = Vec<ExportCell>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut wtr = Writer::from_path(path)?;
+    wtr.write_record(headers)?;
+
+    for row in rows {
+        wtr.write_record(row.iter().map(ToString::to_string))?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+pub(crate) fn write_table_xlsx<P: AsRef<Path>>(
+    path: P,
+    headers: &[&str],
+    rows: impl IntoIterator<Item = Vec<ExportCell>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+
+    let header_format = Format::new()
+        .set_bold()
+        .set_pattern(rust_xlsxwriter::FormatPattern::Solid)
+        .set_background_color(0xD0E0F0);
+
+    // Write headers
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &header_format)?;
+        worksheet.set_column_width(col as u16, 15)?;
+    }
+
+    for (row_num, row) in (1u32..).zip(rows) {
+        for (col, cell) in row.into_iter().enumerate() {
+            match cell {
+                ExportCell::Number(value) => worksheet.write_number(row_num, col as u16, value)?,
+                ExportCell::Text(value) => worksheet.write_string(row_num, col as u16, &value)?,
+            };
+        }
+    }
+
+    workbook.save(path)?;
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Athlete {
     pub id: Option<i64>,
@@ -21,6 +583,14 @@ pub struct Athlete {
     pub weight: f32,
     pub height: f32,
     pub observations: Option<String>,
+    // YYYY-MM-DD. When known, `age` is derived from it.
+    pub birth_date: Option<String>,
+    pub sex: Option<Sex>,
+    pub discipline: Option<Discipline>,
+    pub category: Option<String>,
+    pub club: Option<String>,
+    // Federation licence, unique among athletes
+    pub license_number: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -29,7 +599,83 @@ pub struct EvaluationTemplate {
     pub completed_periods: String,
     pub total_time: i32,
     pub date: String,
+    // Computed from the protocol, the distance measured by the client is
+    // kept for comparison
     pub total_distance: f32,
+    pub measured_distance: Option<f32>,
+    pub distance_flagged: bool,
+    pub config: TestConfiguration,
+    // None when no period was completed
+    pub aerobic: Option<AerobicEstimate>,
+}
+
+// Settings a test was run with. Evaluations saved before they were recorded
+// have no recovery time or start period.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TestConfiguration {
+    pub recovery_time: Option<i32>,
+    pub start_period: Option<i32>,
+    pub protocol_id: String,
+    pub protocol_version: i32,
+    pub track_length: f32,
+    pub segments_per_lap: i32,
+    pub vo2max_equation: Vo2maxEquation,
+    // None for evaluations saved before the rules were recorded
+    pub elimination_rules: Option<EliminationRules>,
+}
+
+impl Default for TestConfiguration {
+    fn default() -> Self {
+        TestConfiguration {
+            recovery_time: None,
+            start_period: None,
+            protocol_id: STANDARD_PROTOCOL_ID.to_string(),
+            protocol_version: 1,
+            track_length: STANDARD_TRACK_LENGTH,
+            segments_per_lap: STANDARD_SEGMENTS_PER_LAP,
+            vo2max_equation: Vo2maxEquation::default(),
+            elimination_rules: None,
+        }
+    }
+}
+
+// Stored as JSON, the linear equation carries its coefficients
+impl ToSql for Vo2maxEquation {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        serde_json::to_string(self)
+            .map(ToSqlOutput::from)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))
+    }
+}
+
+impl FromSql for Vo2maxEquation {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(e.into()))
+    }
+}
+
+impl ToSql for EliminationRules {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        serde_json::to_string(self)
+            .map(ToSqlOutput::from)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))
+    }
+}
+
+impl FromSql for EliminationRules {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(e.into()))
+    }
+}
+
+impl TestConfiguration {
+    pub fn geometry(&self) -> TrackGeometry {
+        TrackGeometry {
+            track_length: self.track_length,
+            segments_per_lap: self.segments_per_lap,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -37,98 +683,702 @@ pub struct AthleteEvaluation {
     pub id: Option<i64>,
     pub athlete_id: i64,
     pub template_id: i64,
-    pub status: String,
+    pub session_id: Option<i64>,
+    pub status: EvaluationStatus,
+    pub status_reason: Option<String>,
     pub date: String,
+    pub observations: Option<String>,
+    pub observations_author: Option<String>,
+    pub observations_updated_at: Option<String>,
+    // Age of the athlete on the evaluation date, set when saving
+    pub athlete_age: Option<i32>,
+    pub measurements: Option<Anthropometrics>,
 }
 
-pub struct Database {
-    pub connection: Mutex<Connection>,
+// Body measurements taken for an evaluation. Weight in kg, height and leg
+// length in cm, body fat in %.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Anthropometrics {
+    pub weight: f32,
+    pub height: f32,
+    pub body_fat: Option<f32>,
+    pub leg_length: Option<f32>,
 }
 
-impl Database {
-    pub fn new() -> Result<Self> {
-        let conn = Connection::open("patinaje.db")?;
+impl Anthropometrics {
+    pub fn bmi(&self) -> f32 {
+        let height = self.height / 100.0;
+        self.weight / (height * height)
+    }
+}
 
-        // Create tables if they don't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS athletes (
-                id INTEGER PRIMARY KEY,
-                name TEXT NOT NULL,
-                age INTEGER NOT NULL CHECK (age > 0 AND age < 150),
-                weight REAL NOT NULL CHECK (weight > 0),
-                height REAL NOT NULL CHECK (height > 0),
-                observations TEXT
-            )",
-            [],
-        )?;
+// Measurements of one evaluation, for the trends over time
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MeasurementRecord {
+    pub evaluation_id: i64,
+    pub date: String,
+    pub measurements: Anthropometrics,
+}
 
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS evaluation_templates (
-                id INTEGER PRIMARY KEY,
-                completed_periods TEXT NOT NULL,
-                total_time INTEGER NOT NULL,
-                date TEXT NOT NULL,
-                total_distance REAL NOT NULL DEFAULT 0
-            )",
-            [],
-        )?;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    pub id: Option<i64>,
+    pub date: String,
+    pub venue: Option<String>,
+    pub surface: Option<String>,
+    pub temperature: Option<f32>,
+    pub humidity: Option<f32>,
+    pub evaluator: Option<String>,
+    pub recovery_time: Option<i32>,
+    pub notes: Option<String>,
+    // Track used by every evaluation of the session, when known
+    pub track_geometry: Option<TrackGeometry>,
+}
 
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS athlete_evaluations (
-                id INTEGER PRIMARY KEY,
-                athlete_id INTEGER NOT NULL,
-                template_id INTEGER NOT NULL,
-                status TEXT NOT NULL CHECK (status IN ('completed', 'cancelled')),
-                date TEXT NOT NULL,
-                FOREIGN KEY (athlete_id) REFERENCES athletes (id),
-                FOREIGN KEY (template_id) REFERENCES evaluation_templates (id)
-            )",
-            [],
-        )?;
+// A version of a user-defined protocol
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StoredProtocol {
+    pub id: Option<i64>,
+    pub code: String,
+    pub version: i32,
+    pub name: String,
+    pub parameters: ProtocolParameters,
+    pub created_at: String,
+}
 
-        let db = Database {
-            connection: Mutex::new(conn),
-        };
+// Test in progress, saved while it runs so that it can be resumed after a
+// crash. `elapsed` is the time of the test clock when `state` was saved.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TestJournal {
+    pub protocol_id: String,
+    pub protocol_version: i32,
+    pub geometry: TrackGeometry,
+    pub start_period: i32,
+    pub recovery_time: i32,
+    pub started_at: String,
+    pub updated_at: String,
+    pub elapsed: f32,
+    // Athletes and configuration as kept by the UI
+    pub state: Option<serde_json::Value>,
+}
 
-        // Migrate old data if necessary
-        if let Err(e) = db.migrate_old_evaluations() {
-            eprintln!("Error migrating old evaluations: {}", e);
-        }
+// An evaluation joined with its template and athlete
+pub type EvaluationRecord = (AthleteEvaluation, EvaluationTemplate, Athlete);
+
+// Column lists and row mappers shared by the evaluation queries. `offset` is
+// the index of the first column of the group in the SELECT.
+const ATHLETE_COLUMNS: &str = "a.id, a.name, a.age, a.weight, a.height, a.observations, a.birth_date,
+     a.sex, a.discipline, a.category, a.club, a.license_number";
+
+// The stored age is only used when the birth date is unknown
+fn athlete_from_row(row: &Row, offset: usize) -> Result<Athlete> {
+    let birth_date: Option<String> = row.get(offset + 6)?;
+    let age = birth_date
+        .as_deref()
+        .and_then(|birth_date| age_on(birth_date, Local::now().date_naive()));
+    Ok(Athlete {
+        id: Some(row.get(offset)?),
+        name: row.get(offset + 1)?,
+        age: age.map_or_else(|| row.get(offset + 2), Ok)?,
+        weight: row.get(offset + 3)?,
+        height: row.get(offset + 4)?,
+        observations: row.get(offset + 5)?,
+        birth_date,
+        sex: row.get(offset + 7)?,
+        discipline: row.get(offset + 8)?,
+        category: row.get(offset + 9)?,
+        club: row.get(offset + 10)?,
+        license_number: row.get(offset + 11)?,
+    })
+}
+
+const SESSION_COLUMNS: &str = "s.id, s.date, s.venue, s.surface, s.temperature, s.humidity,
+     s.evaluator, s.recovery_time, s.notes, s.track_length, s.segments_per_lap";
+const SESSION_COLUMN_COUNT: usize = 11;
+
+fn session_from_row(row: &Row, offset: usize) -> Result<Session> {
+    Ok(Session {
+        id: Some(row.get(offset)?),
+        date: row.get(offset + 1)?,
+        venue: row.get(offset + 2)?,
+        surface: row.get(offset + 3)?,
+        temperature: row.get(offset + 4)?,
+        humidity: row.get(offset + 5)?,
+        evaluator: row.get(offset + 6)?,
+        recovery_time: row.get(offset + 7)?,
+        notes: row.get(offset + 8)?,
+        track_geometry: row
+            .get::<_, Option<f32>>(offset + 9)?
+            .zip(row.get::<_, Option<i32>>(offset + 10)?)
+            .map(|(track_length, segments_per_lap)| TrackGeometry { track_length, segments_per_lap }),
+    })
+}
+
+const TEMPLATE_COLUMNS: &str = "et.id, et.completed_periods, et.total_time, et.date, et.total_distance,
+     et.recovery_time, et.start_period, et.protocol_id, et.protocol_version, et.track_length,
+     et.segments_per_lap, et.vo2max_equation, et.elimination_rules, et.measured_distance,
+     et.distance_flagged, et.final_speed, et.mas, et.vo2max";
+const TEMPLATE_COLUMN_COUNT: usize = 18;
+
+fn config_from_row(row: &Row, offset: usize) -> Result<TestConfiguration> {
+    Ok(TestConfiguration {
+        recovery_time: row.get(offset)?,
+        start_period: row.get(offset + 1)?,
+        protocol_id: row.get(offset + 2)?,
+        protocol_version: row.get(offset + 3)?,
+        track_length: row.get(offset + 4)?,
+        segments_per_lap: row.get(offset + 5)?,
+        vo2max_equation: row.get(offset + 6)?,
+        elimination_rules: row.get(offset + 7)?,
+    })
+}
+
+fn aerobic_from_row(row: &Row, offset: usize) -> Result<Option<AerobicEstimate>> {
+    let final_speed: Option<f32> = row.get(offset)?;
+    let mas: Option<f32> = row.get(offset + 1)?;
+    let vo2max: Option<f32> = row.get(offset + 2)?;
+    Ok(final_speed
+        .zip(mas)
+        .zip(vo2max)
+        .map(|((final_speed, mas), vo2max)| AerobicEstimate { final_speed, mas, vo2max }))
+}
+
+fn template_from_row(row: &Row, offset: usize) -> Result<EvaluationTemplate> {
+    Ok(EvaluationTemplate {
+        id: Some(row.get(offset)?),
+        completed_periods: row.get(offset + 1)?,
+        total_time: row.get(offset + 2)?,
+        date: row.get(offset + 3)?,
+        total_distance: row.get(offset + 4)?,
+        config: config_from_row(row, offset + 5)?,
+        measured_distance: row.get(offset + 13)?,
+        distance_flagged: row.get(offset + 14)?,
+        aerobic: aerobic_from_row(row, offset + 15)?,
+    })
+}
+
+const PROTOCOL_COLUMNS: &str = "p.id, p.code, p.version, p.name, p.start_period, p.period_count, p.start_speed,
+     p.speed_increment, p.laps_per_period, p.track_length, p.segments_per_lap, p.created_at";
+
+fn protocol_from_row(row: &Row, offset: usize) -> Result<StoredProtocol> {
+    Ok(StoredProtocol {
+        id: Some(row.get(offset)?),
+        code: row.get(offset + 1)?,
+        version: row.get(offset + 2)?,
+        name: row.get(offset + 3)?,
+        parameters: ProtocolParameters {
+            start_period: row.get(offset + 4)?,
+            period_count: row.get(offset + 5)?,
+            start_speed: row.get(offset + 6)?,
+            speed_increment: row.get(offset + 7)?,
+            laps_per_period: row.get(offset + 8)?,
+            track_length: row.get(offset + 9)?,
+            segments_per_lap: row.get(offset + 10)?,
+        },
+        created_at: row.get(offset + 11)?,
+    })
+}
+
+const EVALUATION_COLUMNS: &str = "ae.id, ae.athlete_id, ae.template_id, ae.status, ae.status_reason, ae.date,
+     ae.observations, ae.observations_author, ae.observations_updated_at, ae.session_id,
+     ae.athlete_age, ae.weight, ae.height, ae.body_fat, ae.leg_length";
+const EVALUATION_COLUMN_COUNT: usize = 15;
+
+// Weight and height are only missing when the athlete of a legacy evaluation
+// was gone when they were copied to the evaluations
+fn measurements_from_row(row: &Row, offset: usize) -> Result<Option<Anthropometrics>> {
+    let weight: Option<f32> = row.get(offset)?;
+    let height: Option<f32> = row.get(offset + 1)?;
+    let body_fat = row.get(offset + 2)?;
+    let leg_length = row.get(offset + 3)?;
+    Ok(weight.zip(height).map(|(weight, height)| Anthropometrics {
+        weight,
+        height,
+        body_fat,
+        leg_length,
+    }))
+}
+
+fn evaluation_from_row(row: &Row, offset: usize) -> Result<AthleteEvaluation> {
+    Ok(AthleteEvaluation {
+        id: Some(row.get(offset)?),
+        athlete_id: row.get(offset + 1)?,
+        template_id: row.get(offset + 2)?,
+        status: row.get(offset + 3)?,
+        status_reason: row.get(offset + 4)?,
+        date: row.get(offset + 5)?,
+        observations: row.get(offset + 6)?,
+        observations_author: row.get(offset + 7)?,
+        observations_updated_at: row.get(offset + 8)?,
+        session_id: row.get(offset + 9)?,
+        athlete_age: row.get(offset + 10)?,
+        measurements: measurements_from_row(row, offset + 11)?,
+    })
+}
 
-        // Run migrations
-        db.migrate()?;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeriodResult {
+    pub id: Option<i64>,
+    pub evaluation_id: i64,
+    pub period: i32,
+    pub speed: f32,
+    pub lap_time: f32,
+    pub cumulative_distance: f32,
+    pub recorded_at: String,
+    pub completed: bool,
+}
 
-        Ok(db)
+pub fn save_period_results(
+    conn: &Connection,
+    evaluation_id: i64,
+    period_results: &[PeriodResult],
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO period_results
+            (evaluation_id, period, speed, lap_time, cumulative_distance, recorded_at, completed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for result in period_results {
+        stmt.execute(params![
+            evaluation_id,
+            result.period,
+            result.speed,
+            result.lap_time,
+            result.cumulative_distance,
+            result.recorded_at,
+            result.completed,
+        ])?;
     }
+    Ok(())
+}
 
-    fn migrate(&self) -> Result<()> {
-        let conn = self.connection.lock().unwrap();
-        
-        // Add observations column to athletes table if it doesn't exist
-        let columns = conn.query_row(
-            "SELECT sql FROM sqlite_master WHERE type='table' AND name='athletes'",
-            [],
-            |row| row.get::<_, String>(0),
-        )?;
-        
-        if !columns.to_lowercase().contains("observations") {
-            conn.execute("ALTER TABLE athletes ADD COLUMN observations TEXT", [])?;
-        }
+pub fn get_period_results(conn: &Connection, evaluation_id: i64) -> Result<Vec<PeriodResult>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, evaluation_id, period, speed, lap_time, cumulative_distance, recorded_at, completed
+         FROM period_results
+         WHERE evaluation_id = ?1
+         ORDER BY period",
+    )?;
+    let rows = stmt.query_map([evaluation_id], period_result_from_row)?;
+    rows.collect()
+}
 
-        // Add total_distance column to evaluation_templates table if it doesn't exist
-        let columns = conn.query_row(
-            "SELECT sql FROM sqlite_master WHERE type='table' AND name='evaluation_templates'",
-            [],
-            |row| row.get::<_, String>(0),
-        )?;
-        
-        if !columns.to_lowercase().contains("total_distance") {
-            conn.execute(
-                "ALTER TABLE evaluation_templates ADD COLUMN total_distance REAL NOT NULL DEFAULT 0",
-                [],
+fn period_result_from_row(row: &Row) -> Result<PeriodResult> {
+    Ok(PeriodResult {
+        id: Some(row.get(0)?),
+        evaluation_id: row.get(1)?,
+        period: row.get(2)?,
+        speed: row.get(3)?,
+        lap_time: row.get(4)?,
+        cumulative_distance: row.get(5)?,
+        recorded_at: row.get(6)?,
+        completed: row.get(7)?,
+    })
+}
+
+// Returns the id of the stored athlete, refreshing its anthropometric data.
+// Athletes are matched by `id` when present, then by licence number, and
+// otherwise by name (case and surrounding whitespace are ignored). A new row
+// is only inserted when no match is found.
+pub fn upsert_athlete(conn: &Connection, athlete: &Athlete) -> Result<i64> {
+    // A licence identifies the athlete on its own; without one, the name only
+    // matches athletes that have no licence either
+    let existing_id = match (athlete.id, &athlete.license_number) {
+        (Some(id), _) => Some(id),
+        (None, Some(license_number)) => conn
+            .query_row(
+                "SELECT id FROM athletes WHERE license_number = ?1",
+                [license_number],
+                |row| row.get(0),
+            )
+            .optional()?,
+        (None, None) => find_unlicensed_athlete(conn, &athlete.name, None)?,
+    };
+
+    // Empty observations must not clobber the notes already stored
+    let observations = athlete
+        .observations
+        .as_deref()
+        .filter(|obs| !obs.trim().is_empty());
+
+    // Likewise, profile fields left empty keep the stored values
+    match existing_id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE athletes
+                 SET name = ?1, age = ?2, weight = ?3, height = ?4,
+                     observations = COALESCE(?5, observations),
+                     birth_date = COALESCE(?6, birth_date), sex = COALESCE(?7, sex),
+                     discipline = COALESCE(?8, discipline), category = COALESCE(?9, category),
+                     club = COALESCE(?10, club), license_number = COALESCE(?11, license_number)
+                 WHERE id = ?12",
+                params![
+                    athlete.name.trim(),
+                    athlete.age,
+                    athlete.weight,
+                    athlete.height,
+                    observations,
+                    athlete.birth_date,
+                    athlete.sex,
+                    athlete.discipline,
+                    athlete.category,
+                    athlete.club,
+                    athlete.license_number,
+                    id,
+                ],
             )?;
+            if updated == 0 {
+                return Err(rusqlite::Error::QueryReturnedNoRows);
+            }
+            Ok(id)
+        }
+        None => insert_athlete(
+            conn,
+            &Athlete {
+                observations: observations.map(str::to_string),
+                ..athlete.clone()
+            },
+        ),
+    }
+}
+
+// SQLite's LOWER only folds ASCII, so names are compared here
+fn find_unlicensed_athlete(conn: &Connection, name: &str, exclude_id: Option<i64>) -> Result<Option<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name FROM athletes
+         WHERE license_number IS NULL AND id != COALESCE(?1, -1)
+         ORDER BY id",
+    )?;
+    let athletes = stmt.query_map([exclude_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+    let name = name.trim().to_lowercase();
+    for athlete in athletes {
+        let (id, other) = athlete?;
+        if other.trim().to_lowercase() == name {
+            return Ok(Some(id));
+        }
+    }
+    Ok(None)
+}
+
+fn insert_athlete(conn: &Connection, athlete: &Athlete) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO athletes
+            (name, age, weight, height, observations, birth_date, sex, discipline, category,
+             club, license_number)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            athlete.name.trim(),
+            athlete.age,
+            athlete.weight,
+            athlete.height,
+            athlete.observations,
+            athlete.birth_date,
+            athlete.sex,
+            athlete.discipline,
+            athlete.category,
+            athlete.club,
+            athlete.license_number,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AthleteSortField {
+    #[default]
+    Name,
+    Age,
+    Weight,
+    Height,
+    Id,
+}
+
+impl AthleteSortField {
+    fn column(&self) -> &'static str {
+        match self {
+            AthleteSortField::Name => "a.name COLLATE NOCASE",
+            AthleteSortField::Age => "a.age",
+            AthleteSortField::Weight => "a.weight",
+            AthleteSortField::Height => "a.height",
+            AthleteSortField::Id => "a.id",
         }
+    }
+}
+
+// Selects the evaluations of a group of athletes. Every criterion is
+// optional; dates are inclusive and compared as YYYY-MM-DD. Category, club,
+// sex and discipline are the athlete's current ones, not those they had on
+// the date of each evaluation.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GroupFilter {
+    pub session_id: Option<i64>,
+    pub category: Option<String>,
+    pub club: Option<String>,
+    pub sex: Option<Sex>,
+    pub discipline: Option<Discipline>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+// Saves one evaluation with its template, period results and warnings,
+// reusing the athlete if it already exists. Returns the athlete, template and
+// evaluation ids.
+pub fn insert_evaluation_data(
+    conn: &Connection,
+    athlete: &Athlete,
+    template: &EvaluationTemplate,
+    athlete_evaluation: &AthleteEvaluation,
+    period_results: &[PeriodResult],
+    warnings: &[Warning],
+) -> Result<(i64, i64, i64)> {
+    // Reuse the athlete if it already exists, otherwise create it
+    let athlete_id = upsert_athlete(conn, athlete)?;
+
+    // Save evaluation template
+    conn.execute(
+        "INSERT INTO evaluation_templates
+            (completed_periods, total_time, date, total_distance, recovery_time, start_period,
+             protocol_id, protocol_version, track_length, segments_per_lap, vo2max_equation,
+             elimination_rules, measured_distance, distance_flagged, final_speed, mas, vo2max)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            template.completed_periods,
+            template.total_time,
+            template.date,
+            template.total_distance,
+            template.config.recovery_time,
+            template.config.start_period,
+            template.config.protocol_id,
+            template.config.protocol_version,
+            template.config.track_length,
+            template.config.segments_per_lap,
+            template.config.vo2max_equation,
+            template.config.elimination_rules,
+            template.measured_distance,
+            template.distance_flagged,
+            template.aerobic.map(|a| a.final_speed),
+            template.aerobic.map(|a| a.mas),
+            template.aerobic.map(|a| a.vo2max),
+        ],
+    )?;
+
+    let template_id = conn.last_insert_rowid();
+
+    // Validate date format
+    if NaiveDateTime::parse_from_str(&athlete_evaluation.date, "%Y-%m-%dT%H:%M:%S%.f%z").is_err() {
+        return Err(rusqlite::Error::InvalidParameterName(
+            "Invalid date format".into(),
+        ));
+    }
+
+    // Measurements not taken for this evaluation are the athlete's current ones
+    let measurements = athlete_evaluation.measurements.unwrap_or(Anthropometrics {
+        weight: athlete.weight,
+        height: athlete.height,
+        body_fat: None,
+        leg_length: None,
+    });
+
+    // The age at the evaluation date, from the stored birth date when known
+    let (birth_date, stored_age): (Option<String>, i32) = conn.query_row(
+        "SELECT birth_date, age FROM athletes WHERE id = ?1",
+        [athlete_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let evaluation_day = DateTime::parse_from_rfc3339(&athlete_evaluation.date)
+        .map(|date| date.date_naive())
+        .ok();
+    let athlete_age = birth_date
+        .as_deref()
+        .zip(evaluation_day)
+        .and_then(|(birth_date, day)| age_on(birth_date, day))
+        .unwrap_or(stored_age);
+
+    // Save athlete evaluation with the new athlete_id and template_id
+    conn.execute(
+        "INSERT INTO athlete_evaluations
+            (athlete_id, template_id, session_id, status, status_reason, date,
+             observations, observations_author, observations_updated_at, athlete_age,
+             weight, height, body_fat, leg_length)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            athlete_id,
+            template_id,
+            athlete_evaluation.session_id,
+            athlete_evaluation.status,
+            athlete_evaluation.status_reason,
+            athlete_evaluation.date,
+            athlete_evaluation.observations,
+            athlete_evaluation.observations_author,
+            athlete_evaluation.observations_updated_at,
+            athlete_age,
+            measurements.weight,
+            measurements.height,
+            measurements.body_fat,
+            measurements.leg_length,
+        ],
+    )?;
+
+    let eval_id = conn.last_insert_rowid();
+
+    save_period_results(conn, eval_id, period_results)?;
+    save_warnings(conn, eval_id, warnings)?;
+
+    Ok((athlete_id, template_id, eval_id))
+}
+
+const NORM_COLUMNS: &str = "n.id, n.metric, n.sex, n.discipline, n.age_min, n.age_max, n.p10, n.p25,
+     n.p50, n.p75, n.p90, n.source";
+
+fn norm_from_row(row: &Row, offset: usize) -> Result<Norm> {
+    Ok(Norm {
+        id: Some(row.get(offset)?),
+        metric: row.get(offset + 1)?,
+        sex: row.get(offset + 2)?,
+        discipline: row.get(offset + 3)?,
+        age_min: row.get(offset + 4)?,
+        age_max: row.get(offset + 5)?,
+        p10: row.get(offset + 6)?,
+        p25: row.get(offset + 7)?,
+        p50: row.get(offset + 8)?,
+        p75: row.get(offset + 9)?,
+        p90: row.get(offset + 10)?,
+        source: row.get(offset + 11)?,
+    })
+}
+
+pub fn load_norms(conn: &Connection) -> Result<Vec<Norm>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM norms n ORDER BY n.metric, n.age_min, n.age_max, n.id",
+        NORM_COLUMNS,
+    ))?;
+    let rows = stmt.query_map([], |row| norm_from_row(row, 0))?;
+    rows.collect()
+}
+
+pub fn load_heart_rate(conn: &Connection, evaluation_id: i64) -> Result<Option<HeartRateRecording>> {
+    let recording = conn
+        .query_row(
+            "SELECT evaluation_id, source, format, start_offset, imported_at
+             FROM heart_rate_recordings
+             WHERE evaluation_id = ?1",
+            [evaluation_id],
+            |row| {
+                Ok(HeartRateRecording {
+                    evaluation_id: row.get(0)?,
+                    source: row.get(1)?,
+                    format: row.get(2)?,
+                    start_offset: row.get(3)?,
+                    imported_at: row.get(4)?,
+                    samples: Vec::new(),
+                })
+            },
+        )
+        .optional()?;
+    let Some(mut recording) = recording else {
+        return Ok(None);
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT elapsed, heart_rate FROM heart_rate_samples
+         WHERE evaluation_id = ?1
+         ORDER BY elapsed",
+    )?;
+    let samples = stmt.query_map([evaluation_id], |row| {
+        Ok(HeartRateSample { elapsed: row.get(0)?, heart_rate: row.get(1)? })
+    })?;
+    recording.samples = samples.collect::<Result<_>>()?;
+    Ok(Some(recording))
+}
+
+// When the periods of an evaluation were skated, None when its times
+// cannot be parsed
+pub fn load_timeline(conn: &Connection, evaluation_id: i64) -> Result<Option<Timeline>> {
+    let (date, total_time): (String, i32) = conn.query_row(
+        "SELECT ae.date, et.total_time
+         FROM athlete_evaluations ae
+         JOIN evaluation_templates et ON ae.template_id = et.id
+         WHERE ae.id = ?1",
+        [evaluation_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let period_results = get_period_results(conn, evaluation_id)?;
+    Ok(heart_rate::timeline(&period_results, &date, total_time))
+}
+
+pub fn load_period_measurements(conn: &Connection, evaluation_id: i64) -> Result<Vec<PeriodMeasurement>> {
+    let mut stmt = conn.prepare(
+        "SELECT period, lactate, rpe FROM period_measurements
+         WHERE evaluation_id = ?1
+         ORDER BY period",
+    )?;
+    let rows = stmt.query_map([evaluation_id], |row| {
+        Ok(PeriodMeasurement { period: row.get(0)?, lactate: row.get(1)?, rpe: row.get(2)? })
+    })?;
+    rows.collect()
+}
+
+fn save_warnings(conn: &Connection, evaluation_id: i64, warnings: &[Warning]) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO evaluation_warnings (evaluation_id, period, segment, elapsed, recorded_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for warning in warnings {
+        stmt.execute(params![
+            evaluation_id,
+            warning.period,
+            warning.segment,
+            warning.elapsed,
+            warning.recorded_at,
+        ])?;
+    }
+    Ok(())
+}
+
+pub fn load_warnings(conn: &Connection, evaluation_id: i64) -> Result<Vec<Warning>> {
+    let mut stmt = conn.prepare(
+        "SELECT period, segment, elapsed, recorded_at FROM evaluation_warnings
+         WHERE evaluation_id = ?1
+         ORDER BY period, segment",
+    )?;
+    let rows = stmt.query_map([evaluation_id], |row| {
+        Ok(Warning {
+            period: row.get(0)?,
+            segment: row.get(1)?,
+            elapsed: row.get(2)?,
+            recorded_at: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub struct Database {
+    pub connection: Mutex<Connection>,
+    path: PathBuf,
+}
+
+impl Database {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut conn = Connection::open(path.as_ref())?;
+        migrations::run(&mut conn)?;
 
+        Ok(Database {
+            connection: Mutex::new(conn),
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Writes a consistent copy of the database to `path`. The connection
+    // stays locked meanwhile, so no write of the app is left out.
+    pub fn copy_to(&self, path: &Path) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])?;
         Ok(())
     }
 
@@ -137,457 +1387,847 @@ impl Database {
         athlete: &Athlete,
         template: &EvaluationTemplate,
         athlete_evaluation: &AthleteEvaluation,
+        period_results: &[PeriodResult],
+        warnings: &[Warning],
     ) -> Result<(i64, i64, i64)> {
         let mut conn = self.connection.lock().unwrap();
         let tx = conn.transaction()?;
 
-        // Save athlete
-        tx.execute(
-            "INSERT INTO athletes (name, age, weight, height, observations) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                athlete.name,
-                athlete.age,
-                athlete.weight,
-                athlete.height,
-                athlete.observations,
-            ],
-        )?;
-
-        let athlete_id = tx.last_insert_rowid();
-
-        // Save evaluation template
-        tx.execute(
-            "INSERT INTO evaluation_templates (completed_periods, total_time, date, total_distance) 
-             VALUES (?1, ?2, ?3, ?4)",
-            [
-                &template.completed_periods,
-                &template.total_time.to_string(),
-                &template.date,
-                &template.total_distance.to_string(),
-            ],
-        )?;
-
-        let template_id = tx.last_insert_rowid();
-
-        // Validate date format
-        if NaiveDateTime::parse_from_str(&athlete_evaluation.date, "%Y-%m-%dT%H:%M:%S%.f%z").is_err() {
-            return Err(rusqlite::Error::InvalidParameterName(
-                "Invalid date format".into(),
-            ));
-        }
-
-        // Save athlete evaluation with the new athlete_id and template_id
-        tx.execute(
-            "INSERT INTO athlete_evaluations (athlete_id, template_id, status, date) 
-             VALUES (?1, ?2, ?3, ?4)",
-            [
-                &athlete_id.to_string(),
-                &template_id.to_string(),
-                &athlete_evaluation.status,
-                &athlete_evaluation.date,
-            ],
-        )?;
-
-        let eval_id = tx.last_insert_rowid();
+        let ids = insert_evaluation_data(&tx, athlete, template, athlete_evaluation, period_results, warnings)?;
 
         // Commit the transaction
         tx.commit()?;
 
-        Ok((athlete_id, template_id, eval_id))
+        Ok(ids)
     }
 
-    pub fn get_athlete_evaluations(&self, athlete_id: i64) -> Result<Vec<(AthleteEvaluation, EvaluationTemplate)>> {
+    pub fn get_athlete_evaluations(
+        &self,
+        athlete_id: i64,
+    ) -> Result<Vec<(AthleteEvaluation, EvaluationTemplate, Vec<PeriodResult>)>> {
         let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT ae.id, ae.athlete_id, ae.template_id, ae.status, ae.date,
-                    et.id, et.completed_periods, et.total_time, et.date
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {}
              FROM athlete_evaluations ae
              JOIN evaluation_templates et ON ae.template_id = et.id
-             WHERE ae.athlete_id = ?1 
+             WHERE ae.athlete_id = ?1
              ORDER BY ae.date DESC",
-        )?;
+            EVALUATION_COLUMNS, TEMPLATE_COLUMNS,
+        ))?;
 
         let evals = stmt.query_map([athlete_id], |row| {
             Ok((
-                AthleteEvaluation {
-                    id: Some(row.get(0)?),
-                    athlete_id: row.get(1)?,
-                    template_id: row.get(2)?,
-                    status: row.get(3)?,
-                    date: row.get(4)?,
-                },
-                EvaluationTemplate {
-                    id: Some(row.get(5)?),
-                    completed_periods: row.get(6)?,
-                    total_time: row.get(7)?,
-                    date: row.get(8)?,
-                    total_distance: row.get(9)?,
-                }
+                evaluation_from_row(row, 0)?,
+                template_from_row(row, EVALUATION_COLUMN_COUNT)?,
             ))
         })?;
 
-        evals.collect()
+        evals
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .map(|(eval, template)| {
+                let period_results = get_period_results(&conn, eval.id.unwrap_or_default())?;
+                Ok((eval, template, period_results))
+            })
+            .collect()
     }
 
     pub fn export_all_evaluations_to_csv<P: AsRef<Path>>(
         &self,
         path: P,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let rows = query_export_rows(&self.connection.lock().unwrap(), ExportFilter::All)?;
+        write_csv(path, rows)
+    }
+
+    pub fn export_athlete_evaluations_to_csv<P: AsRef<Path>>(
+        &self,
+        athlete_id: i64,
+        path: P,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rows = query_export_rows(&self.connection.lock().unwrap(), ExportFilter::Athlete(athlete_id))?;
+        write_csv(path, rows)
+    }
+
+    pub fn get_all_evaluations(&self) -> Result<Vec<EvaluationRecord>> {
         let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT ae.id, ae.athlete_id, a.name, et.completed_periods, et.total_time, ae.date, ae.status, 
-                    a.observations, et.total_distance
-             FROM athlete_evaluations ae 
-             JOIN athletes a ON ae.athlete_id = a.id 
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {}, {}
+             FROM athlete_evaluations ae
              JOIN evaluation_templates et ON ae.template_id = et.id
-             ORDER BY ae.date DESC"
-        )?;
-
-        let mut wtr = Writer::from_path(path)?;
-        wtr.write_record(&[
-            "ID",
-            "Atleta ID",
-            "Nombre del Atleta",
-            "Periodos Completados",
-            "Tiempo Total",
-            "Fecha",
-            "Estado",
-            "Observaciones",
-            "Distancia Total (m)",
-        ])?;
+             JOIN athletes a ON ae.athlete_id = a.id
+             ORDER BY ae.date DESC",
+            EVALUATION_COLUMNS, TEMPLATE_COLUMNS, ATHLETE_COLUMNS,
+        ))?;
 
         let rows = stmt.query_map([], |row| {
             Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, i32>(4)?,
-                row.get::<_, String>(5)?,
-                row.get::<_, String>(6)?,
-                row.get::<_, Option<String>>(7)?.unwrap_or_default(),
-                row.get::<_, f32>(8)?,
+                evaluation_from_row(row, 0)?,
+                template_from_row(row, EVALUATION_COLUMN_COUNT)?,
+                athlete_from_row(row, EVALUATION_COLUMN_COUNT + TEMPLATE_COLUMN_COUNT)?,
             ))
         })?;
 
-        for row in rows {
-            let (id, athlete_id, name, periods, time, date, status, observations, total_distance) = row?;
-            wtr.write_record(&[
-                id.to_string(),
-                athlete_id.to_string(),
-                name,
-                periods,
-                format_seconds_to_time(time),
-                date,
-                status,
-                observations,
-                total_distance.to_string(),
-            ])?;
-        }
-
-        wtr.flush()?;
-        Ok(())
+        rows.collect()
     }
 
-    pub fn export_athlete_evaluations_to_csv<P: AsRef<Path>>(
+    // Measurements of the athlete's evaluations, oldest first, optionally
+    // restricted to the evaluations between `from` and `to` (YYYY-MM-DD,
+    // inclusive). Dates are compared as recorded, in local time.
+    pub fn get_measurement_history(
         &self,
         athlete_id: i64,
-        path: P,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+        from: Option<&str>,
+        to: Option<&str>,
+    ) -> Result<Vec<MeasurementRecord>> {
         let conn = self.connection.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT ae.id, ae.athlete_id, a.name, et.completed_periods, et.total_time, ae.date, ae.status,
-                    a.observations, et.total_distance
-             FROM athlete_evaluations ae 
-             JOIN athletes a ON ae.athlete_id = a.id 
-             JOIN evaluation_templates et ON ae.template_id = et.id
+            "SELECT ae.id, ae.date, ae.weight, ae.height, ae.body_fat, ae.leg_length
+             FROM athlete_evaluations ae
              WHERE ae.athlete_id = ?1
-             ORDER BY ae.date DESC"
+               AND ae.weight IS NOT NULL AND ae.height IS NOT NULL
+               AND (?2 IS NULL OR SUBSTR(ae.date, 1, 10) >= ?2)
+               AND (?3 IS NULL OR SUBSTR(ae.date, 1, 10) <= ?3)
+             ORDER BY ae.date, ae.id",
         )?;
+        let rows = stmt.query_map(params![athlete_id, from, to], |row| {
+            Ok(MeasurementRecord {
+                evaluation_id: row.get(0)?,
+                date: row.get(1)?,
+                measurements: Anthropometrics {
+                    weight: row.get(2)?,
+                    height: row.get(3)?,
+                    body_fat: row.get(4)?,
+                    leg_length: row.get(5)?,
+                },
+            })
+        })?;
+        rows.collect()
+    }
 
-        let mut wtr = Writer::from_path(path)?;
-        wtr.write_record(&[
-            "ID",
-            "Atleta ID",
-            "Nombre del Atleta",
-            "Periodos Completados",
-            "Tiempo Total",
-            "Fecha",
-            "Estado",
-            "Observaciones",
-            "Distancia Total (m)",
-        ])?;
-
+    // Results of the valid tests of an athlete, oldest first
+    pub fn get_test_results(&self, athlete_id: i64) -> Result<Vec<TestResult>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT ae.id, ae.date, ae.status, et.total_distance, et.final_speed
+             FROM athlete_evaluations ae
+             JOIN evaluation_templates et ON ae.template_id = et.id
+             WHERE ae.athlete_id = ?1
+             ORDER BY ae.date, ae.id",
+        )?;
         let rows = stmt.query_map([athlete_id], |row| {
+            let status: EvaluationStatus = row.get(2)?;
             Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, i32>(4)?,
-                row.get::<_, String>(5)?,
-                row.get::<_, String>(6)?,
-                row.get::<_, Option<String>>(7)?.unwrap_or_default(),
-                row.get::<_, f32>(8)?,
+                status,
+                TestResult {
+                    evaluation_id: row.get(0)?,
+                    date: row.get(1)?,
+                    total_distance: row.get(3)?,
+                    final_speed: row.get(4)?,
+                },
             ))
         })?;
+        Ok(rows
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|(status, _)| status.is_valid_result())
+            .map(|(_, result)| result)
+            .collect())
+    }
 
-        for row in rows {
-            let (id, athlete_id, name, periods, time, date, status, observations, total_distance) = row?;
-            wtr.write_record(&[
-                id.to_string(),
-                athlete_id.to_string(),
-                name,
-                periods,
-                format_seconds_to_time(time),
-                date,
-                status,
-                observations,
-                total_distance.to_string(),
-            ])?;
+    // Last valid evaluation of an athlete with an aerobic estimate
+    pub fn get_latest_test(&self, athlete_id: i64) -> Result<Option<EvaluationRecord>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {}, {}
+             FROM athlete_evaluations ae
+             JOIN evaluation_templates et ON ae.template_id = et.id
+             JOIN athletes a ON ae.athlete_id = a.id
+             WHERE ae.athlete_id = ?1 AND et.mas IS NOT NULL
+             ORDER BY ae.date DESC, ae.id DESC",
+            EVALUATION_COLUMNS, TEMPLATE_COLUMNS, ATHLETE_COLUMNS,
+        ))?;
+        let rows = stmt.query_map([athlete_id], |row| {
+            Ok((
+                evaluation_from_row(row, 0)?,
+                template_from_row(row, EVALUATION_COLUMN_COUNT)?,
+                athlete_from_row(row, EVALUATION_COLUMN_COUNT + TEMPLATE_COLUMN_COUNT)?,
+            ))
+        })?;
+        for record in rows {
+            let record = record?;
+            if record.0.status.is_valid_result() {
+                return Ok(Some(record));
+            }
         }
+        Ok(None)
+    }
 
-        wtr.flush()?;
+    pub fn update_evaluation_observations(
+        &self,
+        evaluation_id: i64,
+        observations: Option<String>,
+        author: Option<String>,
+        updated_at: &str,
+    ) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE athlete_evaluations
+             SET observations = ?1, observations_author = ?2, observations_updated_at = ?3
+             WHERE id = ?4",
+            params![observations, author, updated_at, evaluation_id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
         Ok(())
     }
 
-    pub fn get_all_evaluations(&self) -> Result<Vec<(AthleteEvaluation, EvaluationTemplate, Athlete)>> {
+    pub fn get_evaluation_period_results(
+        &self,
+        evaluation_id: i64,
+    ) -> Result<(EvaluationTemplate, Vec<PeriodResult>)> {
         let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT ae.id, ae.athlete_id, ae.template_id, ae.status, ae.date,
-                    et.id, et.completed_periods, et.total_time, et.date, et.total_distance,
-                    a.id, a.name, a.age, a.weight, a.height, a.observations
-             FROM athlete_evaluations ae 
-             JOIN evaluation_templates et ON ae.template_id = et.id
-             JOIN athletes a ON ae.athlete_id = a.id 
-             ORDER BY ae.date DESC"
+        let template = conn.query_row(
+            &format!(
+                "SELECT {}
+                 FROM athlete_evaluations ae
+                 JOIN evaluation_templates et ON ae.template_id = et.id
+                 WHERE ae.id = ?1",
+                TEMPLATE_COLUMNS,
+            ),
+            [evaluation_id],
+            |row| template_from_row(row, 0),
         )?;
+        let period_results = get_period_results(&conn, evaluation_id)?;
+        Ok((template, period_results))
+    }
 
-        let rows = stmt.query_map([], |row| {
-            Ok((
-                AthleteEvaluation {
-                    id: Some(row.get(0)?),
-                    athlete_id: row.get(1)?,
-                    template_id: row.get(2)?,
-                    status: row.get(3)?,
-                    date: row.get(4)?,
-                },
-                EvaluationTemplate {
-                    id: Some(row.get(5)?),
-                    completed_periods: row.get(6)?,
-                    total_time: row.get(7)?,
-                    date: row.get(8)?,
-                    total_distance: row.get(9)?,
-                },
-                Athlete {
-                    id: Some(row.get(10)?),
-                    name: row.get(11)?,
-                    age: row.get(12)?,
-                    weight: row.get(13)?,
-                    height: row.get(14)?,
-                    observations: row.get(15)?,
-                }
-            ))
-        })?;
+    // Replaces the period results of an evaluation and sets the template
+    // distance to the last cumulative distance, along with the aerobic
+    // estimate derived from it
+    pub fn update_period_results(
+        &self,
+        evaluation_id: i64,
+        template_id: i64,
+        period_results: &[PeriodResult],
+        aerobic: Option<AerobicEstimate>,
+    ) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM period_results WHERE evaluation_id = ?1", [evaluation_id])?;
+        save_period_results(&tx, evaluation_id, period_results)?;
+        if let Some(last) = period_results.last() {
+            tx.execute(
+                "UPDATE evaluation_templates SET total_distance = ?1 WHERE id = ?2",
+                params![last.cumulative_distance, template_id],
+            )?;
+        }
+        tx.execute(
+            "UPDATE evaluation_templates SET final_speed = ?1, mas = ?2, vo2max = ?3 WHERE id = ?4",
+            params![
+                aerobic.map(|a| a.final_speed),
+                aerobic.map(|a| a.mas),
+                aerobic.map(|a| a.vo2max),
+                template_id,
+            ],
+        )?;
+
+        tx.commit()
+    }
+
+    pub fn export_all_evaluations_to_xlsx<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rows = query_export_rows(&self.connection.lock().unwrap(), ExportFilter::All)?;
+        write_xlsx(path, rows)
+    }
+
+    pub fn export_athlete_evaluations_to_xlsx<P: AsRef<Path>>(
+        &self,
+        athlete_id: i64,
+        path: P,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rows = query_export_rows(&self.connection.lock().unwrap(), ExportFilter::Athlete(athlete_id))?;
+        write_xlsx(path, rows)
+    }
+
+    // Whether another athlete than `exclude_id` has this name (case and
+    // surrounding whitespace are ignored)
+    pub fn athlete_name_taken(&self, name: &str, exclude_id: Option<i64>) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        Ok(find_unlicensed_athlete(&conn, name, exclude_id)?.is_some())
+    }
+
+    pub fn license_number_taken(&self, license_number: &str, exclude_id: Option<i64>) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM athletes WHERE license_number = ?1 AND id != COALESCE(?2, -1)",
+            params![license_number, exclude_id],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn athlete_exists(&self, athlete_id: i64) -> Result<bool> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row("SELECT COUNT(*) FROM athletes WHERE id = ?1", [athlete_id], |row| row.get(0))
+    }
+
+    pub fn count_athlete_evaluations(&self, athlete_id: i64) -> Result<i64> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*) FROM athlete_evaluations WHERE athlete_id = ?1",
+            [athlete_id],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn create_athlete(&self, athlete: &Athlete) -> Result<i64> {
+        let conn = self.connection.lock().unwrap();
+        insert_athlete(&conn, athlete)
+    }
+
+    pub fn update_athlete(&self, id: i64, athlete: &Athlete) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE athletes
+             SET name = ?1, age = ?2, weight = ?3, height = ?4, observations = ?5,
+                 birth_date = ?6, sex = ?7, discipline = ?8, category = ?9, club = ?10,
+                 license_number = ?11
+             WHERE id = ?12",
+            params![
+                athlete.name.trim(),
+                athlete.age,
+                athlete.weight,
+                athlete.height,
+                athlete.observations,
+                athlete.birth_date,
+                athlete.sex,
+                athlete.discipline,
+                athlete.category,
+                athlete.club,
+                athlete.license_number,
+                id,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    pub fn get_athlete(&self, athlete_id: i64) -> Result<Athlete> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            &format!("SELECT {} FROM athletes a WHERE a.id = ?1", ATHLETE_COLUMNS),
+            [athlete_id],
+            |row| athlete_from_row(row, 0),
+        )
+    }
+
+    pub fn list_athletes(
+        &self,
+        search: Option<&str>,
+        sort_by: AthleteSortField,
+        descending: bool,
+    ) -> Result<Vec<Athlete>> {
+        let conn = self.connection.lock().unwrap();
+        let query = format!(
+            "SELECT {}
+             FROM athletes a
+             WHERE ?1 IS NULL OR a.name LIKE '%' || ?1 || '%' ESCAPE '\\'
+                OR a.club LIKE '%' || ?1 || '%' ESCAPE '\\'
+                OR a.license_number LIKE '%' || ?1 || '%' ESCAPE '\\'
+             ORDER BY {} {}, a.id",
+            ATHLETE_COLUMNS,
+            sort_by.column(),
+            if descending { "DESC" } else { "ASC" },
+        );
+        let mut stmt = conn.prepare(&query)?;
+
+        // The term is matched literally, not as a LIKE pattern
+        let search = search
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        let rows = stmt.query_map([search], |row| athlete_from_row(row, 0))?;
 
         rows.collect()
     }
 
-    pub fn migrate_old_evaluations(&self) -> Result<(), Box<dyn std::error::Error>> {
+    // Deletes an athlete with its evaluations, and the templates only they
+    // reference
+    pub fn delete_athlete(&self, athlete_id: i64) -> Result<()> {
         let mut conn = self.connection.lock().unwrap();
         let tx = conn.transaction()?;
 
-        // Check if old evaluations table exists
-        let old_table_exists: bool = tx
-            .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='evaluations'",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(false);
-
-        if old_table_exists {
-            // Create temporary table to store old data
-            tx.execute(
-                "CREATE TEMPORARY TABLE old_evaluations AS SELECT * FROM evaluations",
-                [],
+        let template_ids: Vec<i64> = {
+            let mut stmt = tx.prepare(
+                "SELECT template_id FROM athlete_evaluations WHERE athlete_id = ?1",
             )?;
+            let ids = stmt.query_map([athlete_id], |row| row.get(0))?;
+            ids.collect::<Result<_>>()?
+        };
 
-            // Migrate data to new structure
-            tx.execute(
-                "INSERT INTO evaluation_templates (completed_periods, total_time, date)
-                 SELECT DISTINCT completed_periods, total_time, date
-                 FROM old_evaluations",
-                [],
-            )?;
+        tx.execute(
+            "DELETE FROM period_results
+             WHERE evaluation_id IN (SELECT id FROM athlete_evaluations WHERE athlete_id = ?1)",
+            [athlete_id],
+        )?;
+        tx.execute(
+            "DELETE FROM period_measurements
+             WHERE evaluation_id IN (SELECT id FROM athlete_evaluations WHERE athlete_id = ?1)",
+            [athlete_id],
+        )?;
+        tx.execute(
+            "DELETE FROM evaluation_warnings
+             WHERE evaluation_id IN (SELECT id FROM athlete_evaluations WHERE athlete_id = ?1)",
+            [athlete_id],
+        )?;
+        tx.execute(
+            "DELETE FROM heart_rate_samples
+             WHERE evaluation_id IN (SELECT id FROM athlete_evaluations WHERE athlete_id = ?1)",
+            [athlete_id],
+        )?;
+        tx.execute(
+            "DELETE FROM heart_rate_recordings
+             WHERE evaluation_id IN (SELECT id FROM athlete_evaluations WHERE athlete_id = ?1)",
+            [athlete_id],
+        )?;
+        tx.execute(
+            "DELETE FROM athlete_evaluations WHERE athlete_id = ?1",
+            [athlete_id],
+        )?;
 
+        for template_id in template_ids {
             tx.execute(
-                "INSERT INTO athlete_evaluations (athlete_id, template_id, status, date)
-                 SELECT 
-                     oe.athlete_id,
-                     et.id,
-                     oe.status,
-                     oe.date
-                 FROM old_evaluations oe
-                 JOIN evaluation_templates et 
-                     ON et.completed_periods = oe.completed_periods
-                     AND et.total_time = oe.total_time
-                     AND et.date = oe.date",
-                [],
+                "DELETE FROM evaluation_templates
+                 WHERE id = ?1
+                   AND NOT EXISTS (SELECT 1 FROM athlete_evaluations WHERE template_id = ?1)",
+                [template_id],
             )?;
+        }
 
-            // Drop old table
-            tx.execute("DROP TABLE evaluations", [])?;
-            tx.execute("DROP TABLE old_evaluations", [])?;
+        let deleted = tx.execute("DELETE FROM athletes WHERE id = ?1", [athlete_id])?;
+        if deleted == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
         }
 
         tx.commit()?;
         Ok(())
     }
 
-    pub fn update_evaluation_observations(
-        &self,
-        evaluation_id: i64,
-        observations: String,
-    ) -> Result<()> {
+    pub fn create_session(&self, session: &Session) -> Result<i64> {
         let conn = self.connection.lock().unwrap();
         conn.execute(
-            "UPDATE athletes SET observations = ?1 
-             WHERE id = (
-                SELECT athlete_id 
-                FROM athlete_evaluations 
-                WHERE id = ?2
-             )",
-            params![observations, evaluation_id],
+            "INSERT INTO sessions
+                (date, venue, surface, temperature, humidity, evaluator, recovery_time, notes,
+                 track_length, segments_per_lap)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                session.date,
+                session.venue,
+                session.surface,
+                session.temperature,
+                session.humidity,
+                session.evaluator,
+                session.recovery_time,
+                session.notes,
+                session.track_geometry.map(|g| g.track_length),
+                session.track_geometry.map(|g| g.segments_per_lap),
+            ],
         )?;
-        Ok(())
+        Ok(conn.last_insert_rowid())
     }
 
-    pub fn export_all_evaluations_to_xlsx<P: AsRef<Path>>(
-        &self,
-        path: P,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn list_sessions(&self) -> Result<Vec<(Session, i64)>> {
         let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT ae.id, ae.athlete_id, a.name, et.completed_periods, et.total_time, ae.date, ae.status, 
-                    a.observations, et.total_distance
-             FROM athlete_evaluations ae 
-             JOIN athletes a ON ae.athlete_id = a.id 
-             JOIN evaluation_templates et ON ae.template_id = et.id
-             ORDER BY ae.date DESC"
-        )?;
-
-        let mut workbook = Workbook::new();
-        let worksheet = workbook.add_worksheet();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, (SELECT COUNT(*) FROM athlete_evaluations ae WHERE ae.session_id = s.id)
+             FROM sessions s
+             ORDER BY s.date DESC, s.id DESC",
+            SESSION_COLUMNS,
+        ))?;
+        let rows = stmt.query_map([], |row| Ok((session_from_row(row, 0)?, row.get(SESSION_COLUMN_COUNT)?)))?;
+        rows.collect()
+    }
 
-        let header_format = Format::new()
-            .set_bold()
-            .set_pattern(rust_xlsxwriter::FormatPattern::Solid)
-            .set_background_color(0xD0E0F0);
+    pub fn get_session(&self, session_id: i64) -> Result<Session> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            &format!("SELECT {} FROM sessions s WHERE s.id = ?1", SESSION_COLUMNS),
+            [session_id],
+            |row| session_from_row(row, 0),
+        )
+    }
 
-        // Write headers
-        let headers = ["ID", "Atleta ID", "Nombre del Atleta", "Periodos Completados", 
-                      "Tiempo Total", "Fecha", "Estado", "Observaciones", "Distancia Total (m)"];
-        for (col, header) in headers.iter().enumerate() {
-            worksheet.write_string_with_format(0, col as u16, *header, &header_format)?;
-            worksheet.set_column_width(col as u16, 15)?;
-        }
+    pub fn get_session_results(
+        &self,
+        session_id: i64,
+    ) -> Result<(Session, Vec<EvaluationRecord>)> {
+        let conn = self.connection.lock().unwrap();
+        let session = conn.query_row(
+            &format!("SELECT {} FROM sessions s WHERE s.id = ?1", SESSION_COLUMNS),
+            [session_id],
+            |row| session_from_row(row, 0),
+        )?;
 
-        let rows = stmt.query_map([], |row| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {}, {}
+             FROM athlete_evaluations ae
+             JOIN evaluation_templates et ON ae.template_id = et.id
+             JOIN athletes a ON ae.athlete_id = a.id
+             WHERE ae.session_id = ?1
+             ORDER BY a.name COLLATE NOCASE",
+            EVALUATION_COLUMNS, TEMPLATE_COLUMNS, ATHLETE_COLUMNS,
+        ))?;
+        let rows = stmt.query_map([session_id], |row| {
             Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, i32>(4)?,
-                row.get::<_, String>(5)?,
-                row.get::<_, String>(6)?,
-                row.get::<_, Option<String>>(7)?.unwrap_or_default(),
-                row.get::<_, f32>(8)?,
+                evaluation_from_row(row, 0)?,
+                template_from_row(row, EVALUATION_COLUMN_COUNT)?,
+                athlete_from_row(row, EVALUATION_COLUMN_COUNT + TEMPLATE_COLUMN_COUNT)?,
             ))
         })?;
 
-        let mut row_num = 1;
-        for row in rows {
-            let (id, athlete_id, name, periods, time, date, status, observations, total_distance) = row?;
-            worksheet.write_number(row_num, 0, id as f64)?;
-            worksheet.write_number(row_num, 1, athlete_id as f64)?;
-            worksheet.write_string(row_num, 2, &name)?;
-            worksheet.write_string(row_num, 3, &periods)?;
-            worksheet.write_string(row_num, 4, &format_seconds_to_time(time))?;
-            worksheet.write_string(row_num, 5, &date)?;
-            worksheet.write_string(row_num, 6, &status)?;
-            worksheet.write_string(row_num, 7, &observations)?;
-            worksheet.write_number(row_num, 8, total_distance as f64)?;
-            row_num += 1;
-        }
+        Ok((session, rows.collect::<Result<_>>()?))
+    }
 
-        workbook.save(path)?;
-        Ok(())
+    pub fn get_group_evaluations(&self, filter: &GroupFilter) -> Result<Vec<EvaluationRecord>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {}, {}
+             FROM athlete_evaluations ae
+             JOIN evaluation_templates et ON ae.template_id = et.id
+             JOIN athletes a ON ae.athlete_id = a.id
+             WHERE (?1 IS NULL OR ae.session_id = ?1)
+               AND (?2 IS NULL OR a.category = ?2 COLLATE NOCASE)
+               AND (?3 IS NULL OR a.club = ?3 COLLATE NOCASE)
+               AND (?4 IS NULL OR a.sex = ?4)
+               AND (?5 IS NULL OR a.discipline = ?5)
+               AND (?6 IS NULL OR SUBSTR(ae.date, 1, 10) >= ?6)
+               AND (?7 IS NULL OR SUBSTR(ae.date, 1, 10) <= ?7)
+             ORDER BY ae.date, ae.id",
+            EVALUATION_COLUMNS, TEMPLATE_COLUMNS, ATHLETE_COLUMNS,
+        ))?;
+        let rows = stmt.query_map(
+            params![
+                filter.session_id,
+                filter.category,
+                filter.club,
+                filter.sex,
+                filter.discipline,
+                filter.from,
+                filter.to,
+            ],
+            |row| {
+                Ok((
+                    evaluation_from_row(row, 0)?,
+                    template_from_row(row, EVALUATION_COLUMN_COUNT)?,
+                    athlete_from_row(row, EVALUATION_COLUMN_COUNT + TEMPLATE_COLUMN_COUNT)?,
+                ))
+            },
+        )?;
+        rows.collect()
     }
 
-    pub fn export_athlete_evaluations_to_xlsx<P: AsRef<Path>>(
+    pub fn export_session_evaluations_to_csv<P: AsRef<Path>>(
         &self,
-        athlete_id: i64,
+        session_id: i64,
+        path: P,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rows = query_export_rows(&self.connection.lock().unwrap(), ExportFilter::Session(session_id))?;
+        write_csv(path, rows)
+    }
+
+    pub fn export_session_evaluations_to_xlsx<P: AsRef<Path>>(
+        &self,
+        session_id: i64,
         path: P,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let rows = query_export_rows(&self.connection.lock().unwrap(), ExportFilter::Session(session_id))?;
+        write_xlsx(path, rows)
+    }
+
+    // Adds the norms in a single transaction, replacing the stored ones when
+    // `replace` is set. Returns the number of norms saved.
+    pub fn import_norms(&self, norms: &[Norm], replace: bool) -> Result<usize> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        if replace {
+            tx.execute("DELETE FROM norms", [])?;
+        }
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO norms
+                    (metric, sex, discipline, age_min, age_max, p10, p25, p50, p75, p90, source)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            for norm in norms {
+                stmt.execute(params![
+                    norm.metric,
+                    norm.sex,
+                    norm.discipline,
+                    norm.age_min,
+                    norm.age_max,
+                    norm.p10,
+                    norm.p25,
+                    norm.p50,
+                    norm.p75,
+                    norm.p90,
+                    norm.source,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(norms.len())
+    }
+
+    pub fn list_norms(&self) -> Result<Vec<Norm>> {
+        load_norms(&self.connection.lock().unwrap())
+    }
+
+    pub fn delete_norm(&self, norm_id: i64) -> Result<()> {
         let conn = self.connection.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT ae.id, ae.athlete_id, a.name, et.completed_periods, et.total_time, ae.date, ae.status,
-                    a.observations, et.total_distance
-             FROM athlete_evaluations ae 
-             JOIN athletes a ON ae.athlete_id = a.id 
-             JOIN evaluation_templates et ON ae.template_id = et.id
-             WHERE ae.athlete_id = ?1
-             ORDER BY ae.date DESC"
+        let deleted = conn.execute("DELETE FROM norms WHERE id = ?1", [norm_id])?;
+        if deleted == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    // Replaces the heart rate recording of an evaluation
+    pub fn save_heart_rate(&self, recording: &HeartRateRecording) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM heart_rate_samples WHERE evaluation_id = ?1", [recording.evaluation_id])?;
+        tx.execute("DELETE FROM heart_rate_recordings WHERE evaluation_id = ?1", [recording.evaluation_id])?;
+        tx.execute(
+            "INSERT INTO heart_rate_recordings (evaluation_id, source, format, start_offset, imported_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                recording.evaluation_id,
+                recording.source,
+                recording.format,
+                recording.start_offset,
+                recording.imported_at,
+            ],
         )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO heart_rate_samples (evaluation_id, elapsed, heart_rate) VALUES (?1, ?2, ?3)",
+            )?;
+            for sample in &recording.samples {
+                stmt.execute(params![recording.evaluation_id, sample.elapsed, sample.heart_rate])?;
+            }
+        }
 
-        let mut workbook = Workbook::new();
-        let worksheet = workbook.add_worksheet();
+        tx.commit()
+    }
 
-        let header_format = Format::new()
-            .set_bold()
-            .set_pattern(rust_xlsxwriter::FormatPattern::Solid)
-            .set_background_color(0xD0E0F0);
+    pub fn get_heart_rate(&self, evaluation_id: i64) -> Result<Option<(HeartRateRecording, Option<Timeline>)>> {
+        let conn = self.connection.lock().unwrap();
+        let Some(recording) = load_heart_rate(&conn, evaluation_id)? else {
+            return Ok(None);
+        };
+        Ok(Some((recording, load_timeline(&conn, evaluation_id)?)))
+    }
+
+    pub fn get_evaluation_timeline(&self, evaluation_id: i64) -> Result<Option<Timeline>> {
+        load_timeline(&self.connection.lock().unwrap(), evaluation_id)
+    }
 
-        // Write headers
-        let headers = ["ID", "Atleta ID", "Nombre del Atleta", "Periodos Completados", 
-                      "Tiempo Total", "Fecha", "Estado", "Observaciones", "Distancia Total (m)"];
-        for (col, header) in headers.iter().enumerate() {
-            worksheet.write_string_with_format(0, col as u16, *header, &header_format)?;
-            worksheet.set_column_width(col as u16, 15)?;
+    pub fn delete_heart_rate(&self, evaluation_id: i64) -> Result<()> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM heart_rate_samples WHERE evaluation_id = ?1", [evaluation_id])?;
+        let deleted = tx.execute("DELETE FROM heart_rate_recordings WHERE evaluation_id = ?1", [evaluation_id])?;
+        if deleted == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
         }
+        tx.commit()
+    }
 
-        let rows = stmt.query_map([athlete_id], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-                row.get::<_, i32>(4)?,
-                row.get::<_, String>(5)?,
-                row.get::<_, String>(6)?,
-                row.get::<_, Option<String>>(7)?.unwrap_or_default(),
-                row.get::<_, f32>(8)?,
-            ))
-        })?;
+    // Replaces the lactate and RPE of an evaluation
+    pub fn save_period_measurements(
+        &self,
+        evaluation_id: i64,
+        measurements: &[PeriodMeasurement],
+    ) -> Result<LactateAnalysis> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
 
-        let mut row_num = 1;
-        for row in rows {
-            let (id, athlete_id, name, periods, time, date, status, observations, total_distance) = row?;
-            worksheet.write_number(row_num, 0, id as f64)?;
-            worksheet.write_number(row_num, 1, athlete_id as f64)?;
-            worksheet.write_string(row_num, 2, &name)?;
-            worksheet.write_string(row_num, 3, &periods)?;
-            worksheet.write_string(row_num, 4, &format_seconds_to_time(time))?;
-            worksheet.write_string(row_num, 5, &date)?;
-            worksheet.write_string(row_num, 6, &status)?;
-            worksheet.write_string(row_num, 7, &observations)?;
-            worksheet.write_number(row_num, 8, total_distance as f64)?;
-            row_num += 1;
+        tx.execute("DELETE FROM period_measurements WHERE evaluation_id = ?1", [evaluation_id])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO period_measurements (evaluation_id, period, lactate, rpe)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for measurement in measurements {
+                stmt.execute(params![evaluation_id, measurement.period, measurement.lactate, measurement.rpe])?;
+            }
         }
+        let period_results = get_period_results(&tx, evaluation_id)?;
+        tx.commit()?;
+        Ok(lactate::analyze(evaluation_id, &period_results, measurements))
+    }
+
+    // Every period of the evaluation, with or without measurements
+    pub fn get_lactate_analysis(&self, evaluation_id: i64) -> Result<LactateAnalysis> {
+        let conn = self.connection.lock().unwrap();
+        let measurements = load_period_measurements(&conn, evaluation_id)?;
+        let period_results = get_period_results(&conn, evaluation_id)?;
+        Ok(lactate::analyze(evaluation_id, &period_results, &measurements))
+    }
+
+    pub fn get_warnings(&self, evaluation_id: i64) -> Result<Vec<Warning>> {
+        let conn = self.connection.lock().unwrap();
+        load_warnings(&conn, evaluation_id)
+    }
 
-        workbook.save(path)?;
+    // Starts the journal of a new test, replacing the previous one
+    pub fn start_test_journal(&self, journal: &TestJournal) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO test_journal
+                (id, protocol_id, protocol_version, track_length, segments_per_lap, start_period,
+                 recovery_time, started_at, updated_at, elapsed, state)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                journal.protocol_id,
+                journal.protocol_version,
+                journal.geometry.track_length,
+                journal.geometry.segments_per_lap,
+                journal.start_period,
+                journal.recovery_time,
+                journal.started_at,
+                journal.updated_at,
+                journal.elapsed,
+                journal.state.as_ref().map(|state| state.to_string()),
+            ],
+        )?;
         Ok(())
     }
+
+    pub fn update_test_journal(&self, elapsed: f32, state: &serde_json::Value, updated_at: &str) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE test_journal SET elapsed = ?1, state = ?2, updated_at = ?3 WHERE id = 1",
+            params![elapsed, state.to_string(), updated_at],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
+    pub fn get_test_journal(&self) -> Result<Option<TestJournal>> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            "SELECT protocol_id, protocol_version, track_length, segments_per_lap, start_period,
+                    recovery_time, started_at, updated_at, elapsed, state
+             FROM test_journal
+             WHERE id = 1",
+            [],
+            |row| {
+                let state: Option<String> = row.get(9)?;
+                Ok(TestJournal {
+                    protocol_id: row.get(0)?,
+                    protocol_version: row.get(1)?,
+                    geometry: TrackGeometry {
+                        track_length: row.get(2)?,
+                        segments_per_lap: row.get(3)?,
+                    },
+                    start_period: row.get(4)?,
+                    recovery_time: row.get(5)?,
+                    started_at: row.get(6)?,
+                    updated_at: row.get(7)?,
+                    elapsed: row.get(8)?,
+                    state: state
+                        .map(|state| serde_json::from_str(&state))
+                        .transpose()
+                        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(9, rusqlite::types::Type::Text, Box::new(e)))?,
+                })
+            },
+        )
+        .optional()
+    }
+
+    pub fn delete_test_journal(&self) -> Result<()> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute("DELETE FROM test_journal", [])?;
+        Ok(())
+    }
+
+    // Stores a new version of the protocol `code` and returns its number
+    pub fn save_protocol_version(
+        &self,
+        code: &str,
+        name: &str,
+        parameters: &ProtocolParameters,
+        created_at: &str,
+    ) -> Result<i32> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let version: i32 = tx.query_row(
+            "SELECT COALESCE(MAX(version), 0) + 1 FROM protocols WHERE code = ?1",
+            [code],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "INSERT INTO protocols
+                (code, version, name, start_period, period_count, start_speed, speed_increment,
+                 laps_per_period, track_length, segments_per_lap, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                code,
+                version,
+                name,
+                parameters.start_period,
+                parameters.period_count,
+                parameters.start_speed,
+                parameters.speed_increment,
+                parameters.laps_per_period,
+                parameters.track_length,
+                parameters.segments_per_lap,
+                created_at,
+            ],
+        )?;
+
+        tx.commit()?;
+        Ok(version)
+    }
+
+    // Returns the requested version of a protocol, or its latest one
+    pub fn get_protocol(&self, code: &str, version: Option<i32>) -> Result<Option<StoredProtocol>> {
+        let conn = self.connection.lock().unwrap();
+        conn.query_row(
+            &format!(
+                "SELECT {}
+                 FROM protocols p
+                 WHERE p.code = ?1 AND (?2 IS NULL OR p.version = ?2)
+                 ORDER BY p.version DESC
+                 LIMIT 1",
+                PROTOCOL_COLUMNS,
+            ),
+            params![code, version],
+            |row| protocol_from_row(row, 0),
+        )
+        .optional()
+    }
+
+    pub fn list_protocols(&self) -> Result<Vec<StoredProtocol>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM protocols p ORDER BY p.name COLLATE NOCASE, p.code, p.version DESC",
+            PROTOCOL_COLUMNS,
+        ))?;
+        let rows = stmt.query_map([], |row| protocol_from_row(row, 0))?;
+        rows.collect()
+    }
 }
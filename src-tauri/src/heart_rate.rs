@@ -0,0 +1,606 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::db::{text_enum, PeriodResult};
+use crate::performance::round_to;
+
+text_enum!(HeartRateFormat, "heart rate format", {
+    Csv => ("csv", "CSV"),
+    Tcx => ("tcx", "TCX"),
+    Fit => ("fit", "FIT"),
+});
+
+impl HeartRateFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        HeartRateFormat::ALL.iter().copied().find(|format| format.as_str() == extension)
+    }
+}
+
+// Plausible readings, chest straps report 0 when they lose contact
+const MIN_HEART_RATE: i32 = 20;
+const MAX_HEART_RATE: i32 = 250;
+
+// Samples of a recording as seconds since its first sample. `start` is the
+// time of the first sample when the file has absolute timestamps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub start: Option<DateTime<Utc>>,
+    pub samples: Vec<(f64, i32)>,
+}
+
+impl Recording {
+    fn from_timed(samples: Vec<(DateTime<Utc>, i32)>) -> Recording {
+        let start = samples.iter().map(|(time, _)| *time).min();
+        Recording {
+            start,
+            samples: samples
+                .into_iter()
+                .map(|(time, heart_rate)| {
+                    let elapsed = (time - start.unwrap_or(time)).num_milliseconds() as f64 / 1000.0;
+                    (elapsed, heart_rate)
+                })
+                .collect(),
+        }
+    }
+}
+
+pub fn parse(format: HeartRateFormat, bytes: &[u8]) -> Result<Recording, String> {
+    let mut recording = match format {
+        HeartRateFormat::Csv => parse_csv(bytes)?,
+        HeartRateFormat::Tcx => parse_tcx(bytes)?,
+        HeartRateFormat::Fit => parse_fit(bytes)?,
+    };
+    recording
+        .samples
+        .retain(|(_, heart_rate)| (MIN_HEART_RATE..=MAX_HEART_RATE).contains(heart_rate));
+    recording.samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    recording.samples.dedup_by(|a, b| a.0 == b.0);
+    if recording.samples.is_empty() {
+        return Err(format!(
+            "El archivo {} no contiene registros de frecuencia cardíaca",
+            format.label()
+        ));
+    }
+    Ok(recording)
+}
+
+const CSV_TIME_COLUMNS: [&str; 6] = ["time", "timestamp", "elapsed", "seconds", "tiempo", "segundos"];
+const CSV_HEART_RATE_COLUMNS: [&str; 5] = ["heart_rate", "hr", "bpm", "fc", "frecuencia_cardiaca"];
+
+enum CsvTime {
+    Elapsed(f64),
+    Absolute(DateTime<Utc>),
+}
+
+// Seconds, [hh:]mm:ss or an RFC 3339 timestamp
+fn parse_csv_time(value: &str) -> Option<CsvTime> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Some(CsvTime::Elapsed(seconds));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(CsvTime::Absolute(time.with_timezone(&Utc)));
+    }
+    let parts: Vec<f64> = value.split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [minutes, seconds] => Some(CsvTime::Elapsed(minutes * 60.0 + seconds)),
+        [hours, minutes, seconds] => Some(CsvTime::Elapsed(hours * 3600.0 + minutes * 60.0 + seconds)),
+        _ => None,
+    }
+}
+
+// CSV files need a time column and a heart rate column, see the accepted
+// names above (case is ignored)
+fn parse_csv(bytes: &[u8]) -> Result<Recording, String> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(bytes);
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.to_lowercase().as_str()))
+    };
+    let time_column = column(&CSV_TIME_COLUMNS).ok_or("El CSV no tiene columna de tiempo")?;
+    let heart_rate_column =
+        column(&CSV_HEART_RATE_COLUMNS).ok_or("El CSV no tiene columna de frecuencia cardíaca")?;
+
+    let mut elapsed = Vec::new();
+    let mut absolute = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        // Header is line 1
+        let line = index + 2;
+        let record = record.map_err(|e| format!("Fila {}: {}", line, e))?;
+        let heart_rate = match record.get(heart_rate_column).unwrap_or_default() {
+            "" => continue,
+            value => value
+                .parse::<f32>()
+                .map_err(|_| format!("Fila {}: frecuencia cardíaca no válida", line))?
+                .round() as i32,
+        };
+        match parse_csv_time(record.get(time_column).unwrap_or_default()) {
+            Some(CsvTime::Elapsed(seconds)) => elapsed.push((seconds, heart_rate)),
+            Some(CsvTime::Absolute(time)) => absolute.push((time, heart_rate)),
+            None => return Err(format!("Fila {}: tiempo no válido", line)),
+        }
+    }
+
+    match (elapsed.is_empty(), absolute.is_empty()) {
+        (_, true) => {
+            let first = elapsed.iter().map(|(seconds, _)| *seconds).fold(f64::INFINITY, f64::min);
+            Ok(Recording {
+                start: None,
+                samples: elapsed.into_iter().map(|(seconds, hr)| (seconds - first, hr)).collect(),
+            })
+        }
+        (true, false) => Ok(Recording::from_timed(absolute)),
+        (false, false) => Err("El CSV mezcla tiempos relativos y absolutos".to_string()),
+    }
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| child.tag_name().name() == name)
+}
+
+// Trackpoints of a Garmin Training Center file. Namespaces are ignored.
+fn parse_tcx(bytes: &[u8]) -> Result<Recording, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "El archivo TCX no es UTF-8".to_string())?;
+    let document = roxmltree::Document::parse(text).map_err(|e| format!("TCX no válido: {}", e))?;
+    let samples = document
+        .descendants()
+        .filter(|node| node.tag_name().name() == "Trackpoint")
+        .filter_map(|trackpoint| {
+            let time = child(trackpoint, "Time")?.text()?.trim();
+            let heart_rate = child(child(trackpoint, "HeartRateBpm")?, "Value")?.text()?.trim();
+            Some((time.to_string(), heart_rate.to_string()))
+        })
+        .map(|(time, heart_rate)| {
+            let time = DateTime::parse_from_rfc3339(&time)
+                .map_err(|_| format!("Hora no válida en el TCX: {}", time))?;
+            let heart_rate = heart_rate
+                .parse::<i32>()
+                .map_err(|_| format!("Frecuencia cardíaca no válida en el TCX: {}", heart_rate))?;
+            Ok((time.with_timezone(&Utc), heart_rate))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(Recording::from_timed(samples))
+}
+
+// FIT timestamps count seconds since 1989-12-31T00:00:00Z
+const FIT_EPOCH: i64 = 631_065_600;
+const FIT_RECORD_MESSAGE: u16 = 20;
+const FIT_TIMESTAMP_FIELD: u8 = 253;
+const FIT_HEART_RATE_FIELD: u8 = 3;
+
+struct FitDefinition {
+    global_message: u16,
+    big_endian: bool,
+    // (field number, size)
+    fields: Vec<(u8, usize)>,
+    developer_size: usize,
+}
+
+fn fit_unsigned(bytes: &[u8], big_endian: bool) -> u64 {
+    let fold = |value: u64, byte: &u8| (value << 8) | *byte as u64;
+    if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    }
+}
+
+fn fit_take<'a>(bytes: &'a [u8], position: &mut usize, size: usize) -> Result<&'a [u8], String> {
+    let slice = bytes
+        .get(*position..*position + size)
+        .ok_or_else(|| "Archivo FIT no válido".to_string())?;
+    *position += size;
+    Ok(slice)
+}
+
+// Minimal decoder of the FIT protocol: it follows definition and data
+// messages (including compressed timestamp headers) and keeps the
+// timestamp and heart rate of record messages. CRCs are not checked.
+fn parse_fit(bytes: &[u8]) -> Result<Recording, String> {
+    let invalid = || "Archivo FIT no válido".to_string();
+    let header_size = *bytes.first().ok_or_else(invalid)? as usize;
+    if bytes.len() < header_size || header_size < 12 || bytes.get(8..12) != Some(b".FIT") {
+        return Err(invalid());
+    }
+    let data_size = fit_unsigned(&bytes[4..8], false) as usize;
+    let end = (header_size + data_size).min(bytes.len());
+
+    let mut definitions: HashMap<u8, FitDefinition> = HashMap::new();
+    let mut last_timestamp: Option<u32> = None;
+    let mut samples = Vec::new();
+    let mut position = header_size;
+
+    while position < end {
+        let header = fit_take(bytes, &mut position, 1)?[0];
+        if header & 0x40 != 0 && header & 0x80 == 0 {
+            let local = header & 0x0F;
+            let fixed = fit_take(bytes, &mut position, 5)?;
+            let big_endian = fixed[1] == 1;
+            let global_message = fit_unsigned(&fixed[2..4], big_endian) as u16;
+            let field_count = fixed[4] as usize;
+            let fields = fit_take(bytes, &mut position, field_count * 3)?
+                .chunks(3)
+                .map(|field| (field[0], field[1] as usize))
+                .collect();
+            let mut developer_size = 0;
+            if header & 0x20 != 0 {
+                let developer_count = fit_take(bytes, &mut position, 1)?[0] as usize;
+                developer_size = fit_take(bytes, &mut position, developer_count * 3)?
+                    .chunks(3)
+                    .map(|field| field[1] as usize)
+                    .sum();
+            }
+            definitions.insert(
+                local,
+                FitDefinition { global_message, big_endian, fields, developer_size },
+            );
+            continue;
+        }
+
+        // Compressed headers carry the low 5 bits of the timestamp
+        let (local, compressed_offset) = if header & 0x80 != 0 {
+            ((header >> 5) & 0x03, Some((header & 0x1F) as u32))
+        } else {
+            (header & 0x0F, None)
+        };
+        let definition = definitions.get(&local).ok_or_else(invalid)?;
+        let mut timestamp = match (compressed_offset, last_timestamp) {
+            (Some(offset), Some(last)) => {
+                let mut timestamp = (last & !0x1F) | offset;
+                if offset < (last & 0x1F) {
+                    timestamp += 0x20;
+                }
+                Some(timestamp)
+            }
+            _ => None,
+        };
+        let mut heart_rate = None;
+        for (number, size) in &definition.fields {
+            let value = fit_take(bytes, &mut position, *size)?;
+            match *number {
+                FIT_TIMESTAMP_FIELD if *size == 4 && value != [0xFF; 4] => {
+                    timestamp = Some(fit_unsigned(value, definition.big_endian) as u32);
+                }
+                FIT_HEART_RATE_FIELD if *size == 1 && value[0] != 0xFF => {
+                    heart_rate = Some(value[0] as i32);
+                }
+                _ => {}
+            }
+        }
+        fit_take(bytes, &mut position, definition.developer_size)?;
+
+        if timestamp.is_some() {
+            last_timestamp = timestamp;
+        }
+        if definition.global_message == FIT_RECORD_MESSAGE {
+            if let (Some(timestamp), Some(heart_rate)) = (timestamp, heart_rate) {
+                let time = DateTime::from_timestamp(FIT_EPOCH + timestamp as i64, 0).ok_or_else(invalid)?;
+                samples.push((time, heart_rate));
+            }
+        }
+    }
+    Ok(Recording::from_timed(samples))
+}
+
+// Heart rate `elapsed` seconds after the start of the test (negative before
+// it started)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct HeartRateSample {
+    pub elapsed: f32,
+    pub heart_rate: i32,
+}
+
+// When each period was skated, in seconds since the start of the test
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PeriodWindow {
+    pub period: i32,
+    pub start: f32,
+    pub end: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    pub start: DateTime<Utc>,
+    pub end: f32,
+    pub periods: Vec<PeriodWindow>,
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|time| time.with_timezone(&Utc))
+}
+
+// Each period result is recorded when the period ends. Its duration is the
+// distance skated in it at the period speed, so recoveries between periods
+// are left out. Without period results the test is assumed to have ended on
+// the evaluation date after `total_time` seconds.
+pub fn timeline(period_results: &[PeriodResult], evaluation_date: &str, total_time: i32) -> Option<Timeline> {
+    let mut previous_distance = 0.0;
+    let mut ends = Vec::new();
+    for result in period_results {
+        let end = parse_time(&result.recorded_at)?;
+        let distance = result.cumulative_distance - previous_distance;
+        previous_distance = result.cumulative_distance;
+        let duration = if result.speed > 0.0 { distance / (result.speed / 3.6) } else { 0.0 };
+        ends.push((result.period, end, duration));
+    }
+
+    let Some((_, first_end, first_duration)) = ends.first().copied() else {
+        let end = parse_time(evaluation_date)?;
+        return Some(Timeline {
+            start: end - Duration::seconds(total_time as i64),
+            end: total_time as f32,
+            periods: Vec::new(),
+        });
+    };
+    let start = first_end - Duration::milliseconds((first_duration * 1000.0) as i64);
+    let seconds = |time: DateTime<Utc>| (time - start).num_milliseconds() as f32 / 1000.0;
+    let periods: Vec<PeriodWindow> = ends
+        .iter()
+        .map(|(period, end, duration)| PeriodWindow {
+            period: *period,
+            start: round_to(seconds(*end) - duration, 1),
+            end: round_to(seconds(*end), 1),
+        })
+        .collect();
+    Some(Timeline {
+        start,
+        end: periods.last().map_or(0.0, |window| window.end),
+        periods,
+    })
+}
+
+// Places a recording on the test timeline. `offset` (seconds after the
+// start of the test when the recording started) wins over the timestamps of
+// the file; recordings without either are assumed to start with the test.
+pub fn align(recording: &Recording, test_start: Option<DateTime<Utc>>, offset: Option<f32>) -> (f32, Vec<HeartRateSample>) {
+    let offset = offset.unwrap_or_else(|| match (recording.start, test_start) {
+        (Some(recording_start), Some(test_start)) => {
+            (recording_start - test_start).num_milliseconds() as f32 / 1000.0
+        }
+        _ => 0.0,
+    });
+    let samples = recording
+        .samples
+        .iter()
+        .map(|(elapsed, heart_rate)| HeartRateSample {
+            elapsed: round_to(*elapsed as f32 + offset, 1),
+            heart_rate: *heart_rate,
+        })
+        .collect();
+    (offset, samples)
+}
+
+// Heart rate recording of an evaluation as stored
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HeartRateRecording {
+    pub evaluation_id: i64,
+    // Name of the imported file
+    pub source: String,
+    pub format: HeartRateFormat,
+    pub start_offset: f32,
+    pub imported_at: String,
+    pub samples: Vec<HeartRateSample>,
+}
+
+// A recording with the periods of its test and the values derived from them
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HeartRateAnalysis {
+    pub recording: HeartRateRecording,
+    pub periods: Vec<PeriodWindow>,
+    pub test_end: Option<f32>,
+    pub summary: Option<HeartRateSummary>,
+}
+
+impl HeartRateAnalysis {
+    pub fn new(recording: HeartRateRecording, timeline: Option<Timeline>) -> Self {
+        let summary = timeline
+            .as_ref()
+            .and_then(|timeline| summarize(&recording.samples, timeline));
+        HeartRateAnalysis {
+            periods: timeline.as_ref().map(|t| t.periods.clone()).unwrap_or_default(),
+            test_end: timeline.map(|t| t.end),
+            summary,
+            recording,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PeriodHeartRate {
+    pub period: i32,
+    pub mean: f32,
+    pub max: i32,
+}
+
+// Drop from the final heart rate `after` seconds after the end of the test
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HeartRateRecovery {
+    pub after: i32,
+    pub heart_rate: i32,
+    pub drop: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HeartRateSummary {
+    // Highest value during the test
+    pub max: i32,
+    pub mean: f32,
+    // Last value before the end of the test
+    pub end: Option<i32>,
+    pub periods: Vec<PeriodHeartRate>,
+    pub recovery: Vec<HeartRateRecovery>,
+}
+
+// Recovery is measured at these seconds after the end of the test, with the
+// closest sample within RECOVERY_TOLERANCE seconds
+pub const RECOVERY_TIMES: [i32; 2] = [60, 120];
+const RECOVERY_TOLERANCE: f32 = 5.0;
+
+fn mean(samples: &[&HeartRateSample]) -> f32 {
+    let sum: i32 = samples.iter().map(|sample| sample.heart_rate).sum();
+    round_to(sum as f32 / samples.len() as f32, 1)
+}
+
+pub fn summarize(samples: &[HeartRateSample], timeline: &Timeline) -> Option<HeartRateSummary> {
+    let during = |start: f32, end: f32| -> Vec<&HeartRateSample> {
+        samples.iter().filter(|s| s.elapsed >= start && s.elapsed <= end).collect()
+    };
+    let test = during(0.0, timeline.end);
+    if test.is_empty() {
+        return None;
+    }
+
+    let end = test.last().map(|sample| sample.heart_rate);
+    let recovery = RECOVERY_TIMES
+        .iter()
+        .filter_map(|after| {
+            let target = timeline.end + *after as f32;
+            let sample = samples
+                .iter()
+                .filter(|s| (s.elapsed - target).abs() <= RECOVERY_TOLERANCE)
+                .min_by(|a, b| (a.elapsed - target).abs().total_cmp(&(b.elapsed - target).abs()))?;
+            Some(HeartRateRecovery {
+                after: *after,
+                heart_rate: sample.heart_rate,
+                drop: end? - sample.heart_rate,
+            })
+        })
+        .collect();
+
+    Some(HeartRateSummary {
+        max: test.iter().map(|sample| sample.heart_rate).max()?,
+        mean: mean(&test),
+        end,
+        periods: timeline
+            .periods
+            .iter()
+            .filter_map(|window| {
+                let period = during(window.start, window.end);
+                Some(PeriodHeartRate {
+                    period: window.period,
+                    mean: (!period.is_empty()).then(|| mean(&period))?,
+                    max: period.iter().map(|sample| sample.heart_rate).max()?,
+                })
+            })
+            .collect(),
+        recovery,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(period: i32, recorded_at: &str, cumulative_distance: f32) -> PeriodResult {
+        PeriodResult {
+            id: None,
+            evaluation_id: 1,
+            period,
+            speed: 18.0,
+            lap_time: 40.0,
+            cumulative_distance,
+            recorded_at: recorded_at.to_string(),
+            completed: true,
+        }
+    }
+
+    #[test]
+    fn reads_csv_and_tcx() {
+        let csv = "Tiempo,FC\n0:00,90\n0:01,0\n0:02,95\n";
+        let recording = parse(HeartRateFormat::Csv, csv.as_bytes()).unwrap();
+        assert_eq!(recording.start, None);
+        assert_eq!(recording.samples, vec![(0.0, 90), (2.0, 95)]);
+
+        let tcx = r#"<?xml version="1.0"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+  <Activities><Activity><Lap><Track>
+    <Trackpoint><Time>2024-05-01T10:00:05Z</Time><HeartRateBpm><Value>120</Value></HeartRateBpm></Trackpoint>
+    <Trackpoint><Time>2024-05-01T10:00:00Z</Time><HeartRateBpm><Value>110</Value></HeartRateBpm></Trackpoint>
+    <Trackpoint><Time>2024-05-01T10:00:07Z</Time></Trackpoint>
+  </Track></Lap></Activity></Activities>
+</TrainingCenterDatabase>"#;
+        let recording = parse(HeartRateFormat::Tcx, tcx.as_bytes()).unwrap();
+        assert_eq!(recording.start, parse_time("2024-05-01T10:00:00Z"));
+        assert_eq!(recording.samples, vec![(0.0, 110), (5.0, 120)]);
+
+        assert!(parse(HeartRateFormat::Csv, b"time,hr\n").is_err());
+    }
+
+    #[test]
+    fn reads_fit_records() {
+        let mut data = Vec::new();
+        // Local message 0: record with timestamp and heart rate
+        data.extend_from_slice(&[0x40, 0, 0, 20, 0, 2, 253, 4, 0x86, 3, 1, 0x02]);
+        data.push(0x00);
+        data.extend_from_slice(&1_000_000_000u32.to_le_bytes());
+        data.push(100);
+        // Local message 1: record with heart rate only, sent with compressed
+        // timestamp headers (2 s later)
+        data.extend_from_slice(&[0x41, 0, 0, 20, 0, 1, 3, 1, 0x02]);
+        let offset = ((1_000_000_000u32 + 2) & 0x1F) as u8;
+        data.extend_from_slice(&[0x80 | (1 << 5) | offset, 105]);
+
+        let mut file = vec![12, 0x10, 0, 0];
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(b".FIT");
+        file.extend_from_slice(&data);
+
+        let recording = parse(HeartRateFormat::Fit, &file).unwrap();
+        assert_eq!(recording.samples, vec![(0.0, 100), (2.0, 105)]);
+        assert!(parse(HeartRateFormat::Fit, b"not a fit file").is_err());
+    }
+
+    #[test]
+    fn aligns_samples_to_periods() {
+        // 200 m at 18 km/h take 40 s, with a 20 s recovery in between
+        let results = vec![
+            result(1, "2024-05-01T10:00:40Z", 200.0),
+            result(2, "2024-05-01T10:01:40Z", 400.0),
+        ];
+        let timeline = timeline(&results, "", 0).unwrap();
+        assert_eq!(timeline.start, parse_time("2024-05-01T10:00:00Z").unwrap());
+        assert_eq!(
+            timeline.periods,
+            vec![
+                PeriodWindow { period: 1, start: 0.0, end: 40.0 },
+                PeriodWindow { period: 2, start: 60.0, end: 100.0 },
+            ]
+        );
+
+        // The strap was started 10 s before the test. Heart rate rises until
+        // the end of the test (100 s) and then drops 1 bpm every 2 s.
+        let recording = Recording {
+            start: parse_time("2024-05-01T09:59:50Z"),
+            samples: (0..=23)
+                .map(|i| {
+                    let elapsed = i * 10 - 10;
+                    let heart_rate = if elapsed <= 100 { 100 + i * 5 } else { 155 - (elapsed - 100) / 2 };
+                    ((i * 10) as f64, heart_rate)
+                })
+                .collect(),
+        };
+        let (offset, samples) = align(&recording, Some(timeline.start), None);
+        assert_eq!(offset, -10.0);
+        assert_eq!(samples[1].elapsed, 0.0);
+
+        let summary = summarize(&samples, &timeline).unwrap();
+        assert_eq!(summary.max, 155);
+        assert_eq!(summary.end, Some(155));
+        assert_eq!(summary.periods[0], PeriodHeartRate { period: 1, mean: 115.0, max: 125 });
+        assert_eq!(summary.periods[1].max, 155);
+        assert_eq!(
+            summary.recovery,
+            vec![
+                HeartRateRecovery { after: 60, heart_rate: 125, drop: 30 },
+                HeartRateRecovery { after: 120, heart_rate: 95, drop: 60 },
+            ]
+        );
+
+        let (offset, _) = align(&recording, Some(timeline.start), Some(5.0));
+        assert_eq!(offset, 5.0);
+    }
+}
@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+
+use crate::db::PeriodResult;
+use crate::performance::round_to;
+
+// Blood lactate (mmol/L) and RPE (Borg CR-10) taken in the recovery after a
+// period
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PeriodMeasurement {
+    pub period: i32,
+    pub lactate: Option<f32>,
+    pub rpe: Option<f32>,
+}
+
+const MAX_LACTATE: f32 = 30.0;
+const MAX_RPE: f32 = 10.0;
+
+pub fn validate(measurements: &[PeriodMeasurement]) -> Result<(), String> {
+    for (index, measurement) in measurements.iter().enumerate() {
+        let period = measurement.period;
+        if period < 1 {
+            return Err(format!("El periodo {} no es válido", period));
+        }
+        if measurements[..index].iter().any(|m| m.period == period) {
+            return Err(format!("El periodo {} está repetido", period));
+        }
+        if measurement.lactate.is_none() && measurement.rpe.is_none() {
+            return Err(format!("El periodo {} no tiene lactato ni RPE", period));
+        }
+        if let Some(lactate) = measurement.lactate {
+            if !(lactate > 0.0 && lactate <= MAX_LACTATE) {
+                return Err(format!(
+                    "El lactato del periodo {} debe estar entre 0 y {} mmol/L",
+                    period, MAX_LACTATE
+                ));
+            }
+        }
+        if let Some(rpe) = measurement.rpe {
+            if !(0.0..=MAX_RPE).contains(&rpe) {
+                return Err(format!("El RPE del periodo {} debe estar entre 0 y {}", period, MAX_RPE));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Periods of the test with their speed (km/h) and measurements. `speed` is
+// None when the evaluation has no result for the period.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PeriodEntry {
+    pub period: i32,
+    pub speed: Option<f32>,
+    pub lactate: Option<f32>,
+    pub rpe: Option<f32>,
+}
+
+// Speeds (km/h) of the lactate thresholds, None when the curve does not
+// allow them
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct LactateThresholds {
+    // Speed where lactate reaches FIXED_THRESHOLD
+    pub fixed: Option<f32>,
+    // Point of a third order fit of the curve farthest from the line joining
+    // its ends
+    pub dmax: Option<f32>,
+    pub dmax_lactate: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LactateAnalysis {
+    pub evaluation_id: i64,
+    pub periods: Vec<PeriodEntry>,
+    pub peak_lactate: Option<f32>,
+    pub max_rpe: Option<f32>,
+    pub thresholds: LactateThresholds,
+}
+
+pub const FIXED_THRESHOLD: f32 = 4.0;
+
+// First crossing of `threshold`, interpolated between the two measurements
+// around it. None when the first measurement is already above it.
+fn fixed_threshold(points: &[(f64, f64)], threshold: f64) -> Option<f32> {
+    if points.first()?.1 >= threshold {
+        return None;
+    }
+    points.windows(2).find_map(|pair| {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        (y0 < threshold && y1 >= threshold)
+            .then(|| round_to((x0 + (threshold - y0) / (y1 - y0) * (x1 - x0)) as f32, 2))
+    })
+}
+
+// Solves a linear system by Gaussian elimination with partial pivoting
+fn solve<const N: usize>(mut a: [[f64; N]; N], mut b: [f64; N]) -> Option<[f64; N]> {
+    for column in 0..N {
+        let pivot = (column..N).max_by(|i, j| a[*i][column].abs().total_cmp(&a[*j][column].abs()))?;
+        if a[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        let pivot_row = a[column];
+        for row in column + 1..N {
+            let factor = a[row][column] / pivot_row[column];
+            for (value, pivot) in a[row][column..].iter_mut().zip(&pivot_row[column..]) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[column];
+        }
+    }
+    let mut x = [0.0; N];
+    for row in (0..N).rev() {
+        let sum: f64 = (row + 1..N).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+// Least squares cubic `c0 + c1·t + c2·t² + c3·t³`
+fn fit_cubic(points: &[(f64, f64)]) -> Option<[f64; 4]> {
+    let mut a = [[0.0; 4]; 4];
+    let mut b = [0.0; 4];
+    for (t, y) in points {
+        let powers = [1.0, *t, t * t, t * t * t];
+        for i in 0..4 {
+            for j in 0..4 {
+                a[i][j] += powers[i] * powers[j];
+            }
+            b[i] += powers[i] * y;
+        }
+    }
+    solve(a, b)
+}
+
+fn dmax(points: &[(f64, f64)]) -> Option<(f32, f32)> {
+    let mut speeds: Vec<f64> = points.iter().map(|(x, _)| *x).collect();
+    speeds.dedup();
+    if speeds.len() < 4 {
+        return None;
+    }
+
+    // Centred on the first speed to keep the normal equations well scaled
+    let origin = points[0].0;
+    let centred: Vec<(f64, f64)> = points.iter().map(|(x, y)| (x - origin, *y)).collect();
+    let [c0, c1, c2, c3] = fit_cubic(&centred)?;
+    let curve = |t: f64| c0 + c1 * t + c2 * t * t + c3 * t * t * t;
+
+    let end = centred[centred.len() - 1].0;
+    let slope = (curve(end) - curve(0.0)) / end;
+    let distance = |t: f64| curve(0.0) + slope * t - curve(t);
+
+    // The farthest point has the slope of the line: 3·c3·t² + 2·c2·t + c1 = slope
+    let (a, b, c) = (3.0 * c3, 2.0 * c2, c1 - slope);
+    let roots = if a.abs() < 1e-12 {
+        vec![-c / b]
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+    };
+    let t = roots
+        .into_iter()
+        .filter(|t| t.is_finite() && *t > 0.0 && *t < end)
+        .max_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+        // Rounding leaves tiny distances on straight lines
+        .filter(|t| distance(*t) > 1e-6)?;
+    Some((round_to((origin + t) as f32, 2), round_to(curve(t) as f32, 2)))
+}
+
+pub fn thresholds(entries: &[PeriodEntry]) -> LactateThresholds {
+    let mut points: Vec<(f64, f64)> = entries
+        .iter()
+        .filter_map(|entry| Some((entry.speed? as f64, entry.lactate? as f64)))
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let dmax = dmax(&points);
+    LactateThresholds {
+        fixed: fixed_threshold(&points, FIXED_THRESHOLD as f64),
+        dmax: dmax.map(|(speed, _)| speed),
+        dmax_lactate: dmax.map(|(_, lactate)| lactate),
+    }
+}
+
+// Joins the measurements of an evaluation with the speeds of its periods
+pub fn analyze(
+    evaluation_id: i64,
+    period_results: &[PeriodResult],
+    measurements: &[PeriodMeasurement],
+) -> LactateAnalysis {
+    let mut periods: Vec<i32> = period_results
+        .iter()
+        .map(|result| result.period)
+        .chain(measurements.iter().map(|m| m.period))
+        .collect();
+    periods.sort_unstable();
+    periods.dedup();
+
+    let entries: Vec<PeriodEntry> = periods
+        .into_iter()
+        .map(|period| {
+            let measurement = measurements.iter().find(|m| m.period == period);
+            PeriodEntry {
+                period,
+                speed: period_results.iter().find(|r| r.period == period).map(|r| r.speed),
+                lactate: measurement.and_then(|m| m.lactate),
+                rpe: measurement.and_then(|m| m.rpe),
+            }
+        })
+        .collect();
+
+    let max = |values: Vec<f32>| values.into_iter().max_by(f32::total_cmp);
+    LactateAnalysis {
+        evaluation_id,
+        peak_lactate: max(entries.iter().filter_map(|e| e.lactate).collect()),
+        max_rpe: max(entries.iter().filter_map(|e| e.rpe).collect()),
+        thresholds: thresholds(&entries),
+        periods: entries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(speed: f32, lactate: f32) -> PeriodEntry {
+        PeriodEntry { period: 0, speed: Some(speed), lactate: Some(lactate), rpe: None }
+    }
+
+    #[test]
+    fn interpolates_the_fixed_threshold() {
+        let entries = [entry(10.0, 1.5), entry(11.0, 2.5), entry(12.0, 3.5), entry(13.0, 5.5)];
+        assert_eq!(thresholds(&entries).fixed, Some(12.25));
+        // Too few distinct speeds for the cubic fit
+        assert_eq!(thresholds(&entries[..3]).dmax, None);
+        assert_eq!(thresholds(&[entry(10.0, 4.5), entry(11.0, 6.0)]).fixed, None);
+    }
+
+    #[test]
+    fn finds_the_dmax_point() {
+        // y = 1 + (x - 10)³ / 8 between 10 and 14 km/h. The line joining its
+        // ends has slope 2, the farthest point has y' = 2 at x = 10 + 4/√3.
+        let entries: Vec<PeriodEntry> = (0..5)
+            .map(|i| {
+                let t = i as f32;
+                entry(10.0 + t, 1.0 + t * t * t / 8.0)
+            })
+            .collect();
+        let dmax = thresholds(&entries);
+        assert_eq!(dmax.dmax, Some(12.31));
+        assert_eq!(dmax.dmax_lactate, Some(2.54));
+
+        // A straight line has no point away from it
+        let linear: Vec<PeriodEntry> = (0..5).map(|i| entry(10.0 + i as f32, 1.0 + i as f32)).collect();
+        assert_eq!(thresholds(&linear).dmax, None);
+    }
+
+    #[test]
+    fn validates_measurements() {
+        let measurement = |period, lactate, rpe| PeriodMeasurement { period, lactate, rpe };
+        assert!(validate(&[measurement(1, Some(2.0), Some(5.0)), measurement(2, None, Some(7.0))]).is_ok());
+        assert!(validate(&[measurement(1, Some(2.0), None), measurement(1, Some(3.0), None)]).is_err());
+        assert!(validate(&[measurement(1, None, None)]).is_err());
+        assert!(validate(&[measurement(1, Some(0.0), None)]).is_err());
+        assert!(validate(&[measurement(1, None, Some(11.0))]).is_err());
+    }
+}
@@ -1,12 +1,38 @@
 mod audio;
 mod db;
+mod heart_rate;
+mod lactate;
+mod migrations;
 mod models;
+mod norms;
+mod performance;
+mod progress;
+mod protocol;
 mod services;
+mod settings;
+mod statistics;
+mod timing;
+mod training;
+mod warnings;
 
 use audio::ThreadSafeAudioPlayer;
 use db::Database;
-use models::{Athlete, AthleteEvaluation, EvaluationTemplate};
+use heart_rate::HeartRateAnalysis;
+use lactate::{LactateAnalysis, PeriodMeasurement};
+use norms::Norm;
+use progress::AthleteProgress;
+use statistics::SquadStatistics;
+use timing::{Schedule, TestTimer, TimerSettings, TimerSnapshot};
+use training::{TrainingPlan, TrainingZone};
+use warnings::{EliminationRules, Warning, WarningRecord};
+use protocol::{Protocol, ProtocolParameters, TrackGeometry};
+use models::{
+    Anthropometrics, Athlete, AthleteEvaluation, AthleteSortField, EvaluationInput,
+    EvaluationStatus, EvaluationTemplate, GroupFilter, MeasurementPoint, PeriodResult, Session,
+    TestConfiguration, TestJournal,
+};
 use services::evaluation_service::EvaluationService;
+use settings::AppSettings;
 use tauri::Emitter;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -19,8 +45,10 @@ struct AudioState(Arc<ThreadSafeAudioPlayer>);
 struct DbState(Arc<Database>);
 #[allow(dead_code)]
 struct ServiceState(Arc<EvaluationService>);
+struct TimerState(TestTimer);
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn save_evaluation_data(
     state: State<'_, ServiceState>,
     app: tauri::AppHandle,
@@ -28,10 +56,19 @@ async fn save_evaluation_data(
     completed_periods: String,
     total_time: i32,
     total_distance: f32,
-    status: String,
+    status: EvaluationStatus,
+    status_reason: Option<String>,
+    observations: Option<String>,
+    session_id: Option<i64>,
+    config: Option<TestConfiguration>,
+    last_period_elapsed: Option<f32>,
+    measurements: Option<Anthropometrics>,
+    period_results: Option<Vec<PeriodResult>>,
+    warnings: Option<Vec<Warning>>,
 ) -> Result<String, String> {
-    // Validar los datos antes de guardar
-    if athlete.age <= 0 || athlete.age >= 150 {
+    // Validar los datos antes de guardar, la edad se calcula a partir de la
+    // fecha de nacimiento cuando se conoce
+    if athlete.birth_date.is_none() && (athlete.age <= 0 || athlete.age >= 150) {
         return Err("La edad debe estar entre 1 y 149 años".to_string());
     }
     if athlete.weight <= 0.0 {
@@ -41,7 +78,23 @@ async fn save_evaluation_data(
         return Err("La altura debe ser mayor que 0".to_string());
     }
 
-    state.0.save_evaluation(athlete, completed_periods, total_time, total_distance, status)
+    let input = EvaluationInput {
+        athlete,
+        completed_periods,
+        total_time,
+        total_distance,
+        status,
+        status_reason,
+        observations,
+        session_id,
+        config,
+        last_period_elapsed,
+        measurements,
+        period_results: period_results.unwrap_or_default(),
+        warnings: warnings.unwrap_or_default(),
+    };
+
+    state.0.save_evaluation(input)
         .await
         .map(|(athlete_id, template_id, eval_id)| {
             let _ = app.emit("evaluation-completed", ());
@@ -54,12 +107,66 @@ async fn save_evaluation_data(
 async fn get_athlete_evaluations(
     athlete_id: i64,
     state: State<'_, ServiceState>,
-) -> Result<Vec<(AthleteEvaluation, EvaluationTemplate)>, String> {
+) -> Result<Vec<(AthleteEvaluation, EvaluationTemplate, Vec<PeriodResult>)>, String> {
     state.0.get_athlete_evaluations(athlete_id)
         .await
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn get_protocol(
+    protocol_id: Option<String>,
+    version: Option<i32>,
+    geometry: Option<TrackGeometry>,
+    state: State<'_, ServiceState>,
+) -> Result<Protocol, String> {
+    state.0.get_protocol(protocol_id.as_deref(), version, geometry).await
+}
+
+#[tauri::command]
+async fn list_protocols(state: State<'_, ServiceState>) -> Result<Vec<Protocol>, String> {
+    state.0.list_protocols().await
+}
+
+#[tauri::command]
+async fn create_protocol(
+    code: String,
+    name: String,
+    parameters: ProtocolParameters,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<Protocol, String> {
+    let protocol = state.0.create_protocol(code, name, parameters).await?;
+    let _ = app.emit("protocols-updated", ());
+    Ok(protocol)
+}
+
+#[tauri::command]
+async fn update_protocol(
+    code: String,
+    name: String,
+    parameters: ProtocolParameters,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<Protocol, String> {
+    let protocol = state.0.update_protocol(code, name, parameters).await?;
+    let _ = app.emit("protocols-updated", ());
+    Ok(protocol)
+}
+
+#[tauri::command]
+async fn recompute_evaluation(
+    evaluation_id: i64,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<(EvaluationTemplate, Vec<PeriodResult>), String> {
+    let result = state.0.recompute_evaluation(evaluation_id).await;
+    if result.is_ok() {
+        let _ = app.emit("evaluation-completed", ());
+    }
+    result
+}
+
 #[tauri::command]
 async fn play_sound(sound_type: String, state: State<'_, AudioState>) -> Result<(), String> {
     state.0.play_sound(&sound_type).map_err(|e| e.to_string())
@@ -70,6 +177,102 @@ async fn stop_all_sounds(state: State<'_, AudioState>) -> Result<(), String> {
     state.0.stop_all().map_err(|e| e.to_string())
 }
 
+// Starts the test clock: the preparation countdown, then every period of
+// the protocol from `settings.start_period` with its recovery
+#[tauri::command]
+async fn start_test_timer(
+    protocol_id: Option<String>,
+    version: Option<i32>,
+    geometry: Option<TrackGeometry>,
+    settings: TimerSettings,
+    service: State<'_, ServiceState>,
+    timer: State<'_, TimerState>,
+) -> Result<TimerSnapshot, String> {
+    let protocol = service.0.get_protocol(protocol_id.as_deref(), version, geometry).await?;
+    let schedule = Schedule::new(&protocol, &settings)?;
+    service.0.start_test_journal(&protocol, settings.start_period, settings.recovery_time).await?;
+    Ok(timer.0.start(schedule))
+}
+
+// Saves the UI state of the test in progress with the clock time it matches
+#[tauri::command]
+async fn update_test_journal(
+    elapsed: f32,
+    state: serde_json::Value,
+    service: State<'_, ServiceState>,
+) -> Result<(), String> {
+    service.0.update_test_journal(elapsed, state).await
+}
+
+#[tauri::command]
+async fn get_test_journal(service: State<'_, ServiceState>) -> Result<Option<TestJournal>, String> {
+    service.0.get_test_journal().await
+}
+
+// Restores the journaled test, with the clock paused where it was saved
+#[tauri::command]
+async fn recover_test(
+    service: State<'_, ServiceState>,
+    timer: State<'_, TimerState>,
+) -> Result<(TimerSnapshot, TestJournal), String> {
+    let journal = service.0.get_test_journal().await?
+        .ok_or("No hay ningún test que recuperar")?;
+    let protocol = service.0.get_protocol(
+        Some(&journal.protocol_id),
+        Some(journal.protocol_version),
+        Some(journal.geometry),
+    ).await?;
+    let schedule = Schedule::new(&protocol, &TimerSettings {
+        start_period: journal.start_period,
+        recovery_time: journal.recovery_time,
+    })?;
+    Ok((timer.0.restore(schedule, journal.elapsed), journal))
+}
+
+#[tauri::command]
+async fn discard_test_journal(service: State<'_, ServiceState>) -> Result<(), String> {
+    service.0.discard_test_journal().await
+}
+
+#[tauri::command]
+async fn pause_test_timer(timer: State<'_, TimerState>) -> Result<TimerSnapshot, String> {
+    timer.0.pause()
+}
+
+#[tauri::command]
+async fn resume_test_timer(timer: State<'_, TimerState>) -> Result<TimerSnapshot, String> {
+    timer.0.resume()
+}
+
+#[tauri::command]
+async fn stop_test_timer(timer: State<'_, TimerState>) -> Result<TimerSnapshot, String> {
+    timer.0.stop()
+}
+
+#[tauri::command]
+async fn get_test_timer(timer: State<'_, TimerState>) -> Result<Option<TimerSnapshot>, String> {
+    Ok(timer.0.snapshot())
+}
+
+// Warns an athlete who missed the last beep. `warnings` are the ones the
+// athlete already has in this test.
+#[tauri::command]
+async fn record_warning(
+    warnings: Vec<Warning>,
+    rules: Option<EliminationRules>,
+    timer: State<'_, TimerState>,
+) -> Result<WarningRecord, String> {
+    timer.0.record_warning(warnings, &rules.unwrap_or_default())
+}
+
+#[tauri::command]
+async fn get_evaluation_warnings(
+    evaluation_id: i64,
+    state: State<'_, ServiceState>,
+) -> Result<Vec<Warning>, String> {
+    state.0.get_warnings(evaluation_id).await
+}
+
 #[tauri::command]
 async fn export_all_evaluations(
     path: PathBuf,
@@ -100,18 +303,135 @@ async fn get_all_evaluations(
     state.0.get_all_evaluations().await
 }
 
+#[tauri::command]
+async fn import_norms(
+    path: PathBuf,
+    replace: Option<bool>,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<usize, String> {
+    let count = state.0.import_norms(path, replace.unwrap_or(false)).await?;
+    let _ = app.emit("norms-updated", ());
+    Ok(count)
+}
+
+#[tauri::command]
+async fn list_norms(
+    state: State<'_, ServiceState>,
+) -> Result<Vec<Norm>, String> {
+    state.0.list_norms().await
+}
+
+#[tauri::command]
+async fn delete_norm(
+    norm_id: i64,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    state.0.delete_norm(norm_id).await?;
+    let _ = app.emit("norms-updated", ());
+    Ok(())
+}
+
 #[tauri::command]
 async fn update_evaluation_observations(
     evaluation_id: i64,
     observations: String,
+    author: Option<String>,
     state: State<'_, ServiceState>,
 ) -> Result<String, String> {
-    state.0.update_evaluation_observations(evaluation_id, observations)
+    state.0.update_evaluation_observations(evaluation_id, observations, author)
         .await
         .map(|_| "Observaciones actualizadas exitosamente".to_string())
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn get_training_zones(
+    athlete_id: i64,
+    geometry: Option<TrackGeometry>,
+    zones: Option<Vec<TrainingZone>>,
+    state: State<'_, ServiceState>,
+) -> Result<TrainingPlan, String> {
+    let mut plans = state.0.get_training_plans(&[athlete_id], geometry, zones).await?;
+    plans.pop().ok_or_else(|| "No se pudieron calcular las zonas".to_string())
+}
+
+#[tauri::command]
+async fn get_default_training_zones() -> Vec<TrainingZone> {
+    TrainingZone::defaults()
+}
+
+#[tauri::command]
+async fn export_training_zones(
+    athlete_ids: Vec<i64>,
+    geometry: Option<TrackGeometry>,
+    zones: Option<Vec<TrainingZone>>,
+    path: PathBuf,
+    state: State<'_, ServiceState>,
+) -> Result<String, String> {
+    state.0.export_training_plans(&athlete_ids, geometry, zones, path)
+        .await
+        .map(|_| "Zonas de entrenamiento exportadas exitosamente".to_string())
+}
+
+#[tauri::command]
+async fn export_training_zones_to_xlsx(
+    athlete_ids: Vec<i64>,
+    geometry: Option<TrackGeometry>,
+    zones: Option<Vec<TrainingZone>>,
+    path: PathBuf,
+    state: State<'_, ServiceState>,
+) -> Result<String, String> {
+    state.0.export_training_plans_to_xlsx(&athlete_ids, geometry, zones, path)
+        .await
+        .map(|_| "Zonas de entrenamiento exportadas exitosamente a Excel".to_string())
+}
+
+#[tauri::command]
+async fn import_heart_rate(
+    evaluation_id: i64,
+    path: PathBuf,
+    offset: Option<f32>,
+    state: State<'_, ServiceState>,
+) -> Result<HeartRateAnalysis, String> {
+    state.0.import_heart_rate(evaluation_id, path, offset).await
+}
+
+#[tauri::command]
+async fn get_heart_rate(
+    evaluation_id: i64,
+    state: State<'_, ServiceState>,
+) -> Result<Option<HeartRateAnalysis>, String> {
+    state.0.get_heart_rate(evaluation_id).await
+}
+
+#[tauri::command]
+async fn delete_heart_rate(
+    evaluation_id: i64,
+    state: State<'_, ServiceState>,
+) -> Result<String, String> {
+    state.0.delete_heart_rate(evaluation_id).await?;
+    Ok("Registro de frecuencia cardíaca eliminado exitosamente".to_string())
+}
+
+#[tauri::command]
+async fn save_period_measurements(
+    evaluation_id: i64,
+    measurements: Vec<PeriodMeasurement>,
+    state: State<'_, ServiceState>,
+) -> Result<LactateAnalysis, String> {
+    state.0.save_period_measurements(evaluation_id, measurements).await
+}
+
+#[tauri::command]
+async fn get_period_measurements(
+    evaluation_id: i64,
+    state: State<'_, ServiceState>,
+) -> Result<LactateAnalysis, String> {
+    state.0.get_period_measurements(evaluation_id).await
+}
+
 #[tauri::command]
 async fn export_all_evaluations_to_xlsx(
     path: PathBuf,
@@ -139,8 +459,20 @@ async fn export_athlete_evaluations_to_xlsx(
 async fn save_batch_evaluations(
     state: State<'_, ServiceState>,
     app: tauri::AppHandle,
-    evaluations: Vec<(Athlete, String, i32, f32, String)>,
+    evaluations: Vec<EvaluationInput>,
+    session_id: Option<i64>,
+    config: Option<TestConfiguration>,
 ) -> Result<Vec<(i64, i64, i64)>, String> {
+    // A batch is a group tested together, so it belongs to one session and
+    // was run with one configuration
+    let evaluations = evaluations
+        .into_iter()
+        .map(|input| EvaluationInput {
+            session_id: input.session_id.or(session_id),
+            config: input.config.or_else(|| config.clone()),
+            ..input
+        })
+        .collect();
     let result = state.0.save_batch_evaluations(evaluations).await;
     if result.is_ok() {
         let _ = app.emit("evaluation-completed", ());
@@ -148,17 +480,189 @@ async fn save_batch_evaluations(
     result
 }
 
+#[tauri::command]
+async fn create_session(
+    session: Session,
+    state: State<'_, ServiceState>,
+) -> Result<i64, String> {
+    state.0.create_session(session).await
+}
+
+#[tauri::command]
+async fn list_sessions(
+    state: State<'_, ServiceState>,
+) -> Result<Vec<(Session, i64)>, String> {
+    state.0.list_sessions().await
+}
+
+#[tauri::command]
+async fn get_session_results(
+    session_id: i64,
+    state: State<'_, ServiceState>,
+) -> Result<(Session, Vec<(AthleteEvaluation, EvaluationTemplate, Athlete)>), String> {
+    state.0.get_session_results(session_id).await
+}
+
+#[tauri::command]
+async fn get_session_statistics(
+    session_id: i64,
+    state: State<'_, ServiceState>,
+) -> Result<(Session, SquadStatistics), String> {
+    state.0.get_session_statistics(session_id).await
+}
+
+#[tauri::command]
+async fn get_group_statistics(
+    filter: Option<GroupFilter>,
+    state: State<'_, ServiceState>,
+) -> Result<SquadStatistics, String> {
+    state.0.get_group_statistics(filter.unwrap_or_default()).await
+}
+
+#[tauri::command]
+async fn export_session_evaluations(
+    session_id: i64,
+    path: PathBuf,
+    state: State<'_, ServiceState>,
+) -> Result<String, String> {
+    state.0.export_session_evaluations(session_id, path)
+        .await
+        .map(|_| "Evaluaciones de la sesión exportadas exitosamente".to_string())
+}
+
+#[tauri::command]
+async fn export_session_evaluations_to_xlsx(
+    session_id: i64,
+    path: PathBuf,
+    state: State<'_, ServiceState>,
+) -> Result<String, String> {
+    state.0.export_session_evaluations_to_xlsx(session_id, path)
+        .await
+        .map(|_| "Evaluaciones de la sesión exportadas exitosamente a Excel".to_string())
+}
+
+#[tauri::command]
+async fn create_athlete(
+    athlete: Athlete,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<i64, String> {
+    let athlete_id = state.0.create_athlete(athlete).await?;
+    let _ = app.emit("athletes-updated", ());
+    Ok(athlete_id)
+}
+
+#[tauri::command]
+async fn update_athlete(
+    athlete: Athlete,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    state.0.update_athlete(athlete).await?;
+    let _ = app.emit("athletes-updated", ());
+    Ok("Atleta actualizado exitosamente".to_string())
+}
+
+#[tauri::command]
+async fn get_athlete(
+    athlete_id: i64,
+    state: State<'_, ServiceState>,
+) -> Result<Athlete, String> {
+    state.0.get_athlete(athlete_id).await
+}
+
+#[tauri::command]
+async fn list_athletes(
+    search: Option<String>,
+    sort_by: Option<AthleteSortField>,
+    descending: Option<bool>,
+    state: State<'_, ServiceState>,
+) -> Result<Vec<Athlete>, String> {
+    state.0.list_athletes(search, sort_by.unwrap_or_default(), descending.unwrap_or(false))
+        .await
+}
+
+#[tauri::command]
+async fn get_anthropometric_trend(
+    athlete_id: i64,
+    from: Option<String>,
+    to: Option<String>,
+    state: State<'_, ServiceState>,
+) -> Result<Vec<MeasurementPoint>, String> {
+    state.0.get_anthropometric_trend(athlete_id, from, to).await
+}
+
+#[tauri::command]
+async fn get_athlete_progress(
+    athlete_id: i64,
+    state: State<'_, ServiceState>,
+) -> Result<AthleteProgress, String> {
+    state.0.get_athlete_progress(athlete_id).await
+}
+
+#[tauri::command]
+async fn delete_athlete(
+    athlete_id: i64,
+    cascade: Option<bool>,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    state.0.delete_athlete(athlete_id, cascade.unwrap_or(false)).await?;
+    let _ = app.emit("athletes-updated", ());
+    Ok("Atleta eliminado exitosamente".to_string())
+}
+
+#[tauri::command]
+async fn get_database_path(state: State<'_, DbState>) -> Result<String, String> {
+    Ok(state.0.path().display().to_string())
+}
+
+// The data is carried over to the new location before it is saved in the
+// settings. Returns the path the app will use from the next start.
+#[tauri::command]
+async fn set_database_path(
+    path: Option<PathBuf>,
+    state: State<'_, ServiceState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let mut app_settings = AppSettings::load(&config_dir);
+    app_settings.database_path = path;
+    let target = settings::resolve_database_path(&app_settings, &data_dir);
+    if app_settings.database_path.is_none() {
+        std::fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    }
+
+    state.0.relocate_database(&target).await?;
+    app_settings.save(&config_dir).map_err(|e| e.to_string())?;
+    Ok(target.display().to_string())
+}
+
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_sql::Builder::default().build())
         .setup(|app| {
             let app_handle = app.handle().clone();
-            let audio_player = ThreadSafeAudioPlayer::new(app_handle.clone());
-            let database = Arc::new(Database::new().expect("Failed to initialize database"));
+            let audio_player = Arc::new(ThreadSafeAudioPlayer::new(app_handle.clone()));
+            let app_settings = AppSettings::load(&app.path().app_config_dir()?);
+            let db_path = settings::resolve_database_path(&app_settings, &app.path().app_data_dir()?);
+            // Without a readable working directory there is no legacy database
+            if let Ok(legacy_dir) = std::env::current_dir() {
+                if let Err(e) = settings::migrate_legacy_database(&legacy_dir, &db_path) {
+                    eprintln!("Error moving legacy database: {}", e);
+                }
+            }
+            if let Some(parent) = db_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let database = Arc::new(Database::new(&db_path).expect("Failed to initialize database"));
             let evaluation_service = Arc::new(EvaluationService::new(database.clone()));
 
-            app.manage(AudioState(Arc::new(audio_player)));
+            app.manage(TimerState(TestTimer::new(app_handle.clone(), audio_player.clone())));
+            app.manage(AudioState(audio_player));
             app.manage(DbState(database));
             app.manage(ServiceState(evaluation_service));
             Ok(())
@@ -166,15 +670,59 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             play_sound,
             stop_all_sounds,
+            start_test_timer,
+            pause_test_timer,
+            resume_test_timer,
+            stop_test_timer,
+            get_test_timer,
+            record_warning,
+            get_evaluation_warnings,
+            update_test_journal,
+            get_test_journal,
+            recover_test,
+            discard_test_journal,
             save_evaluation_data,
             get_athlete_evaluations,
             get_all_evaluations,
+            import_norms,
+            list_norms,
+            delete_norm,
             export_all_evaluations,
             export_athlete_evaluations,
             export_all_evaluations_to_xlsx,
             export_athlete_evaluations_to_xlsx,
             update_evaluation_observations,
             save_batch_evaluations,
+            create_athlete,
+            update_athlete,
+            get_athlete,
+            list_athletes,
+            get_anthropometric_trend,
+            get_athlete_progress,
+            delete_athlete,
+            get_database_path,
+            set_database_path,
+            create_session,
+            list_sessions,
+            get_session_results,
+            get_session_statistics,
+            get_group_statistics,
+            import_heart_rate,
+            get_heart_rate,
+            delete_heart_rate,
+            save_period_measurements,
+            get_period_measurements,
+            get_training_zones,
+            get_default_training_zones,
+            export_training_zones,
+            export_training_zones_to_xlsx,
+            export_session_evaluations,
+            export_session_evaluations_to_xlsx,
+            get_protocol,
+            list_protocols,
+            create_protocol,
+            update_protocol,
+            recompute_evaluation,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,927 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+// Schema migrations are applied in order and tracked with `PRAGMA user_version`.
+// Each migration runs in its own transaction together with the version bump,
+// so a failure leaves the database at the last successfully applied version.
+// Foreign keys are disabled while migrating so tables can be rebuilt (SQLite
+// ignores that pragma inside a transaction).
+//
+// Databases created before this framework existed report version 0, so the
+// first migrations must be idempotent: they bring every historical schema up
+// to the same state. Migrations added afterwards can rely on that state.
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create base tables",
+        up: create_base_tables,
+    },
+    Migration {
+        version: 2,
+        description: "Move legacy evaluations into templates and athlete evaluations",
+        up: migrate_legacy_evaluations,
+    },
+    Migration {
+        version: 3,
+        description: "Add observations to athletes",
+        up: add_athlete_observations,
+    },
+    Migration {
+        version: 4,
+        description: "Add total distance to evaluation templates",
+        up: add_template_total_distance,
+    },
+    Migration {
+        version: 5,
+        description: "Create period results",
+        up: create_period_results,
+    },
+    Migration {
+        version: 6,
+        description: "Extend evaluation statuses and add status reason",
+        up: extend_evaluation_statuses,
+    },
+    Migration {
+        version: 7,
+        description: "Add observations to athlete evaluations",
+        up: add_evaluation_observations,
+    },
+    Migration {
+        version: 8,
+        description: "Create sessions",
+        up: create_sessions,
+    },
+    Migration {
+        version: 9,
+        description: "Add test configuration to evaluation templates",
+        up: add_template_configuration,
+    },
+    Migration {
+        version: 10,
+        description: "Create custom protocols",
+        up: create_protocols,
+    },
+    Migration {
+        version: 11,
+        description: "Add track geometry to sessions and templates",
+        up: add_track_geometry,
+    },
+    Migration {
+        version: 12,
+        description: "Keep the measured distance of evaluations",
+        up: add_measured_distance,
+    },
+    Migration {
+        version: 13,
+        description: "Add athlete profile and age at evaluation",
+        up: add_athlete_profile,
+    },
+    Migration {
+        version: 14,
+        description: "Record anthropometrics with each evaluation",
+        up: add_evaluation_anthropometrics,
+    },
+    Migration {
+        version: 15,
+        description: "Estimate the aerobic capacity of every evaluation",
+        up: add_aerobic_estimate,
+    },
+    Migration {
+        version: 16,
+        description: "Create normative tables",
+        up: create_norms,
+    },
+    Migration {
+        version: 17,
+        description: "Store heart rate recordings of evaluations",
+        up: create_heart_rate,
+    },
+    Migration {
+        version: 18,
+        description: "Store lactate and RPE taken after each period",
+        up: create_period_measurements,
+    },
+    Migration {
+        version: 19,
+        description: "Journal the test in progress",
+        up: create_test_journal,
+    },
+    Migration {
+        version: 20,
+        description: "Store warnings and elimination rules with evaluations",
+        up: create_evaluation_warnings,
+    },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+pub fn run(conn: &mut Connection) -> Result<()> {
+    let current = current_version(conn)?;
+    if current > latest_version() {
+        return Err(rusqlite::Error::InvalidParameterName(format!(
+            "Database schema version {} is newer than supported version {}",
+            current,
+            latest_version()
+        )));
+    }
+
+    // Table rebuilds would trip the foreign keys half way, so they are only
+    // enforced once the schema is up to date. They stay on for the
+    // connection, deletes have to remove dependent rows first.
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let result = apply_pending(conn, current);
+    conn.pragma_update(None, "foreign_keys", true)?;
+    result
+}
+
+fn apply_pending(conn: &mut Connection, current: i64) -> Result<()> {
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx).map_err(|e| {
+            eprintln!(
+                "Migration {} ({}) failed: {}",
+                migration.version, migration.description, e
+            );
+            e
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+pub fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )
+}
+
+pub fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name?.eq_ignore_ascii_case(column) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn create_base_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS athletes (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL CHECK (age > 0 AND age < 150),
+            weight REAL NOT NULL CHECK (weight > 0),
+            height REAL NOT NULL CHECK (height > 0)
+        );
+
+        CREATE TABLE IF NOT EXISTS evaluation_templates (
+            id INTEGER PRIMARY KEY,
+            completed_periods TEXT NOT NULL,
+            total_time INTEGER NOT NULL,
+            date TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS athlete_evaluations (
+            id INTEGER PRIMARY KEY,
+            athlete_id INTEGER NOT NULL,
+            template_id INTEGER NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('completed', 'cancelled')),
+            date TEXT NOT NULL,
+            FOREIGN KEY (athlete_id) REFERENCES athletes (id),
+            FOREIGN KEY (template_id) REFERENCES evaluation_templates (id)
+        );",
+    )
+}
+
+fn migrate_legacy_evaluations(conn: &Connection) -> Result<()> {
+    if !table_exists(conn, "evaluations")? {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO evaluation_templates (completed_periods, total_time, date)
+         SELECT DISTINCT completed_periods, total_time, date
+         FROM evaluations",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT INTO athlete_evaluations (athlete_id, template_id, status, date)
+         SELECT
+             oe.athlete_id,
+             et.id,
+             oe.status,
+             oe.date
+         FROM evaluations oe
+         JOIN evaluation_templates et
+             ON et.completed_periods = oe.completed_periods
+             AND et.total_time = oe.total_time
+             AND et.date = oe.date",
+        [],
+    )?;
+
+    conn.execute("DROP TABLE evaluations", [])?;
+    Ok(())
+}
+
+fn add_athlete_observations(conn: &Connection) -> Result<()> {
+    if !has_column(conn, "athletes", "observations")? {
+        conn.execute("ALTER TABLE athletes ADD COLUMN observations TEXT", [])?;
+    }
+    Ok(())
+}
+
+fn add_template_total_distance(conn: &Connection) -> Result<()> {
+    if !has_column(conn, "evaluation_templates", "total_distance")? {
+        conn.execute(
+            "ALTER TABLE evaluation_templates ADD COLUMN total_distance REAL NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn create_period_results(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE period_results (
+            id INTEGER PRIMARY KEY,
+            evaluation_id INTEGER NOT NULL,
+            period INTEGER NOT NULL CHECK (period > 0),
+            speed REAL NOT NULL,
+            lap_time REAL NOT NULL,
+            cumulative_distance REAL NOT NULL,
+            recorded_at TEXT NOT NULL,
+            completed INTEGER NOT NULL CHECK (completed IN (0, 1)),
+            FOREIGN KEY (evaluation_id) REFERENCES athlete_evaluations (id)
+        );
+
+        CREATE INDEX idx_period_results_evaluation ON period_results (evaluation_id);",
+    )
+}
+
+// SQLite cannot alter a CHECK constraint, so the table is rebuilt. Statuses
+// outside the known set (only possible in hand-edited databases) become
+// `cancelled`.
+fn extend_evaluation_statuses(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE athlete_evaluations_new (
+            id INTEGER PRIMARY KEY,
+            athlete_id INTEGER NOT NULL,
+            template_id INTEGER NOT NULL,
+            status TEXT NOT NULL CHECK (status IN (
+                'completed', 'voluntary_stop', 'eliminated_by_warnings', 'injury',
+                'equipment_failure', 'disqualified', 'cancelled'
+            )),
+            status_reason TEXT,
+            date TEXT NOT NULL,
+            FOREIGN KEY (athlete_id) REFERENCES athletes (id),
+            FOREIGN KEY (template_id) REFERENCES evaluation_templates (id)
+        );
+
+        INSERT INTO athlete_evaluations_new (id, athlete_id, template_id, status, date)
+        SELECT id, athlete_id, template_id,
+               CASE WHEN status = 'completed' THEN 'completed' ELSE 'cancelled' END,
+               date
+        FROM athlete_evaluations;
+
+        DROP TABLE athlete_evaluations;
+        ALTER TABLE athlete_evaluations_new RENAME TO athlete_evaluations;",
+    )
+}
+
+fn add_evaluation_observations(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE athlete_evaluations ADD COLUMN observations TEXT;
+        ALTER TABLE athlete_evaluations ADD COLUMN observations_author TEXT;
+        ALTER TABLE athlete_evaluations ADD COLUMN observations_updated_at TEXT;",
+    )
+}
+
+fn create_sessions(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE sessions (
+            id INTEGER PRIMARY KEY,
+            date TEXT NOT NULL,
+            venue TEXT,
+            surface TEXT,
+            temperature REAL,
+            humidity REAL CHECK (humidity IS NULL OR (humidity >= 0 AND humidity <= 100)),
+            evaluator TEXT,
+            recovery_time INTEGER CHECK (recovery_time IS NULL OR recovery_time >= 0),
+            notes TEXT
+        );
+
+        ALTER TABLE athlete_evaluations ADD COLUMN session_id INTEGER REFERENCES sessions (id);
+
+        CREATE INDEX idx_athlete_evaluations_session ON athlete_evaluations (session_id);",
+    )
+}
+
+// Evaluations recorded before this migration were all run with the standard
+// protocol on a 200 m track, their recovery time and start period are unknown.
+fn add_template_configuration(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE evaluation_templates ADD COLUMN recovery_time INTEGER
+            CHECK (recovery_time IS NULL OR recovery_time >= 0);
+        ALTER TABLE evaluation_templates ADD COLUMN start_period INTEGER
+            CHECK (start_period IS NULL OR start_period > 0);
+        ALTER TABLE evaluation_templates ADD COLUMN protocol_id TEXT NOT NULL DEFAULT 'standard';
+        ALTER TABLE evaluation_templates ADD COLUMN protocol_version INTEGER NOT NULL DEFAULT 1
+            CHECK (protocol_version > 0);
+        ALTER TABLE evaluation_templates ADD COLUMN track_length REAL NOT NULL DEFAULT 200
+            CHECK (track_length > 0);",
+    )
+}
+
+// Only the parameters are stored, the period table is generated from them.
+// Editing a protocol adds a new version so evaluations keep referencing the
+// table they were run with.
+fn create_protocols(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE protocols (
+            id INTEGER PRIMARY KEY,
+            code TEXT NOT NULL,
+            version INTEGER NOT NULL CHECK (version > 0),
+            name TEXT NOT NULL,
+            start_period INTEGER NOT NULL CHECK (start_period > 0),
+            period_count INTEGER NOT NULL CHECK (period_count > 0),
+            start_speed REAL NOT NULL CHECK (start_speed > 0),
+            speed_increment REAL NOT NULL CHECK (speed_increment > 0),
+            laps_per_period INTEGER NOT NULL CHECK (laps_per_period > 0),
+            track_length REAL NOT NULL CHECK (track_length > 0),
+            segments_per_lap INTEGER NOT NULL CHECK (segments_per_lap > 0),
+            created_at TEXT NOT NULL,
+            UNIQUE (code, version)
+        );",
+    )
+}
+
+// Existing evaluations were run on four 50 m segments per lap
+fn add_track_geometry(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE evaluation_templates ADD COLUMN segments_per_lap INTEGER NOT NULL DEFAULT 4
+            CHECK (segments_per_lap > 0);
+        ALTER TABLE sessions ADD COLUMN track_length REAL CHECK (track_length IS NULL OR track_length > 0);
+        ALTER TABLE sessions ADD COLUMN segments_per_lap INTEGER
+            CHECK (segments_per_lap IS NULL OR segments_per_lap > 0);",
+    )
+}
+
+// Distances stored so far were measured by the client
+fn add_measured_distance(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE evaluation_templates ADD COLUMN measured_distance REAL;
+        ALTER TABLE evaluation_templates ADD COLUMN distance_flagged INTEGER NOT NULL DEFAULT 0;
+        UPDATE evaluation_templates SET measured_distance = total_distance;",
+    )
+}
+
+// Only the static age is known for existing evaluations, it is the best
+// estimate of the age they were run at
+fn add_athlete_profile(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE athletes ADD COLUMN birth_date TEXT;
+        ALTER TABLE athletes ADD COLUMN sex TEXT CHECK (sex IS NULL OR sex IN ('female', 'male'));
+        ALTER TABLE athletes ADD COLUMN discipline TEXT
+            CHECK (discipline IS NULL OR discipline IN ('inline_speed', 'quad', 'artistic', 'hockey'));
+        ALTER TABLE athletes ADD COLUMN category TEXT;
+        ALTER TABLE athletes ADD COLUMN club TEXT;
+        ALTER TABLE athletes ADD COLUMN license_number TEXT;
+        CREATE UNIQUE INDEX idx_athletes_license_number ON athletes (license_number)
+            WHERE license_number IS NOT NULL;
+        ALTER TABLE athlete_evaluations ADD COLUMN athlete_age INTEGER;
+        UPDATE athlete_evaluations
+            SET athlete_age = (SELECT age FROM athletes WHERE athletes.id = athlete_evaluations.athlete_id);",
+    )
+}
+
+// Earlier evaluations get the measurements currently stored for the athlete,
+// the only ones known
+fn add_evaluation_anthropometrics(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE athlete_evaluations ADD COLUMN weight REAL CHECK (weight IS NULL OR weight > 0);
+        ALTER TABLE athlete_evaluations ADD COLUMN height REAL CHECK (height IS NULL OR height > 0);
+        ALTER TABLE athlete_evaluations ADD COLUMN body_fat REAL
+            CHECK (body_fat IS NULL OR (body_fat > 0 AND body_fat < 100));
+        ALTER TABLE athlete_evaluations ADD COLUMN leg_length REAL
+            CHECK (leg_length IS NULL OR leg_length > 0);
+        UPDATE athlete_evaluations
+            SET weight = (SELECT weight FROM athletes WHERE athletes.id = athlete_evaluations.athlete_id),
+                height = (SELECT height FROM athletes WHERE athletes.id = athlete_evaluations.athlete_id);",
+    )
+}
+
+// Period speeds of the protocol a stored evaluation was run with, None when
+// its protocol is no longer known. Speeds grow linearly from the first
+// period, in km/h.
+#[derive(Clone, Copy)]
+struct StoredSpeeds {
+    start_period: i32,
+    period_count: i32,
+    start_speed: f32,
+    speed_increment: f32,
+    laps_per_period: i32,
+}
+
+impl StoredSpeeds {
+    fn speed(&self, period: i32) -> Option<f32> {
+        let index = period - self.start_period;
+        (0..self.period_count)
+            .contains(&index)
+            .then(|| ((self.start_speed + self.speed_increment * index as f32) * 100.0).round() / 100.0)
+    }
+}
+
+fn stored_speeds(conn: &Connection, code: &str, version: i32) -> Result<Option<StoredSpeeds>> {
+    // Periods 2 to 35 of the standard table
+    if code == "standard" && version == 1 {
+        return Ok(Some(StoredSpeeds {
+            start_period: 2,
+            period_count: 34,
+            start_speed: 20.8,
+            speed_increment: 0.8,
+            laps_per_period: 4,
+        }));
+    }
+    conn.query_row(
+        "SELECT start_period, period_count, start_speed, speed_increment, laps_per_period
+         FROM protocols WHERE code = ?1 AND version = ?2",
+        params![code, version],
+        |row| {
+            Ok(StoredSpeeds {
+                start_period: row.get(0)?,
+                period_count: row.get(1)?,
+                start_speed: row.get(2)?,
+                speed_increment: row.get(3)?,
+                laps_per_period: row.get(4)?,
+            })
+        },
+    )
+    .optional()
+}
+
+// Existing evaluations are estimated as the app did when the columns were
+// added, so later changes to the protocols or to the estimate do not alter
+// this migration: the maximal aerobic speed adds the fraction of the next
+// period covered to the speed of the last completed one, and VO2max is
+// 3.5 · MAS. Those whose periods or protocol cannot be read, or that did not
+// complete a period, are left without an estimate.
+fn add_aerobic_estimate(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE evaluation_templates ADD COLUMN vo2max_equation TEXT NOT NULL
+            DEFAULT '{\"kind\":\"leger_mercier\"}';
+        ALTER TABLE evaluation_templates ADD COLUMN final_speed REAL;
+        ALTER TABLE evaluation_templates ADD COLUMN mas REAL;
+        ALTER TABLE evaluation_templates ADD COLUMN vo2max REAL;",
+    )?;
+
+    let templates: Vec<(i64, String, f32, String, i32, f32)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, completed_periods, total_distance, protocol_id, protocol_version, track_length
+             FROM evaluation_templates",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+        })?;
+        rows.collect::<Result<_>>()?
+    };
+
+    for (id, completed_periods, total_distance, code, version, track_length) in templates {
+        let Ok(completed_periods) = serde_json::from_str::<Vec<i32>>(&completed_periods) else {
+            continue;
+        };
+        let Some(speeds) = stored_speeds(conn, &code, version)? else {
+            continue;
+        };
+        let Some(last) = completed_periods.last() else {
+            continue;
+        };
+        let Some(final_speed) = speeds.speed(*last) else {
+            continue;
+        };
+
+        let period_distance = track_length * speeds.laps_per_period as f32;
+        let partial_distance = total_distance - completed_periods.len() as f32 * period_distance;
+        let mas = match speeds.speed(last + 1) {
+            Some(next_speed) if partial_distance > 0.0 => {
+                let fraction = (partial_distance / period_distance).min(1.0);
+                final_speed + fraction * (next_speed - final_speed)
+            }
+            _ => final_speed,
+        };
+
+        conn.execute(
+            "UPDATE evaluation_templates SET final_speed = ?1, mas = ?2, vo2max = ?3 WHERE id = ?4",
+            params![
+                final_speed,
+                (mas * 100.0).round() / 100.0,
+                (3.5 * mas * 10.0).round() / 10.0,
+                id,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn create_norms(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE norms (
+            id INTEGER PRIMARY KEY,
+            metric TEXT NOT NULL CHECK (metric IN ('total_distance', 'mas', 'vo2max')),
+            sex TEXT CHECK (sex IS NULL OR sex IN ('female', 'male')),
+            discipline TEXT
+                CHECK (discipline IS NULL OR discipline IN ('inline_speed', 'quad', 'artistic', 'hockey')),
+            age_min INTEGER NOT NULL CHECK (age_min >= 0),
+            age_max INTEGER NOT NULL CHECK (age_max >= age_min),
+            p10 REAL NOT NULL,
+            p25 REAL NOT NULL,
+            p50 REAL NOT NULL,
+            p75 REAL NOT NULL,
+            p90 REAL NOT NULL,
+            source TEXT
+        );
+        CREATE INDEX idx_norms_metric ON norms (metric);",
+    )
+}
+
+// One recording per evaluation. Samples are stored aligned to the start of
+// the test; `start_offset` is where the recording started on that timeline.
+fn create_heart_rate(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE heart_rate_recordings (
+            evaluation_id INTEGER PRIMARY KEY,
+            source TEXT NOT NULL,
+            format TEXT NOT NULL CHECK (format IN ('csv', 'tcx', 'fit')),
+            start_offset REAL NOT NULL,
+            imported_at TEXT NOT NULL,
+            FOREIGN KEY (evaluation_id) REFERENCES athlete_evaluations (id)
+        );
+
+        CREATE TABLE heart_rate_samples (
+            evaluation_id INTEGER NOT NULL,
+            elapsed REAL NOT NULL,
+            heart_rate INTEGER NOT NULL CHECK (heart_rate BETWEEN 20 AND 250),
+            PRIMARY KEY (evaluation_id, elapsed),
+            FOREIGN KEY (evaluation_id) REFERENCES heart_rate_recordings (evaluation_id)
+        );",
+    )
+}
+
+fn create_period_measurements(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE period_measurements (
+            evaluation_id INTEGER NOT NULL,
+            period INTEGER NOT NULL,
+            lactate REAL CHECK (lactate > 0 AND lactate <= 30),
+            rpe REAL CHECK (rpe BETWEEN 0 AND 10),
+            PRIMARY KEY (evaluation_id, period),
+            FOREIGN KEY (evaluation_id) REFERENCES athlete_evaluations (id),
+            CHECK (lactate IS NOT NULL OR rpe IS NOT NULL)
+        );",
+    )
+}
+
+// A single row, there is at most one test in progress
+fn create_test_journal(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE test_journal (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            protocol_id TEXT NOT NULL,
+            protocol_version INTEGER NOT NULL,
+            track_length REAL NOT NULL,
+            segments_per_lap INTEGER NOT NULL,
+            start_period INTEGER NOT NULL,
+            recovery_time INTEGER NOT NULL,
+            started_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            elapsed REAL NOT NULL DEFAULT 0,
+            state TEXT
+        );",
+    )
+}
+
+fn create_evaluation_warnings(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE evaluation_templates ADD COLUMN elimination_rules TEXT;
+        CREATE TABLE evaluation_warnings (
+            evaluation_id INTEGER NOT NULL,
+            period INTEGER NOT NULL CHECK (period > 0),
+            segment INTEGER NOT NULL CHECK (segment > 0),
+            elapsed REAL NOT NULL CHECK (elapsed >= 0),
+            recorded_at TEXT NOT NULL,
+            PRIMARY KEY (evaluation_id, period, segment),
+            FOREIGN KEY (evaluation_id) REFERENCES athlete_evaluations (id)
+        );",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Schema shipped before evaluations were split into templates.
+    const LEGACY_SCHEMA: &str = "
+        CREATE TABLE athletes (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL CHECK (age > 0 AND age < 150),
+            weight REAL NOT NULL CHECK (weight > 0),
+            height REAL NOT NULL CHECK (height > 0)
+        );
+        CREATE TABLE evaluations (
+            id INTEGER PRIMARY KEY,
+            athlete_id INTEGER NOT NULL,
+            completed_periods TEXT NOT NULL,
+            total_time INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            date TEXT NOT NULL
+        );
+        INSERT INTO athletes (id, name, age, weight, height) VALUES (1, 'Ana', 14, 50, 160);
+        INSERT INTO evaluations (athlete_id, completed_periods, total_time, status, date)
+            VALUES (1, '[2,3,4]', 400, 'completed', '2024-03-12T10:00:00+00:00');
+    ";
+
+    // Templates and athlete evaluations, before observations and distance.
+    const SPLIT_SCHEMA: &str = "
+        CREATE TABLE athletes (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL CHECK (age > 0 AND age < 150),
+            weight REAL NOT NULL CHECK (weight > 0),
+            height REAL NOT NULL CHECK (height > 0)
+        );
+        CREATE TABLE evaluation_templates (
+            id INTEGER PRIMARY KEY,
+            completed_periods TEXT NOT NULL,
+            total_time INTEGER NOT NULL,
+            date TEXT NOT NULL
+        );
+        CREATE TABLE athlete_evaluations (
+            id INTEGER PRIMARY KEY,
+            athlete_id INTEGER NOT NULL,
+            template_id INTEGER NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('completed', 'cancelled')),
+            date TEXT NOT NULL,
+            FOREIGN KEY (athlete_id) REFERENCES athletes (id),
+            FOREIGN KEY (template_id) REFERENCES evaluation_templates (id)
+        );
+        INSERT INTO athletes (id, name, age, weight, height) VALUES (1, 'Ana', 14, 50, 160);
+        INSERT INTO evaluation_templates (id, completed_periods, total_time, date)
+            VALUES (1, '[2,3,4]', 400, '2024-03-12T10:00:00+00:00');
+        INSERT INTO athlete_evaluations (athlete_id, template_id, status, date)
+            VALUES (1, 1, 'completed', '2024-03-12T10:00:00+00:00');
+    ";
+
+    // Observations added to athletes, templates still without distance.
+    const OBSERVATIONS_SCHEMA: &str = "
+        CREATE TABLE athletes (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL CHECK (age > 0 AND age < 150),
+            weight REAL NOT NULL CHECK (weight > 0),
+            height REAL NOT NULL CHECK (height > 0),
+            observations TEXT
+        );
+        CREATE TABLE evaluation_templates (
+            id INTEGER PRIMARY KEY,
+            completed_periods TEXT NOT NULL,
+            total_time INTEGER NOT NULL,
+            date TEXT NOT NULL
+        );
+        CREATE TABLE athlete_evaluations (
+            id INTEGER PRIMARY KEY,
+            athlete_id INTEGER NOT NULL,
+            template_id INTEGER NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('completed', 'cancelled')),
+            date TEXT NOT NULL,
+            FOREIGN KEY (athlete_id) REFERENCES athletes (id),
+            FOREIGN KEY (template_id) REFERENCES evaluation_templates (id)
+        );
+        INSERT INTO athletes (id, name, age, weight, height, observations)
+            VALUES (1, 'Ana', 14, 50, 160, 'Buena técnica');
+        INSERT INTO evaluation_templates (id, completed_periods, total_time, date)
+            VALUES (1, '[2,3,4]', 400, '2024-03-12T10:00:00+00:00');
+        INSERT INTO athlete_evaluations (athlete_id, template_id, status, date)
+            VALUES (1, 1, 'completed', '2024-03-12T10:00:00+00:00');
+    ";
+
+    // Last schema managed by the ad-hoc column checks, without user_version.
+    const UNVERSIONED_SCHEMA: &str = "
+        CREATE TABLE athletes (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL CHECK (age > 0 AND age < 150),
+            weight REAL NOT NULL CHECK (weight > 0),
+            height REAL NOT NULL CHECK (height > 0),
+            observations TEXT
+        );
+        CREATE TABLE evaluation_templates (
+            id INTEGER PRIMARY KEY,
+            completed_periods TEXT NOT NULL,
+            total_time INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            total_distance REAL NOT NULL DEFAULT 0
+        );
+        CREATE TABLE athlete_evaluations (
+            id INTEGER PRIMARY KEY,
+            athlete_id INTEGER NOT NULL,
+            template_id INTEGER NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('completed', 'cancelled')),
+            date TEXT NOT NULL,
+            FOREIGN KEY (athlete_id) REFERENCES athletes (id),
+            FOREIGN KEY (template_id) REFERENCES evaluation_templates (id)
+        );
+        INSERT INTO athletes (id, name, age, weight, height, observations)
+            VALUES (1, 'Ana', 14, 50, 160, 'Buena técnica');
+        INSERT INTO evaluation_templates (id, completed_periods, total_time, date, total_distance)
+            VALUES (1, '[2,3,4]', 400, '2024-03-12T10:00:00+00:00', 2400);
+        INSERT INTO athlete_evaluations (athlete_id, template_id, status, date)
+            VALUES (1, 1, 'completed', '2024-03-12T10:00:00+00:00');
+    ";
+
+    fn upgrade(fixture: &str) -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(fixture).unwrap();
+        run(&mut conn).unwrap();
+        conn
+    }
+
+    fn assert_latest_schema(conn: &Connection) {
+        assert_eq!(current_version(conn).unwrap(), latest_version());
+        assert!(!table_exists(conn, "evaluations").unwrap());
+        assert!(has_column(conn, "athletes", "observations").unwrap());
+        assert!(has_column(conn, "evaluation_templates", "total_distance").unwrap());
+        assert!(table_exists(conn, "period_results").unwrap());
+        assert!(has_column(conn, "athlete_evaluations", "status_reason").unwrap());
+        assert!(has_column(conn, "athlete_evaluations", "observations_author").unwrap());
+        assert!(has_column(conn, "athlete_evaluations", "session_id").unwrap());
+        assert!(has_column(conn, "evaluation_templates", "protocol_version").unwrap());
+        assert!(has_column(conn, "evaluation_templates", "track_length").unwrap());
+        assert!(table_exists(conn, "protocols").unwrap());
+        assert!(has_column(conn, "evaluation_templates", "segments_per_lap").unwrap());
+        assert!(has_column(conn, "sessions", "segments_per_lap").unwrap());
+        assert!(has_column(conn, "evaluation_templates", "measured_distance").unwrap());
+        assert!(has_column(conn, "athletes", "license_number").unwrap());
+        assert!(has_column(conn, "athlete_evaluations", "athlete_age").unwrap());
+        assert!(has_column(conn, "athlete_evaluations", "leg_length").unwrap());
+        assert!(has_column(conn, "evaluation_templates", "vo2max").unwrap());
+        assert!(table_exists(conn, "norms").unwrap());
+        assert!(table_exists(conn, "heart_rate_samples").unwrap());
+        assert!(table_exists(conn, "period_measurements").unwrap());
+        assert!(table_exists(conn, "test_journal").unwrap());
+        assert!(table_exists(conn, "evaluation_warnings").unwrap());
+        assert!(has_column(conn, "evaluation_templates", "elimination_rules").unwrap());
+        let evaluations_sql: String = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'athlete_evaluations'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(evaluations_sql.contains("'eliminated_by_warnings'"));
+        let foreign_keys: bool = conn
+            .query_row("PRAGMA foreign_keys", [], |row| row.get(0))
+            .unwrap();
+        assert!(foreign_keys);
+    }
+
+    fn evaluation_count(conn: &Connection) -> i64 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM athlete_evaluations ae
+             JOIN evaluation_templates et ON ae.template_id = et.id
+             JOIN athletes a ON ae.athlete_id = a.id",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn migration_versions_are_strictly_increasing() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version);
+        }
+    }
+
+    #[test]
+    fn creates_fresh_database() {
+        let conn = upgrade("");
+        assert_latest_schema(&conn);
+        assert_eq!(evaluation_count(&conn), 0);
+    }
+
+    #[test]
+    fn upgrades_legacy_schema() {
+        let conn = upgrade(LEGACY_SCHEMA);
+        assert_latest_schema(&conn);
+        assert_eq!(evaluation_count(&conn), 1);
+    }
+
+    #[test]
+    fn upgrades_split_schema() {
+        let conn = upgrade(SPLIT_SCHEMA);
+        assert_latest_schema(&conn);
+        assert_eq!(evaluation_count(&conn), 1);
+    }
+
+    #[test]
+    fn upgrades_observations_schema() {
+        let conn = upgrade(OBSERVATIONS_SCHEMA);
+        assert_latest_schema(&conn);
+        assert_eq!(evaluation_count(&conn), 1);
+        let observations: String = conn
+            .query_row("SELECT observations FROM athletes WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(observations, "Buena técnica");
+    }
+
+    #[test]
+    fn upgrades_unversioned_schema() {
+        let conn = upgrade(UNVERSIONED_SCHEMA);
+        assert_latest_schema(&conn);
+        assert_eq!(evaluation_count(&conn), 1);
+        let distance: f64 = conn
+            .query_row("SELECT total_distance FROM evaluation_templates WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(distance, 2400.0);
+        let athlete_age: i32 = conn
+            .query_row("SELECT athlete_age FROM athlete_evaluations", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(athlete_age, 14);
+        let (weight, height): (f64, f64) = conn
+            .query_row("SELECT weight, height FROM athlete_evaluations", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((weight, height), (50.0, 160.0));
+        let estimate: (f32, f32, f32) = conn
+            .query_row("SELECT final_speed, mas, vo2max FROM evaluation_templates WHERE id = 1", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(estimate, (22.4, 22.4, 78.4));
+    }
+
+    #[test]
+    fn rebuilds_evaluations_with_period_results() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(UNVERSIONED_SCHEMA).unwrap();
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= 5) {
+            (migration.up)(&conn).unwrap();
+        }
+        conn.pragma_update(None, "user_version", 5).unwrap();
+        conn.execute(
+            "INSERT INTO period_results
+                (evaluation_id, period, speed, lap_time, cumulative_distance, recorded_at, completed)
+             VALUES (1, 2, 20.8, 34.61, 800, '2024-03-12T10:02:18+00:00', 1)",
+            [],
+        )
+        .unwrap();
+
+        run(&mut conn).unwrap();
+        assert_latest_schema(&conn);
+        assert_eq!(evaluation_count(&conn), 1);
+        let orphans: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM period_results pr
+                 LEFT JOIN athlete_evaluations ae ON pr.evaluation_id = ae.id
+                 WHERE ae.id IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orphans, 0);
+    }
+
+    #[test]
+    fn running_twice_is_a_no_op() {
+        let mut conn = upgrade(UNVERSIONED_SCHEMA);
+        run(&mut conn).unwrap();
+        assert_latest_schema(&conn);
+        assert_eq!(evaluation_count(&conn), 1);
+    }
+
+    #[test]
+    fn rejects_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+        assert!(run(&mut conn).is_err());
+    }
+}
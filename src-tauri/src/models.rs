@@ -1,6 +1,15 @@
 use serde::{Deserialize, Serialize};
 use chrono::prelude::*;
 use crate::db;
+use crate::norms::Classification;
+use crate::performance::AerobicEstimate;
+use crate::protocol::TrackGeometry;
+use crate::warnings::Warning;
+
+pub use crate::db::{
+    Anthropometrics, AthleteSortField, Discipline, EvaluationStatus, GroupFilter, Sex,
+    TestConfiguration, TestJournal,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Athlete {
@@ -10,6 +19,12 @@ pub struct Athlete {
     pub weight: f32,
     pub height: f32,
     pub observations: Option<String>,
+    pub birth_date: Option<String>,
+    pub sex: Option<Sex>,
+    pub discipline: Option<Discipline>,
+    pub category: Option<String>,
+    pub club: Option<String>,
+    pub license_number: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -19,6 +34,10 @@ pub struct EvaluationTemplate {
     pub total_time: i32,
     pub date: String,
     pub total_distance: f32,
+    pub measured_distance: Option<f32>,
+    pub distance_flagged: bool,
+    pub config: TestConfiguration,
+    pub aerobic: Option<AerobicEstimate>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,8 +45,82 @@ pub struct AthleteEvaluation {
     pub id: Option<i64>,
     pub athlete_id: i64,
     pub template_id: i64,
-    pub status: String,
+    pub session_id: Option<i64>,
+    pub status: EvaluationStatus,
+    pub status_reason: Option<String>,
+    pub date: String,
+    pub observations: Option<String>,
+    pub observations_author: Option<String>,
+    pub observations_updated_at: Option<String>,
+    pub athlete_age: Option<i32>,
+    pub measurements: Option<Anthropometrics>,
+    // Derived from the measurements, ignored when received
+    #[serde(default)]
+    pub bmi: Option<f32>,
+    // Against the norms matching the athlete, ignored when received
+    #[serde(default)]
+    pub classifications: Vec<Classification>,
+}
+
+// One point of an athlete's anthropometric trend
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MeasurementPoint {
+    pub evaluation_id: i64,
+    pub date: String,
+    pub weight: f32,
+    pub height: f32,
+    pub body_fat: Option<f32>,
+    pub leg_length: Option<f32>,
+    pub bmi: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PeriodResult {
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub evaluation_id: i64,
+    pub period: i32,
+    pub speed: f32,
+    pub lap_time: f32,
+    pub cumulative_distance: f32,
+    pub recorded_at: String,
+    pub completed: bool,
+}
+
+// Everything the frontend sends to store one athlete's evaluation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvaluationInput {
+    pub athlete: Athlete,
+    pub completed_periods: String,
+    pub total_time: i32,
+    pub total_distance: f32,
+    pub status: EvaluationStatus,
+    pub status_reason: Option<String>,
+    pub observations: Option<String>,
+    pub session_id: Option<i64>,
+    pub config: Option<TestConfiguration>,
+    // Seconds skated in the period the athlete did not complete
+    pub last_period_elapsed: Option<f32>,
+    // Taken before the test, the athlete's weight and height when missing
+    pub measurements: Option<Anthropometrics>,
+    #[serde(default)]
+    pub period_results: Vec<PeriodResult>,
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    pub id: Option<i64>,
     pub date: String,
+    pub venue: Option<String>,
+    pub surface: Option<String>,
+    pub temperature: Option<f32>,
+    pub humidity: Option<f32>,
+    pub evaluator: Option<String>,
+    pub recovery_time: Option<i32>,
+    pub notes: Option<String>,
+    pub track_geometry: Option<TrackGeometry>,
 }
 
 impl AthleteEvaluation {
@@ -35,14 +128,24 @@ impl AthleteEvaluation {
         id: Option<i64>,
         athlete_id: i64,
         template_id: i64,
-        status: String,
+        status: EvaluationStatus,
+        status_reason: Option<String>,
     ) -> Self {
         Self {
             id,
             athlete_id,
             template_id,
+            session_id: None,
             status,
+            status_reason,
             date: Local::now().to_rfc3339(),
+            observations: None,
+            observations_author: None,
+            observations_updated_at: None,
+            athlete_age: None,
+            measurements: None,
+            bmi: None,
+            classifications: Vec::new(),
         }
     }
 }
@@ -56,6 +159,12 @@ impl From<db::Athlete> for Athlete {
             weight: athlete.weight,
             height: athlete.height,
             observations: athlete.observations,
+            birth_date: athlete.birth_date,
+            sex: athlete.sex,
+            discipline: athlete.discipline,
+            category: athlete.category,
+            club: athlete.club,
+            license_number: athlete.license_number,
         }
     }
 }
@@ -68,6 +177,10 @@ impl From<db::EvaluationTemplate> for EvaluationTemplate {
             total_time: template.total_time,
             date: template.date,
             total_distance: template.total_distance,
+            measured_distance: template.measured_distance,
+            distance_flagged: template.distance_flagged,
+            config: template.config,
+            aerobic: template.aerobic,
         }
     }
 }
@@ -78,8 +191,64 @@ impl From<db::AthleteEvaluation> for AthleteEvaluation {
             id: eval.id,
             athlete_id: eval.athlete_id,
             template_id: eval.template_id,
+            session_id: eval.session_id,
             status: eval.status,
+            status_reason: eval.status_reason,
             date: eval.date,
+            observations: eval.observations,
+            observations_author: eval.observations_author,
+            observations_updated_at: eval.observations_updated_at,
+            athlete_age: eval.athlete_age,
+            measurements: eval.measurements,
+            bmi: eval.measurements.map(|m| m.bmi()),
+            classifications: Vec::new(),
+        }
+    }
+}
+
+impl From<db::MeasurementRecord> for MeasurementPoint {
+    fn from(record: db::MeasurementRecord) -> Self {
+        let measurements = record.measurements;
+        Self {
+            evaluation_id: record.evaluation_id,
+            date: record.date,
+            weight: measurements.weight,
+            height: measurements.height,
+            body_fat: measurements.body_fat,
+            leg_length: measurements.leg_length,
+            bmi: measurements.bmi(),
+        }
+    }
+}
+
+impl From<db::PeriodResult> for PeriodResult {
+    fn from(result: db::PeriodResult) -> Self {
+        Self {
+            id: result.id,
+            evaluation_id: result.evaluation_id,
+            period: result.period,
+            speed: result.speed,
+            lap_time: result.lap_time,
+            cumulative_distance: result.cumulative_distance,
+            recorded_at: result.recorded_at,
+            completed: result.completed,
+        }
+    }
+}
+
+impl From<db::Session> for Session {
+    fn from(session: db::Session) -> Self {
+        Self {
+            id: session.id,
+            date: session.date,
+            venue: session.venue,
+            surface: session.surface,
+            temperature: session.temperature,
+            humidity: session.humidity,
+            evaluator: session.evaluator,
+            recovery_time: session.recovery_time,
+            notes: session.notes,
+            track_geometry: session.track_geometry,
         }
     }
-} 
\ No newline at end of file
+}
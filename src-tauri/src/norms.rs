@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+use crate::db::{text_enum, Discipline, Sex};
+use crate::performance::AerobicEstimate;
+
+text_enum!(NormMetric, "norm metric", {
+    TotalDistance => ("total_distance", "Distancia"),
+    Mas => ("mas", "VAM"),
+    Vo2max => ("vo2max", "VO2max"),
+});
+
+text_enum!(Rating, "rating", {
+    VeryPoor => ("very_poor", "Muy bajo"),
+    Poor => ("poor", "Bajo"),
+    Average => ("average", "Medio"),
+    Good => ("good", "Bueno"),
+    Excellent => ("excellent", "Excelente"),
+});
+
+impl NormMetric {
+    pub fn value(&self, total_distance: f32, aerobic: Option<AerobicEstimate>) -> Option<f32> {
+        match self {
+            NormMetric::TotalDistance => Some(total_distance),
+            NormMetric::Mas => aerobic.map(|a| a.mas),
+            NormMetric::Vo2max => aerobic.map(|a| a.vo2max),
+        }
+    }
+}
+
+// Percentiles of a metric for the athletes of an age band (inclusive
+// bounds). A missing sex or discipline applies to all of them.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Norm {
+    pub id: Option<i64>,
+    pub metric: NormMetric,
+    pub sex: Option<Sex>,
+    pub discipline: Option<Discipline>,
+    pub age_min: i32,
+    pub age_max: i32,
+    pub p10: f32,
+    pub p25: f32,
+    pub p50: f32,
+    pub p75: f32,
+    pub p90: f32,
+    pub source: Option<String>,
+}
+
+// Where a value falls within the norm that best matches the athlete
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Classification {
+    pub metric: NormMetric,
+    pub value: f32,
+    pub percentile: f32,
+    pub rating: Rating,
+    pub norm_id: Option<i64>,
+}
+
+impl Norm {
+    fn anchors(&self) -> [(f32, f32); 5] {
+        [
+            (10.0, self.p10),
+            (25.0, self.p25),
+            (50.0, self.p50),
+            (75.0, self.p75),
+            (90.0, self.p90),
+        ]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.age_min < 0 || self.age_max < self.age_min {
+            return Err("El rango de edad de la norma no es válido".to_string());
+        }
+        let anchors = self.anchors();
+        if anchors.iter().any(|(_, value)| !value.is_finite()) {
+            return Err("Los percentiles de la norma deben ser números".to_string());
+        }
+        if anchors.windows(2).any(|pair| pair[1].1 <= pair[0].1) {
+            return Err(format!(
+                "Los percentiles de la norma de {} deben ser crecientes",
+                self.metric.label()
+            ));
+        }
+        Ok(())
+    }
+
+    fn applies_to(&self, sex: Option<Sex>, discipline: Option<Discipline>, age: i32) -> bool {
+        (self.sex.is_none() || self.sex == sex)
+            && (self.discipline.is_none() || self.discipline == discipline)
+            && (self.age_min..=self.age_max).contains(&age)
+    }
+
+    // Percentile of `value`, interpolated between the published ones and
+    // extrapolated from the closest pair outside them
+    pub fn percentile(&self, value: f32) -> f32 {
+        let anchors = self.anchors();
+        let pair = anchors
+            .windows(2)
+            .find(|pair| value <= pair[1].1)
+            .unwrap_or(&anchors[3..]);
+        let ((p0, v0), (p1, v1)) = (pair[0], pair[1]);
+        let percentile = p0 + (value - v0) * (p1 - p0) / (v1 - v0);
+        percentile.clamp(1.0, 99.0).round()
+    }
+
+    pub fn rating(&self, value: f32) -> Rating {
+        if value < self.p10 {
+            Rating::VeryPoor
+        } else if value < self.p25 {
+            Rating::Poor
+        } else if value < self.p75 {
+            Rating::Average
+        } else if value < self.p90 {
+            Rating::Good
+        } else {
+            Rating::Excellent
+        }
+    }
+}
+
+// Classifies every metric of an evaluation that has a matching norm. Norms
+// for the athlete's sex and discipline win over generic ones, then the
+// narrowest age band.
+pub fn classify(
+    norms: &[Norm],
+    sex: Option<Sex>,
+    discipline: Option<Discipline>,
+    age: i32,
+    total_distance: f32,
+    aerobic: Option<AerobicEstimate>,
+) -> Vec<Classification> {
+    NormMetric::ALL
+        .iter()
+        .filter_map(|metric| {
+            let value = metric.value(total_distance, aerobic)?;
+            let norm = norms
+                .iter()
+                .filter(|norm| norm.metric == *metric && norm.applies_to(sex, discipline, age))
+                .min_by_key(|norm| {
+                    (norm.sex.is_none(), norm.discipline.is_none(), norm.age_max - norm.age_min)
+                })?;
+            Some(Classification {
+                metric: *metric,
+                value,
+                percentile: norm.percentile(value),
+                rating: norm.rating(value),
+                norm_id: norm.id,
+            })
+        })
+        .collect()
+}
+
+// Reads norms from a CSV file with the columns metric, sex, discipline,
+// age_min, age_max, p10, p25, p50, p75, p90 and source. Empty sex,
+// discipline or source cells are allowed.
+pub fn read_csv<R: Read>(reader: R) -> Result<Vec<Norm>, String> {
+    let mut reader = csv::Reader::from_reader(reader);
+    reader
+        .deserialize::<Norm>()
+        .enumerate()
+        .map(|(index, record)| {
+            // Header is line 1
+            let line = index + 2;
+            let norm = record.map_err(|e| format!("Fila {}: {}", line, e))?;
+            norm.validate().map_err(|e| format!("Fila {}: {}", line, e))?;
+            Ok(norm)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn norm(sex: Option<Sex>, age_min: i32, age_max: i32, p50: f32) -> Norm {
+        Norm {
+            id: Some(age_min as i64),
+            metric: NormMetric::Vo2max,
+            sex,
+            discipline: None,
+            age_min,
+            age_max,
+            p10: p50 - 10.0,
+            p25: p50 - 5.0,
+            p50,
+            p75: p50 + 5.0,
+            p90: p50 + 10.0,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn interpolates_percentiles_and_ratings() {
+        let norm = norm(None, 12, 14, 50.0);
+        assert_eq!(norm.percentile(50.0), 50.0);
+        assert_eq!(norm.percentile(52.5), 63.0);
+        assert_eq!(norm.percentile(100.0), 99.0);
+        assert_eq!(norm.percentile(0.0), 1.0);
+        assert_eq!(norm.rating(39.0), Rating::VeryPoor);
+        assert_eq!(norm.rating(45.0), Rating::Average);
+        assert_eq!(norm.rating(56.0), Rating::Good);
+        assert_eq!(norm.rating(60.0), Rating::Excellent);
+    }
+
+    #[test]
+    fn picks_the_most_specific_norm() {
+        let norms = vec![
+            norm(None, 10, 18, 40.0),
+            norm(Some(Sex::Female), 10, 18, 45.0),
+            norm(Some(Sex::Female), 14, 14, 50.0),
+            norm(Some(Sex::Male), 14, 14, 55.0),
+        ];
+        let aerobic = Some(AerobicEstimate { final_speed: 20.0, mas: 20.0, vo2max: 50.0 });
+
+        let female = classify(&norms, Some(Sex::Female), None, 14, 2400.0, aerobic);
+        assert_eq!(female.len(), 1);
+        assert_eq!(female[0].norm_id, Some(14));
+        assert_eq!(female[0].percentile, 50.0);
+
+        let unknown = classify(&norms, None, None, 14, 2400.0, aerobic);
+        assert_eq!(unknown[0].norm_id, Some(10));
+        assert!(classify(&norms, None, None, 30, 2400.0, aerobic).is_empty());
+        assert!(classify(&norms, None, None, 14, 2400.0, None).is_empty());
+    }
+
+    #[test]
+    fn reads_csv_norms() {
+        let csv = "metric,sex,discipline,age_min,age_max,p10,p25,p50,p75,p90,source
+vo2max,female,inline_speed,13,14,38,42,46,50,54,Club
+total_distance,,,13,14,1200,1600,2000,2400,2800,
+";
+        let norms = read_csv(csv.as_bytes()).unwrap();
+        assert_eq!(norms.len(), 2);
+        assert_eq!(norms[0].sex, Some(Sex::Female));
+        assert_eq!(norms[0].discipline, Some(Discipline::InlineSpeed));
+        assert_eq!(norms[1].sex, None);
+        assert_eq!(norms[1].source, None);
+
+        let unordered = "metric,sex,discipline,age_min,age_max,p10,p25,p50,p75,p90,source
+mas,,,13,14,20,19,21,22,23,
+";
+        assert!(read_csv(unordered.as_bytes()).unwrap_err().starts_with("Fila 2"));
+    }
+}
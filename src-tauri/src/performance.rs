@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::Protocol;
+
+// Regression used to estimate VO2max (ml/kg/min) from the maximal aerobic
+// speed (km/h).
+//
+// - `LegerMercier`: VO2max = 3.5 · MAS, the energy cost of running on a
+//   track (Léger & Mercier, 1984). It assumes running economy, skaters
+//   should calibrate their own coefficients.
+// - `Linear`: VO2max = intercept + slope · MAS, for equations fitted to a
+//   discipline or a squad.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Vo2maxEquation {
+    #[default]
+    LegerMercier,
+    Linear { intercept: f32, slope: f32 },
+}
+
+impl Vo2maxEquation {
+    pub fn estimate(&self, mas: f32) -> f32 {
+        match self {
+            Vo2maxEquation::LegerMercier => 3.5 * mas,
+            Vo2maxEquation::Linear { intercept, slope } => intercept + slope * mas,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Vo2maxEquation::LegerMercier => "Léger-Mercier (3,5 × VAM)".to_string(),
+            Vo2maxEquation::Linear { intercept, slope } => {
+                format!("Lineal ({} + {} × VAM)", intercept, slope)
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Vo2maxEquation::Linear { intercept, slope } = self {
+            if !intercept.is_finite() || !slope.is_finite() || *slope <= 0.0 {
+                return Err("La pendiente de la ecuación de VO2max debe ser mayor que 0".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+// Speeds in km/h, VO2max in ml/kg/min
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct AerobicEstimate {
+    // Speed of the last completed period
+    pub final_speed: f32,
+    // Maximal aerobic speed
+    pub mas: f32,
+    pub vo2max: f32,
+}
+
+pub(crate) fn round_to(value: f32, decimals: i32) -> f32 {
+    let factor = 10f32.powi(decimals);
+    (value * factor).round() / factor
+}
+
+// Estimates the aerobic capacity shown in a test. The maximal aerobic speed
+// is the speed of the last completed period plus the fraction of the next
+// period covered times the speed increment between them (Kuipers et al.,
+// 1985). The fraction is taken from `total_distance`, which only counts
+// whole segments. None when no period was completed.
+pub fn estimate(
+    protocol: &Protocol,
+    completed_periods: &[i32],
+    total_distance: f32,
+    equation: &Vo2maxEquation,
+) -> Option<AerobicEstimate> {
+    let last = protocol.period(*completed_periods.last()?)?;
+    let period_distance = protocol.period_distance();
+    let partial_distance = total_distance - completed_periods.len() as f32 * period_distance;
+
+    let mas = match protocol.period(last.period + 1) {
+        Some(next) if partial_distance > 0.0 => {
+            let fraction = (partial_distance / period_distance).min(1.0);
+            last.speed + fraction * (next.speed - last.speed)
+        }
+        _ => last.speed,
+    };
+
+    Some(AerobicEstimate {
+        final_speed: last.speed,
+        mas: round_to(mas, 2),
+        vo2max: round_to(equation.estimate(mas), 1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_from_completed_and_partial_periods() {
+        let protocol = Protocol::standard();
+        let first = protocol.periods[0].clone();
+        let second = protocol.periods[1].clone();
+        let period_distance = protocol.period_distance();
+
+        let complete = estimate(&protocol, &[first.period], period_distance, &Vo2maxEquation::default())
+            .unwrap();
+        assert_eq!(complete.final_speed, first.speed);
+        assert_eq!(complete.mas, round_to(first.speed, 2));
+        assert_eq!(complete.vo2max, round_to(3.5 * first.speed, 1));
+
+        // Half of the second period adds half of the speed increment
+        let partial = estimate(
+            &protocol,
+            &[first.period],
+            period_distance * 1.5,
+            &Vo2maxEquation::Linear { intercept: 10.0, slope: 2.0 },
+        )
+        .unwrap();
+        let mas = (first.speed + second.speed) / 2.0;
+        assert_eq!(partial.final_speed, first.speed);
+        assert_eq!(partial.mas, round_to(mas, 2));
+        assert_eq!(partial.vo2max, round_to(10.0 + 2.0 * mas, 1));
+
+        assert!(estimate(&protocol, &[], period_distance / 2.0, &Vo2maxEquation::default()).is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_equations() {
+        assert!(Vo2maxEquation::LegerMercier.validate().is_ok());
+        assert!(Vo2maxEquation::Linear { intercept: 5.0, slope: 3.2 }.validate().is_ok());
+        assert!(Vo2maxEquation::Linear { intercept: 5.0, slope: 0.0 }.validate().is_err());
+        assert!(Vo2maxEquation::Linear { intercept: f32::NAN, slope: 3.0 }.validate().is_err());
+    }
+}
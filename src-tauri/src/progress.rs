@@ -0,0 +1,204 @@
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::performance::round_to;
+
+// Result of one valid test of an athlete. Dates are RFC 3339 timestamps as
+// stored with the evaluation, or plain dates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub evaluation_id: i64,
+    pub date: String,
+    pub total_distance: f32,
+    pub final_speed: Option<f32>,
+}
+
+// A test compared with the previous one (deltas) and with the first one
+// (changes, in percent)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProgressPoint {
+    pub evaluation_id: i64,
+    pub date: String,
+    pub total_distance: f32,
+    pub final_speed: Option<f32>,
+    pub distance_delta: Option<f32>,
+    pub speed_delta: Option<f32>,
+    pub distance_change: Option<f32>,
+    pub speed_change: Option<f32>,
+}
+
+// Least squares line of a metric over time. `baseline` is the fitted value
+// on the date of the first test.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Trend {
+    pub slope_per_month: f32,
+    pub baseline: f32,
+    pub r_squared: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AthleteProgress {
+    pub athlete_id: i64,
+    // Oldest first
+    pub points: Vec<ProgressPoint>,
+    pub personal_best: Option<ProgressPoint>,
+    pub distance_trend: Option<Trend>,
+    pub speed_trend: Option<Trend>,
+}
+
+const DAYS_PER_MONTH: f64 = 30.4375;
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(date)
+        .map(|date| date.date_naive())
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok())
+}
+
+fn change(value: f32, baseline: f32) -> Option<f32> {
+    (baseline > 0.0).then(|| round_to((value - baseline) / baseline * 100.0, 1))
+}
+
+// Fits `value = baseline + slope · days`. None with fewer than two tests or
+// when they were all taken the same day.
+fn trend(samples: &[(f64, f32)]) -> Option<Trend> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let mean_x = samples.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = samples.iter().map(|(_, y)| *y as f64).sum::<f64>() / n;
+    let sxx: f64 = samples.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let syy: f64 = samples.iter().map(|(_, y)| (*y as f64 - mean_y).powi(2)).sum();
+    let sxy: f64 = samples
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (*y as f64 - mean_y))
+        .sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    Some(Trend {
+        slope_per_month: round_to((slope * DAYS_PER_MONTH) as f32, 2),
+        baseline: round_to((mean_y - slope * mean_x) as f32, 2),
+        // Undefined when every test gave the same value
+        r_squared: (syy > 0.0).then(|| round_to((sxy * sxy / (sxx * syy)) as f32, 3)),
+    })
+}
+
+// Analyses the valid tests of an athlete. Tests are sorted by date; the
+// first one is the baseline. The personal best is the longest distance,
+// the earliest one on ties.
+pub fn analyze(athlete_id: i64, mut results: Vec<TestResult>) -> AthleteProgress {
+    results.retain(|result| parse_date(&result.date).is_some());
+    results.sort_by_key(|result| (parse_date(&result.date), result.evaluation_id));
+
+    let points: Vec<ProgressPoint> = results
+        .iter()
+        .enumerate()
+        .map(|(index, result)| {
+            let previous = index.checked_sub(1).map(|i| &results[i]);
+            let baseline = &results[0];
+            ProgressPoint {
+                evaluation_id: result.evaluation_id,
+                date: result.date.clone(),
+                total_distance: result.total_distance,
+                final_speed: result.final_speed,
+                distance_delta: previous.map(|p| result.total_distance - p.total_distance),
+                speed_delta: previous
+                    .and_then(|p| Some(round_to(result.final_speed? - p.final_speed?, 2))),
+                distance_change: change(result.total_distance, baseline.total_distance),
+                speed_change: result
+                    .final_speed
+                    .zip(baseline.final_speed)
+                    .and_then(|(speed, base)| change(speed, base)),
+            }
+        })
+        .collect();
+
+    let personal_best = points
+        .iter()
+        .rev()
+        .max_by(|a, b| a.total_distance.total_cmp(&b.total_distance))
+        .cloned();
+
+    let first_day = results.first().and_then(|result| parse_date(&result.date));
+    let days = |result: &TestResult| -> f64 {
+        match (parse_date(&result.date), first_day) {
+            (Some(date), Some(first)) => (date - first).num_days() as f64,
+            _ => 0.0,
+        }
+    };
+    let distances: Vec<(f64, f32)> = results
+        .iter()
+        .map(|result| (days(result), result.total_distance))
+        .collect();
+    let speeds: Vec<(f64, f32)> = results
+        .iter()
+        .filter_map(|result| Some((days(result), result.final_speed?)))
+        .collect();
+
+    AthleteProgress {
+        athlete_id,
+        points,
+        personal_best,
+        distance_trend: trend(&distances),
+        speed_trend: trend(&speeds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(evaluation_id: i64, date: &str, total_distance: f32, final_speed: Option<f32>) -> TestResult {
+        TestResult { evaluation_id, date: date.to_string(), total_distance, final_speed }
+    }
+
+    #[test]
+    fn compares_tests_with_the_previous_one_and_the_baseline() {
+        let progress = analyze(
+            7,
+            vec![
+                result(3, "2024-03-01T10:00:00+01:00", 2000.0, Some(14.0)),
+                result(1, "2024-01-01T10:00:00+01:00", 1600.0, Some(12.0)),
+                result(2, "2024-02-01", 2000.0, None),
+            ],
+        );
+
+        let ids: Vec<i64> = progress.points.iter().map(|p| p.evaluation_id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(progress.points[0].distance_delta, None);
+        assert_eq!(progress.points[0].distance_change, Some(0.0));
+        assert_eq!(progress.points[1].distance_delta, Some(400.0));
+        assert_eq!(progress.points[1].distance_change, Some(25.0));
+        assert_eq!(progress.points[1].speed_delta, None);
+        assert_eq!(progress.points[2].distance_delta, Some(0.0));
+        assert_eq!(progress.points[2].speed_change, Some(16.7));
+        // Ties keep the first test that reached the distance
+        assert_eq!(progress.personal_best.unwrap().evaluation_id, 2);
+    }
+
+    #[test]
+    fn fits_linear_trends() {
+        let progress = analyze(
+            1,
+            vec![
+                result(1, "2024-01-01", 1000.0, Some(12.0)),
+                result(2, "2024-01-31", 1100.0, Some(12.0)),
+                result(3, "2024-03-01", 1200.0, Some(12.0)),
+            ],
+        );
+        let distance = progress.distance_trend.unwrap();
+        assert_eq!(distance.baseline, 1000.0);
+        assert_eq!(distance.r_squared, Some(1.0));
+        assert!((distance.slope_per_month - 101.46).abs() < 0.01);
+
+        let speed = progress.speed_trend.unwrap();
+        assert_eq!(speed.slope_per_month, 0.0);
+        assert_eq!(speed.r_squared, None);
+
+        let single = analyze(1, vec![result(1, "2024-01-01", 1000.0, None)]);
+        assert!(single.distance_trend.is_none() && single.speed_trend.is_none());
+    }
+}
@@ -0,0 +1,520 @@
+use serde::{Deserialize, Serialize};
+
+use crate::db::PeriodResult;
+
+pub const STANDARD_PROTOCOL_ID: &str = "standard";
+pub const STANDARD_TRACK_LENGTH: f32 = 200.0;
+pub const STANDARD_SEGMENTS_PER_LAP: i32 = 4;
+
+// Rounding allowed between the published table and the values derived from
+// the speed, in seconds
+const LAP_TIME_TOLERANCE: f32 = 0.02;
+const TOTAL_TIME_TOLERANCE: f32 = 0.1;
+const DISTANCE_TOLERANCE: f32 = 0.5;
+
+// Length of a lap in meters and the number of cones splitting it into
+// equal segments
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct TrackGeometry {
+    pub track_length: f32,
+    pub segments_per_lap: i32,
+}
+
+impl Default for TrackGeometry {
+    fn default() -> Self {
+        TrackGeometry {
+            track_length: STANDARD_TRACK_LENGTH,
+            segments_per_lap: STANDARD_SEGMENTS_PER_LAP,
+        }
+    }
+}
+
+impl TrackGeometry {
+    pub fn segment_length(&self) -> f32 {
+        self.track_length / self.segments_per_lap as f32
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.track_length <= 0.0 {
+            return Err("La longitud de la pista debe ser mayor que 0".to_string());
+        }
+        if self.segments_per_lap <= 0 {
+            return Err("Cada vuelta debe tener al menos un segmento".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Speeds in km/h, times in seconds and cumulative distances in meters
+// counted from the first period of the protocol
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProtocolPeriod {
+    pub period: i32,
+    pub speed: f32,
+    pub lap_time: f32,
+    pub partial_time: f32,
+    pub total_time: f32,
+    pub distance: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Protocol {
+    pub id: String,
+    pub version: i32,
+    pub name: String,
+    pub track_length: f32,
+    pub segments_per_lap: i32,
+    pub laps_per_period: i32,
+    pub parameters: Option<ProtocolParameters>,
+    pub periods: Vec<ProtocolPeriod>,
+}
+
+// Parameters custom protocols are generated from. Each period covers
+// `laps_per_period` laps and is `speed_increment` km/h faster than the
+// previous one.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProtocolParameters {
+    pub start_period: i32,
+    pub period_count: i32,
+    pub start_speed: f32,
+    pub speed_increment: f32,
+    pub laps_per_period: i32,
+    pub track_length: f32,
+    pub segments_per_lap: i32,
+}
+
+impl ProtocolParameters {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.start_period <= 0 {
+            return Err("El periodo inicial debe ser mayor que 0".to_string());
+        }
+        if self.period_count <= 0 || self.period_count > 100 {
+            return Err("El número de periodos debe estar entre 1 y 100".to_string());
+        }
+        if self.start_speed <= 0.0 {
+            return Err("La velocidad inicial debe ser mayor que 0".to_string());
+        }
+        if self.speed_increment <= 0.0 {
+            return Err("El incremento de velocidad debe ser mayor que 0".to_string());
+        }
+        if self.laps_per_period <= 0 {
+            return Err("Cada periodo debe tener al menos una vuelta".to_string());
+        }
+        self.geometry().validate()
+    }
+
+    pub fn geometry(&self) -> TrackGeometry {
+        TrackGeometry {
+            track_length: self.track_length,
+            segments_per_lap: self.segments_per_lap,
+        }
+    }
+}
+
+fn round_hundredths(value: f32) -> f32 {
+    (value * 100.0).round() / 100.0
+}
+
+// Lap, segment and period times of consecutive periods run at `speeds`.
+// Times are rounded to hundredths like the standard table.
+fn build_periods(
+    start_period: i32,
+    speeds: impl Iterator<Item = f32>,
+    geometry: &TrackGeometry,
+    laps_per_period: i32,
+) -> Vec<ProtocolPeriod> {
+    let segments = (geometry.segments_per_lap * laps_per_period) as f32;
+    let period_distance = geometry.track_length * laps_per_period as f32;
+
+    speeds
+        .enumerate()
+        .map(|(i, speed)| {
+            let lap_time = round_hundredths(geometry.track_length * 3.6 / speed);
+            let partial_time = round_hundredths(lap_time / geometry.segments_per_lap as f32);
+            ProtocolPeriod {
+                period: start_period + i as i32,
+                speed,
+                lap_time,
+                partial_time,
+                total_time: round_hundredths(partial_time * segments),
+                distance: period_distance * (i + 1) as f32,
+            }
+        })
+        .collect()
+}
+
+// (period, speed, lap time, partial time, total time, distance)
+const STANDARD_TABLE: [(i32, f32, f32, f32, f32, f32); 34] = [
+    (2, 20.8, 34.61, 8.65, 138.4, 800.0),
+    (3, 21.6, 33.33, 8.33, 133.28, 1600.0),
+    (4, 22.4, 32.14, 8.03, 128.48, 2400.0),
+    (5, 23.2, 31.03, 7.75, 124.0, 3200.0),
+    (6, 24.0, 30.0, 7.5, 120.0, 4000.0),
+    (7, 24.8, 29.03, 7.25, 116.0, 4800.0),
+    (8, 25.6, 28.12, 7.03, 112.48, 5600.0),
+    (9, 26.4, 27.27, 6.81, 108.96, 6400.0),
+    (10, 27.2, 26.47, 6.61, 105.76, 7200.0),
+    (11, 28.0, 25.71, 6.42, 102.72, 8000.0),
+    (12, 28.8, 25.0, 6.25, 100.0, 8800.0),
+    (13, 29.6, 24.32, 6.08, 97.28, 9600.0),
+    (14, 30.4, 23.68, 5.92, 94.72, 10400.0),
+    (15, 31.2, 23.07, 5.76, 92.16, 11200.0),
+    (16, 32.0, 22.5, 5.62, 89.92, 12000.0),
+    (17, 32.8, 21.95, 5.48, 87.68, 12800.0),
+    (18, 33.6, 21.42, 5.35, 85.6, 13600.0),
+    (19, 34.4, 20.93, 5.23, 83.68, 14400.0),
+    (20, 35.2, 20.45, 5.11, 81.76, 15200.0),
+    (21, 36.0, 20.0, 5.0, 80.0, 16000.0),
+    (22, 36.8, 19.56, 4.89, 78.24, 16800.0),
+    (23, 37.6, 19.14, 4.79, 76.64, 17600.0),
+    (24, 38.4, 18.75, 4.69, 75.04, 18400.0),
+    (25, 39.2, 18.36, 4.59, 73.44, 19200.0),
+    (26, 40.0, 18.0, 4.5, 72.0, 20000.0),
+    (27, 40.8, 17.64, 4.41, 70.56, 20800.0),
+    (28, 41.6, 17.3, 4.33, 69.28, 21600.0),
+    (29, 42.4, 16.98, 4.24, 67.92, 22400.0),
+    (30, 43.2, 16.66, 4.17, 66.72, 23200.0),
+    (31, 44.0, 16.36, 4.09, 65.44, 24000.0),
+    (32, 44.8, 16.07, 4.02, 64.32, 24800.0),
+    (33, 45.6, 15.79, 3.95, 63.2, 25600.0),
+    (34, 46.4, 15.51, 3.88, 62.08, 26400.0),
+    (35, 47.2, 15.25, 3.81, 61.0, 27200.0),
+];
+
+impl Protocol {
+    // The progressive test used until now: 4 laps of a 200 m track per
+    // period, starting at 20.8 km/h and adding 0.8 km/h each period
+    pub fn standard() -> Self {
+        Protocol {
+            id: STANDARD_PROTOCOL_ID.to_string(),
+            version: 1,
+            name: "Protocolo estándar".to_string(),
+            track_length: STANDARD_TRACK_LENGTH,
+            segments_per_lap: STANDARD_SEGMENTS_PER_LAP,
+            laps_per_period: 4,
+            periods: STANDARD_TABLE
+                .iter()
+                .map(|&(period, speed, lap_time, partial_time, total_time, distance)| ProtocolPeriod {
+                    period,
+                    speed,
+                    lap_time,
+                    partial_time,
+                    total_time,
+                    distance,
+                })
+                .collect(),
+            parameters: Some(ProtocolParameters {
+                start_period: 2,
+                period_count: STANDARD_TABLE.len() as i32,
+                start_speed: 20.8,
+                speed_increment: 0.8,
+                laps_per_period: 4,
+                track_length: STANDARD_TRACK_LENGTH,
+                segments_per_lap: STANDARD_SEGMENTS_PER_LAP,
+            }),
+        }
+    }
+
+    // Builds the period table of a custom protocol
+    pub fn generate(id: &str, version: i32, name: &str, parameters: &ProtocolParameters) -> Self {
+        let speeds = (0..parameters.period_count)
+            .map(|i| round_hundredths(parameters.start_speed + parameters.speed_increment * i as f32));
+        let periods = build_periods(
+            parameters.start_period,
+            speeds,
+            &parameters.geometry(),
+            parameters.laps_per_period,
+        );
+
+        Protocol {
+            id: id.to_string(),
+            version,
+            name: name.to_string(),
+            track_length: parameters.track_length,
+            segments_per_lap: parameters.segments_per_lap,
+            laps_per_period: parameters.laps_per_period,
+            parameters: Some(parameters.clone()),
+            periods,
+        }
+    }
+
+    pub fn geometry(&self) -> TrackGeometry {
+        TrackGeometry {
+            track_length: self.track_length,
+            segments_per_lap: self.segments_per_lap,
+        }
+    }
+
+    // The same speeds run on another track: lap, segment and period times
+    // are recomputed from the speed, periods keep their number of laps
+    pub fn with_geometry(&self, geometry: TrackGeometry) -> Self {
+        if geometry == self.geometry() {
+            return self.clone();
+        }
+        let start_period = self.periods.first().map_or(1, |p| p.period);
+        Protocol {
+            track_length: geometry.track_length,
+            segments_per_lap: geometry.segments_per_lap,
+            parameters: self.parameters.clone().map(|parameters| ProtocolParameters {
+                track_length: geometry.track_length,
+                segments_per_lap: geometry.segments_per_lap,
+                ..parameters
+            }),
+            periods: build_periods(
+                start_period,
+                self.periods.iter().map(|p| p.speed),
+                &geometry,
+                self.laps_per_period,
+            ),
+            ..self.clone()
+        }
+    }
+
+    pub fn period(&self, period: i32) -> Option<&ProtocolPeriod> {
+        self.periods.iter().find(|p| p.period == period)
+    }
+
+    // Distance covered by completing one period
+    pub fn period_distance(&self) -> f32 {
+        self.track_length * self.laps_per_period as f32
+    }
+
+    pub fn segments_per_period(&self) -> i32 {
+        self.segments_per_lap * self.laps_per_period
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.geometry().validate()?;
+        if self.laps_per_period <= 0 {
+            return Err("Cada periodo debe tener al menos una vuelta".to_string());
+        }
+        if self.periods.is_empty() {
+            return Err("El protocolo no tiene periodos".to_string());
+        }
+
+        let period_distance = self.period_distance();
+        let mut previous: Option<&ProtocolPeriod> = None;
+        for p in &self.periods {
+            if p.speed <= 0.0 {
+                return Err(format!("La velocidad del periodo {} debe ser mayor que 0", p.period));
+            }
+            if let Some(prev) = previous {
+                if p.period != prev.period + 1 {
+                    return Err(format!("Los periodos deben ser consecutivos: {} sigue a {}", p.period, prev.period));
+                }
+                if p.speed <= prev.speed {
+                    return Err(format!("La velocidad del periodo {} debe ser mayor que la del anterior", p.period));
+                }
+            }
+
+            // Time to cover one lap at the period speed
+            let lap_time = self.track_length * 3.6 / p.speed;
+            if (p.lap_time - lap_time).abs() > LAP_TIME_TOLERANCE {
+                return Err(format!(
+                    "El tiempo por vuelta del periodo {} ({} s) no corresponde a su velocidad ({:.2} s)",
+                    p.period, p.lap_time, lap_time
+                ));
+            }
+            if (p.partial_time - p.lap_time / self.segments_per_lap as f32).abs() > LAP_TIME_TOLERANCE {
+                return Err(format!("El tiempo parcial del periodo {} no corresponde al tiempo por vuelta", p.period));
+            }
+            let total_time = p.partial_time * (self.segments_per_lap * self.laps_per_period) as f32;
+            if (p.total_time - total_time).abs() > TOTAL_TIME_TOLERANCE {
+                return Err(format!("El tiempo total del periodo {} no corresponde a sus parciales", p.period));
+            }
+
+            let distance = previous.map_or(0.0, |prev| prev.distance) + period_distance;
+            if (p.distance - distance).abs() > DISTANCE_TOLERANCE {
+                return Err(format!(
+                    "La distancia acumulada del periodo {} debería ser {} m",
+                    p.period, distance
+                ));
+            }
+            previous = Some(p);
+        }
+        Ok(())
+    }
+
+    // Distance covered by completing `completed_periods` and skating
+    // `elapsed` seconds into the following period, counted in whole segments
+    // like the cones the athletes pass. Without completed periods the
+    // partial one is `start_period`, or the first of the protocol.
+    pub fn expected_distance(
+        &self,
+        completed_periods: &[i32],
+        start_period: Option<i32>,
+        elapsed: f32,
+    ) -> Result<f32, String> {
+        if elapsed < 0.0 {
+            return Err("El tiempo del último periodo no puede ser negativo".to_string());
+        }
+        for (i, period) in completed_periods.iter().enumerate() {
+            if self.period(*period).is_none() {
+                return Err(format!("El periodo {} no existe en el protocolo {}", period, self.id));
+            }
+            if i > 0 && *period != completed_periods[i - 1] + 1 {
+                return Err("Los periodos completados deben ser consecutivos".to_string());
+            }
+        }
+
+        let completed_distance = completed_periods.len() as f32 * self.period_distance();
+        let next_period = match completed_periods.last() {
+            Some(last) => last + 1,
+            None => start_period.or(self.periods.first().map(|p| p.period)).unwrap_or(1),
+        };
+        // Nothing left to skate after the last period of the protocol
+        let Some(next) = self.period(next_period) else {
+            return Ok(completed_distance);
+        };
+
+        let segments = ((elapsed / next.partial_time).floor() as i32).min(self.segments_per_period());
+        Ok(completed_distance + segments as f32 * self.geometry().segment_length())
+    }
+
+    // Rebuilds the speed, lap time and cumulative distance of stored period
+    // results from the protocol table. A partial last period keeps the whole
+    // segments recorded inside it, capped to the length of a period.
+    pub fn recompute_period_results(&self, results: &[PeriodResult]) -> Result<Vec<PeriodResult>, String> {
+        let period_distance = self.period_distance();
+        let segment_length = self.geometry().segment_length();
+        let mut recorded_distance = 0.0;
+        let mut distance = 0.0;
+
+        results
+            .iter()
+            .map(|result| {
+                let period = self.period(result.period).ok_or_else(|| {
+                    format!("El periodo {} no existe en el protocolo {}", result.period, self.id)
+                })?;
+
+                let covered = if result.completed {
+                    period_distance
+                } else {
+                    let segments = ((result.cumulative_distance - recorded_distance) / segment_length).floor();
+                    (segments * segment_length).clamp(0.0, period_distance)
+                };
+                recorded_distance = result.cumulative_distance;
+                distance += covered;
+
+                Ok(PeriodResult {
+                    speed: period.speed,
+                    lap_time: period.lap_time,
+                    cumulative_distance: distance,
+                    ..result.clone()
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(period: i32, cumulative_distance: f32, completed: bool) -> PeriodResult {
+        PeriodResult {
+            id: None,
+            evaluation_id: 1,
+            period,
+            speed: 0.0,
+            lap_time: 0.0,
+            cumulative_distance,
+            recorded_at: "2024-01-01T00:00:00+00:00".to_string(),
+            completed,
+        }
+    }
+
+    #[test]
+    fn standard_protocol_is_consistent() {
+        let protocol = Protocol::standard();
+        protocol.validate().unwrap();
+        assert_eq!(protocol.period_distance(), 800.0);
+    }
+
+    #[test]
+    fn rejects_inconsistent_tables() {
+        let mut protocol = Protocol::standard();
+        protocol.periods[3].lap_time += 1.0;
+        assert!(protocol.validate().is_err());
+
+        let mut protocol = Protocol::standard();
+        protocol.periods[5].distance = 0.0;
+        assert!(protocol.validate().is_err());
+
+        let mut protocol = Protocol::standard();
+        protocol.periods.remove(10);
+        assert!(protocol.validate().is_err());
+    }
+
+    #[test]
+    fn recomputes_period_results() {
+        let protocol = Protocol::standard();
+        let stored = [result(4, 750.0, true), result(5, 1600.0, true), result(6, 1770.0, false)];
+        let results = protocol.recompute_period_results(&stored).unwrap();
+
+        assert_eq!(results[0].speed, 22.4);
+        assert_eq!(results[0].lap_time, 32.14);
+        let distances: Vec<f32> = results.iter().map(|r| r.cumulative_distance).collect();
+        assert_eq!(distances, vec![800.0, 1600.0, 1750.0]);
+
+        assert!(protocol.recompute_period_results(&[result(40, 800.0, true)]).is_err());
+    }
+
+    #[test]
+    fn generates_consistent_custom_protocols() {
+        let parameters = ProtocolParameters {
+            start_period: 1,
+            period_count: 30,
+            start_speed: 18.0,
+            speed_increment: 0.5,
+            laps_per_period: 3,
+            track_length: 180.0,
+            segments_per_lap: 6,
+        };
+        let protocol = Protocol::generate("club", 2, "Club", &parameters);
+        protocol.validate().unwrap();
+
+        let first = protocol.period(1).unwrap();
+        assert_eq!(first.speed, 18.0);
+        assert_eq!(first.lap_time, 36.0);
+        assert_eq!(first.partial_time, 6.0);
+        assert_eq!(first.total_time, 108.0);
+        assert_eq!(first.distance, 540.0);
+        assert_eq!(protocol.period(30).unwrap().speed, 32.5);
+    }
+
+    #[test]
+    fn computes_expected_distance() {
+        let protocol = Protocol::standard();
+        // Period 4 runs 8.03 s per segment
+        assert_eq!(protocol.expected_distance(&[2, 3], None, 17.0).unwrap(), 1700.0);
+        assert_eq!(protocol.expected_distance(&[], Some(5), 8.0).unwrap(), 50.0);
+        assert_eq!(protocol.expected_distance(&[], None, 0.0).unwrap(), 0.0);
+        // Capped to a full period
+        assert_eq!(protocol.expected_distance(&[], Some(2), 500.0).unwrap(), 800.0);
+        assert_eq!(protocol.expected_distance(&[35], None, 30.0).unwrap(), 800.0);
+
+        assert!(protocol.expected_distance(&[2, 4], None, 0.0).is_err());
+        assert!(protocol.expected_distance(&[1], None, 0.0).is_err());
+        assert!(protocol.expected_distance(&[2], None, -1.0).is_err());
+    }
+
+    #[test]
+    fn recomputes_times_for_other_tracks() {
+        let standard = Protocol::standard();
+        assert_eq!(standard.with_geometry(TrackGeometry::default()), standard);
+
+        let geometry = TrackGeometry { track_length: 150.0, segments_per_lap: 3 };
+        let protocol = standard.with_geometry(geometry);
+        protocol.validate().unwrap();
+        assert_eq!(geometry.segment_length(), 50.0);
+        assert_eq!(protocol.period_distance(), 600.0);
+
+        let period = protocol.period(6).unwrap();
+        assert_eq!(period.speed, 24.0);
+        assert_eq!(period.lap_time, 22.5);
+        assert_eq!(period.partial_time, 7.5);
+        assert_eq!(period.total_time, 90.0);
+        assert_eq!(period.distance, 3000.0);
+    }
+}
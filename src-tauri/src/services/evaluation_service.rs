@@ -1,8 +1,20 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use rusqlite::params;
-use crate::db::{self, Database};
-use crate::models::{Athlete, AthleteEvaluation, EvaluationTemplate};
+use crate::db::{self, AthleteSortField, Database};
+use crate::heart_rate::{self, HeartRateAnalysis, HeartRateFormat, HeartRateRecording};
+use crate::lactate::{self, LactateAnalysis, PeriodMeasurement};
+use crate::norms::{self, Norm};
+use crate::performance;
+use crate::progress::{self, AthleteProgress};
+use crate::settings;
+use crate::protocol::{Protocol, ProtocolParameters, TrackGeometry, STANDARD_PROTOCOL_ID};
+use crate::statistics::{self, Sample, SquadStatistics};
+use crate::training::{self, TrainingPlan, TrainingZone};
+use crate::warnings::{self, Warning};
+use crate::models::{
+    Anthropometrics, Athlete, AthleteEvaluation, EvaluationInput, EvaluationTemplate,
+    GroupFilter, MeasurementPoint, PeriodResult, Session, TestConfiguration, TestJournal,
+};
 
 pub struct EvaluationService {
     db: Arc<Database>,
@@ -17,6 +29,12 @@ impl From<Athlete> for db::Athlete {
             weight: athlete.weight,
             height: athlete.height,
             observations: athlete.observations,
+            birth_date: athlete.birth_date,
+            sex: athlete.sex,
+            discipline: athlete.discipline,
+            category: athlete.category,
+            club: athlete.club,
+            license_number: athlete.license_number,
         }
     }
 }
@@ -29,6 +47,10 @@ impl From<EvaluationTemplate> for db::EvaluationTemplate {
             total_time: template.total_time,
             date: template.date,
             total_distance: template.total_distance,
+            measured_distance: template.measured_distance,
+            distance_flagged: template.distance_flagged,
+            config: template.config,
+            aerobic: template.aerobic,
         }
     }
 }
@@ -39,52 +61,696 @@ impl From<AthleteEvaluation> for db::AthleteEvaluation {
             id: eval.id,
             athlete_id: eval.athlete_id,
             template_id: eval.template_id,
+            session_id: eval.session_id,
             status: eval.status,
+            status_reason: eval.status_reason,
             date: eval.date,
+            observations: eval.observations,
+            observations_author: eval.observations_author,
+            observations_updated_at: eval.observations_updated_at,
+            athlete_age: eval.athlete_age,
+            measurements: eval.measurements,
         }
     }
 }
 
+// Trims the profile fields and derives the age from the birth date, as of
+// today, when it is known
+pub fn normalize_athlete(athlete: Athlete) -> Athlete {
+    let birth_date = normalize_text(athlete.birth_date);
+    let age = birth_date
+        .as_deref()
+        .and_then(|birth_date| db::age_on(birth_date, chrono::Local::now().date_naive()))
+        .unwrap_or(athlete.age);
+    Athlete {
+        age,
+        birth_date,
+        category: normalize_text(athlete.category),
+        club: normalize_text(athlete.club),
+        license_number: normalize_text(athlete.license_number),
+        ..athlete
+    }
+}
+
+pub fn validate_athlete(athlete: &Athlete) -> Result<(), String> {
+    if athlete.name.trim().is_empty() {
+        return Err("El nombre del atleta es obligatorio".to_string());
+    }
+    if let Some(birth_date) = &athlete.birth_date {
+        let birth_date = chrono::NaiveDate::parse_from_str(birth_date, "%Y-%m-%d")
+            .map_err(|_| "La fecha de nacimiento no es válida".to_string())?;
+        if birth_date > chrono::Local::now().date_naive() {
+            return Err("La fecha de nacimiento no puede ser futura".to_string());
+        }
+    }
+    if athlete.age <= 0 || athlete.age >= 150 {
+        return Err("La edad debe estar entre 1 y 149 años".to_string());
+    }
+    if athlete.weight <= 0.0 {
+        return Err("El peso debe ser mayor que 0".to_string());
+    }
+    if athlete.height <= 0.0 {
+        return Err("La altura debe ser mayor que 0".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_anthropometrics(measurements: &Anthropometrics) -> Result<(), String> {
+    if measurements.weight <= 0.0 {
+        return Err("El peso debe ser mayor que 0".to_string());
+    }
+    if measurements.height <= 0.0 {
+        return Err("La altura debe ser mayor que 0".to_string());
+    }
+    if measurements.body_fat.is_some_and(|fat| fat <= 0.0 || fat >= 100.0) {
+        return Err("La grasa corporal debe estar entre 0 y 100%".to_string());
+    }
+    if measurements
+        .leg_length
+        .is_some_and(|length| length <= 0.0 || length >= measurements.height)
+    {
+        return Err("La longitud de pierna debe ser mayor que 0 y menor que la altura".to_string());
+    }
+    Ok(())
+}
+
+impl From<PeriodResult> for db::PeriodResult {
+    fn from(result: PeriodResult) -> Self {
+        db::PeriodResult {
+            id: result.id,
+            evaluation_id: result.evaluation_id,
+            period: result.period,
+            speed: result.speed,
+            lap_time: result.lap_time,
+            cumulative_distance: result.cumulative_distance,
+            recorded_at: result.recorded_at,
+            completed: result.completed,
+        }
+    }
+}
+
+fn normalize_text(reason: Option<String>) -> Option<String> {
+    reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+}
+
+// Periods must be in order with a non-decreasing distance, and only the
+// last one may be partial.
+pub fn validate_period_results(period_results: &[PeriodResult]) -> Result<(), String> {
+    for (index, result) in period_results.iter().enumerate() {
+        if result.period <= 0 || result.speed <= 0.0 || result.lap_time <= 0.0 {
+            return Err(format!("Datos inválidos para el periodo {}", result.period));
+        }
+        if !result.completed && index + 1 != period_results.len() {
+            return Err("Solo el último periodo puede estar incompleto".to_string());
+        }
+        if let Some(previous) = index.checked_sub(1).map(|i| &period_results[i]) {
+            if result.period <= previous.period
+                || result.cumulative_distance < previous.cumulative_distance
+            {
+                return Err("Los periodos deben estar ordenados".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+impl From<Session> for db::Session {
+    fn from(session: Session) -> Self {
+        db::Session {
+            id: session.id,
+            date: session.date,
+            venue: session.venue,
+            surface: session.surface,
+            temperature: session.temperature,
+            humidity: session.humidity,
+            evaluator: session.evaluator,
+            recovery_time: session.recovery_time,
+            notes: session.notes,
+            track_geometry: session.track_geometry,
+        }
+    }
+}
+
+pub fn validate_session(session: &Session) -> Result<(), String> {
+    let date = session.date.trim();
+    if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err()
+        && chrono::DateTime::parse_from_rfc3339(date).is_err()
+    {
+        return Err("La fecha de la sesión no es válida".to_string());
+    }
+    if let Some(humidity) = session.humidity {
+        if !(0.0..=100.0).contains(&humidity) {
+            return Err("La humedad debe estar entre 0 y 100%".to_string());
+        }
+    }
+    if session.recovery_time.is_some_and(|time| time < 0) {
+        return Err("El tiempo de recuperación no puede ser negativo".to_string());
+    }
+    if let Some(geometry) = &session.track_geometry {
+        geometry.validate()?;
+    }
+    Ok(())
+}
+
+pub fn validate_configuration(config: &TestConfiguration) -> Result<(), String> {
+    if config.recovery_time.is_some_and(|time| time < 0) {
+        return Err("El tiempo de recuperación no puede ser negativo".to_string());
+    }
+    if config.start_period.is_some_and(|period| period <= 0) {
+        return Err("El periodo inicial debe ser mayor que 0".to_string());
+    }
+    if config.protocol_id.trim().is_empty() {
+        return Err("El protocolo es obligatorio".to_string());
+    }
+    if config.protocol_version <= 0 {
+        return Err("La versión del protocolo debe ser mayor que 0".to_string());
+    }
+    config.vo2max_equation.validate()?;
+    if let Some(rules) = &config.elimination_rules {
+        rules.validate()?;
+    }
+    config.geometry().validate()
+}
+
+type EvaluationData = (
+    db::Athlete,
+    db::EvaluationTemplate,
+    db::AthleteEvaluation,
+    Vec<db::PeriodResult>,
+    Vec<Warning>,
+);
+
+// The configuration an evaluation was run with, the default one when the
+// client sent none
+fn evaluation_config(input: &EvaluationInput) -> Result<TestConfiguration, String> {
+    let config = input.config.clone().unwrap_or_default();
+    validate_configuration(&config)?;
+    Ok(TestConfiguration {
+        protocol_id: config.protocol_id.trim().to_string(),
+        ..config
+    })
+}
+
+// Validates an evaluation and turns it into the records saved for it. The
+// stored distance is computed from the protocol, the one measured by the
+// client is kept alongside and flagged when they differ by more than a
+// segment.
+fn build_evaluation_data(
+    input: EvaluationInput,
+    config: TestConfiguration,
+    protocol: &Protocol,
+    date: &str,
+) -> Result<EvaluationData, String> {
+    let mut athlete = normalize_athlete(input.athlete);
+    // The measurements taken for the test are also the athlete's current ones
+    if let Some(measurements) = &input.measurements {
+        validate_anthropometrics(measurements)?;
+        athlete.weight = measurements.weight;
+        athlete.height = measurements.height;
+    }
+    validate_athlete(&athlete)?;
+    validate_period_results(&input.period_results)?;
+    warnings::validate(&input.warnings, protocol.segments_per_period())?;
+    if input.total_distance < 0.0 {
+        return Err("La distancia no puede ser negativa".to_string());
+    }
+
+    let completed_periods: Vec<i32> = serde_json::from_str(&input.completed_periods)
+        .map_err(|_| "Los periodos completados no son válidos".to_string())?;
+    let total_distance = protocol.expected_distance(
+        &completed_periods,
+        config.start_period,
+        input.last_period_elapsed.unwrap_or(0.0),
+    )?;
+    let distance_flagged =
+        (input.total_distance - total_distance).abs() > protocol.geometry().segment_length();
+    let aerobic = performance::estimate(
+        protocol,
+        &completed_periods,
+        total_distance,
+        &config.vo2max_equation,
+    );
+
+    let template = EvaluationTemplate {
+        id: None,
+        completed_periods: input.completed_periods,
+        total_time: input.total_time,
+        date: date.to_string(),
+        total_distance,
+        measured_distance: Some(input.total_distance),
+        distance_flagged,
+        config,
+        aerobic,
+    };
+
+    let mut athlete_evaluation = AthleteEvaluation::new(
+        None,
+        0, // Will be set by the database
+        0, // Will be set by the database
+        input.status,
+        normalize_text(input.status_reason),
+    );
+    athlete_evaluation.date = date.to_string();
+    athlete_evaluation.session_id = input.session_id;
+    athlete_evaluation.measurements = input.measurements;
+    athlete_evaluation.observations = normalize_text(input.observations);
+    if athlete_evaluation.observations.is_some() {
+        athlete_evaluation.observations_updated_at = Some(date.to_string());
+    }
+
+    let period_results = input.period_results.into_iter().map(Into::into).collect();
+
+    Ok((athlete.into(), template.into(), athlete_evaluation.into(), period_results, input.warnings))
+}
+
+// Bounds of a date filter, YYYY-MM-DD
+fn validate_date_bounds(from: &Option<String>, to: &Option<String>) -> Result<(), String> {
+    for date in from.iter().chain(to.iter()) {
+        if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            return Err(format!("La fecha {} no es válida", date));
+        }
+    }
+    Ok(())
+}
+
+// Statistics only use the evaluations with a valid result
+fn statistics_sample((evaluation, template, athlete): db::EvaluationRecord) -> Option<Sample> {
+    if !evaluation.status.is_valid_result() {
+        return None;
+    }
+    let completed_periods: Vec<i32> =
+        serde_json::from_str(&template.completed_periods).unwrap_or_default();
+    Some(Sample {
+        evaluation_id: evaluation.id?,
+        athlete_id: evaluation.athlete_id,
+        athlete_name: athlete.name,
+        date: evaluation.date,
+        category: athlete.category,
+        final_period: completed_periods.last().copied(),
+        total_distance: template.total_distance,
+        vo2max: template.aerobic.map(|aerobic| aerobic.vo2max),
+    })
+}
+
+// The latest valid evaluation of each athlete of the group
+fn statistics_samples(evals: Vec<db::EvaluationRecord>) -> Vec<Sample> {
+    statistics::latest_per_athlete(evals.into_iter().filter_map(statistics_sample).collect())
+}
+
+type ClassifiedRecord = (AthleteEvaluation, EvaluationTemplate, Athlete);
+
+// An evaluation as returned to the client, classified against the norms.
+// Evaluations saved without an age use the athlete's current one.
+fn classify_record(
+    norms: &[Norm],
+    (evaluation, template, athlete): db::EvaluationRecord,
+) -> ClassifiedRecord {
+    let mut evaluation = AthleteEvaluation::from(evaluation);
+    evaluation.classifications = norms::classify(
+        norms,
+        athlete.sex,
+        athlete.discipline,
+        evaluation.athlete_age.unwrap_or(athlete.age),
+        template.total_distance,
+        template.aerobic,
+    );
+    (evaluation, template.into(), athlete.into())
+}
+
 impl EvaluationService {
     pub fn new(db: Arc<Database>) -> Self {
         Self { db }
     }
 
+    pub async fn create_session(&self, mut session: Session) -> Result<i64, String> {
+        validate_session(&session)?;
+        session.venue = normalize_text(session.venue);
+        session.surface = normalize_text(session.surface);
+        session.evaluator = normalize_text(session.evaluator);
+        session.notes = normalize_text(session.notes);
+        self.db.create_session(&session.into())
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn list_sessions(&self) -> Result<Vec<(Session, i64)>, String> {
+        self.db.list_sessions()
+            .map(|sessions| sessions.into_iter().map(|(session, count)| (session.into(), count)).collect())
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn get_session_results(
+        &self,
+        session_id: i64,
+    ) -> Result<(Session, Vec<ClassifiedRecord>), String> {
+        let norms = self.db.list_norms().map_err(|e| e.to_string())?;
+        self.db.get_session_results(session_id)
+            .map(|(session, evals)| (
+                session.into(),
+                evals.into_iter().map(|record| classify_record(&norms, record)).collect(),
+            ))
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn get_session_statistics(
+        &self,
+        session_id: i64,
+    ) -> Result<(Session, SquadStatistics), String> {
+        let (session, evals) = self.db.get_session_results(session_id)
+            .map_err(|e| e.to_string())?;
+        Ok((session.into(), statistics::analyze(&statistics_samples(evals))))
+    }
+
+    pub async fn get_group_statistics(&self, mut filter: GroupFilter) -> Result<SquadStatistics, String> {
+        filter.category = normalize_text(filter.category);
+        filter.club = normalize_text(filter.club);
+        validate_date_bounds(&filter.from, &filter.to)?;
+        let evals = self.db.get_group_evaluations(&filter)
+            .map_err(|e| e.to_string())?;
+        Ok(statistics::analyze(&statistics_samples(evals)))
+    }
+
+    pub async fn export_session_evaluations(
+        &self,
+        session_id: i64,
+        path: PathBuf,
+    ) -> Result<(), String> {
+        self.db.export_session_evaluations_to_csv(session_id, path)
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn export_session_evaluations_to_xlsx(
+        &self,
+        session_id: i64,
+        path: PathBuf,
+    ) -> Result<(), String> {
+        self.db.export_session_evaluations_to_xlsx(session_id, path)
+            .map_err(|e| e.to_string())
+    }
+
+    // The licence identifies an athlete in the roster, and the name does so
+    // among the athletes without licence
+    fn check_athlete_unique(&self, athlete: &Athlete, exclude_id: Option<i64>) -> Result<(), String> {
+        match &athlete.license_number {
+            Some(license_number) => {
+                if self.db.license_number_taken(license_number, exclude_id).map_err(|e| e.to_string())? {
+                    return Err(format!("Ya existe un atleta con la licencia {}", license_number));
+                }
+            }
+            None => {
+                if self.db.athlete_name_taken(&athlete.name, exclude_id).map_err(|e| e.to_string())? {
+                    return Err(format!("Ya existe un atleta con el nombre {}", athlete.name.trim()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // An evaluation for a given athlete id must not create a new athlete
+    fn check_athlete_exists(&self, athlete_id: Option<i64>) -> Result<(), String> {
+        match athlete_id {
+            Some(id) if !self.db.athlete_exists(id).map_err(|e| e.to_string())? => {
+                Err(format!("No existe el atleta {}", id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Evaluations of a session are run on its track: its geometry is used
+    // when the client sent no configuration, and a different one is rejected
+    fn session_config(&self, input: &EvaluationInput) -> Result<TestConfiguration, String> {
+        let config = evaluation_config(input)?;
+        let Some(session_id) = input.session_id else {
+            return Ok(config);
+        };
+        let session = self.db.get_session(session_id)
+            .map_err(|_| format!("No existe la sesión {}", session_id))?;
+        match session.track_geometry {
+            Some(geometry) if input.config.is_none() => Ok(TestConfiguration {
+                track_length: geometry.track_length,
+                segments_per_lap: geometry.segments_per_lap,
+                ..config
+            }),
+            Some(geometry) if geometry != config.geometry() => Err(format!(
+                "La pista de la evaluación no coincide con la de la sesión ({} m, {} segmentos por vuelta)",
+                geometry.track_length, geometry.segments_per_lap
+            )),
+            _ => Ok(config),
+        }
+    }
+
+    pub async fn create_athlete(&self, athlete: Athlete) -> Result<i64, String> {
+        let athlete = normalize_athlete(athlete);
+        validate_athlete(&athlete)?;
+        self.check_athlete_unique(&athlete, None)?;
+        self.db.create_athlete(&athlete.into())
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn update_athlete(&self, athlete: Athlete) -> Result<(), String> {
+        let id = athlete.id.ok_or("El id del atleta es obligatorio")?;
+        let athlete = normalize_athlete(athlete);
+        validate_athlete(&athlete)?;
+        self.check_athlete_unique(&athlete, Some(id))?;
+        self.db.update_athlete(id, &athlete.into())
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn get_athlete(&self, athlete_id: i64) -> Result<Athlete, String> {
+        self.db.get_athlete(athlete_id)
+            .map(Athlete::from)
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn list_athletes(
+        &self,
+        search: Option<String>,
+        sort_by: AthleteSortField,
+        descending: bool,
+    ) -> Result<Vec<Athlete>, String> {
+        self.db.list_athletes(search.as_deref(), sort_by, descending)
+            .map(|athletes| athletes.into_iter().map(Athlete::from).collect())
+            .map_err(|e| e.to_string())
+    }
+
+    // Anthropometrics of the athlete over time, oldest first
+    pub async fn get_anthropometric_trend(
+        &self,
+        athlete_id: i64,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<Vec<MeasurementPoint>, String> {
+        validate_date_bounds(&from, &to)?;
+        self.db.get_measurement_history(athlete_id, from.as_deref(), to.as_deref())
+            .map(|records| records.into_iter().map(MeasurementPoint::from).collect())
+            .map_err(|e| e.to_string())
+    }
+
+    // Evolution of the athlete's results across the valid tests
+    pub async fn get_athlete_progress(&self, athlete_id: i64) -> Result<AthleteProgress, String> {
+        self.db.get_athlete(athlete_id).map_err(|e| e.to_string())?;
+        self.db.get_test_results(athlete_id)
+            .map(|results| progress::analyze(athlete_id, results))
+            .map_err(|e| e.to_string())
+    }
+
+    // An athlete with evaluations is only deleted with `cascade`, which also
+    // removes the evaluations
+    pub async fn delete_athlete(&self, athlete_id: i64, cascade: bool) -> Result<(), String> {
+        let evaluations = self.db.count_athlete_evaluations(athlete_id).map_err(|e| e.to_string())?;
+        if evaluations > 0 && !cascade {
+            return Err(format!("El atleta tiene {} evaluaciones registradas", evaluations));
+        }
+        self.db.delete_athlete(athlete_id)
+            .map_err(|e| e.to_string())
+    }
+
+    // Makes the data available at `target` before the app switches to it. A
+    // database already there is used as it is once it opens, otherwise the
+    // current one is copied there.
+    pub async fn relocate_database(&self, target: &Path) -> Result<(), String> {
+        if target == self.db.path() {
+            return Ok(());
+        }
+        settings::validate_database_path(target)?;
+        if target.exists() {
+            return Database::new(target).map(|_| ()).map_err(|e| {
+                format!("No se puede abrir la base de datos {}: {}", target.display(), e)
+            });
+        }
+        self.db.copy_to(target).map_err(|e| {
+            format!("No se pudo copiar la base de datos a {}: {}", target.display(), e)
+        })
+    }
+
     pub async fn save_evaluation(
         &self,
-        athlete: Athlete,
-        completed_periods: String,
-        total_time: i32,
-        total_distance: f32,
-        status: String,
+        input: EvaluationInput,
     ) -> Result<(i64, i64, i64), String> {
+        let config = self.session_config(&input)?;
+        let protocol = self.protocol_for(&config).await?;
+
         let current_date = chrono::Local::now().to_rfc3339();
-        let template = EvaluationTemplate {
-            id: None,
-            completed_periods,
-            total_time,
-            date: current_date.clone(),
-            total_distance,
+        let (athlete, template, athlete_evaluation, period_results, warnings) =
+            build_evaluation_data(input, config, &protocol, &current_date)?;
+        self.check_athlete_exists(athlete.id)?;
+
+        self.db.save_evaluation_data(&athlete, &template, &athlete_evaluation, &period_results, &warnings)
+            .map_err(|e| e.to_string())
+    }
+
+    // The protocol table as run on the track of the configuration
+    async fn protocol_for(&self, config: &TestConfiguration) -> Result<Protocol, String> {
+        self.get_protocol(
+            Some(&config.protocol_id),
+            Some(config.protocol_version),
+            Some(config.geometry()),
+        ).await
+    }
+
+    // Without a version the latest one of the protocol is returned. A track
+    // geometry recomputes the protocol times for that track.
+    pub async fn get_protocol(
+        &self,
+        protocol_id: Option<&str>,
+        version: Option<i32>,
+        geometry: Option<TrackGeometry>,
+    ) -> Result<Protocol, String> {
+        let protocol = match protocol_id.unwrap_or(STANDARD_PROTOCOL_ID) {
+            STANDARD_PROTOCOL_ID if version.unwrap_or(1) == 1 => Protocol::standard(),
+            code => {
+                let stored = self.db.get_protocol(code, version)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| match version {
+                        Some(version) => format!("Protocolo desconocido: {} v{}", code, version),
+                        None => format!("Protocolo desconocido: {}", code),
+                    })?;
+                Protocol::generate(&stored.code, stored.version, &stored.name, &stored.parameters)
+            }
+        };
+        let protocol = match geometry {
+            Some(geometry) => {
+                geometry.validate()?;
+                protocol.with_geometry(geometry)
+            }
+            None => protocol,
         };
+        protocol.validate()?;
+        Ok(protocol)
+    }
+
+    // The standard protocol followed by every version of the custom ones
+    pub async fn list_protocols(&self) -> Result<Vec<Protocol>, String> {
+        let stored = self.db.list_protocols().map_err(|e| e.to_string())?;
+        Ok(std::iter::once(Protocol::standard())
+            .chain(stored.into_iter().map(|p| Protocol::generate(&p.code, p.version, &p.name, &p.parameters)))
+            .collect())
+    }
+
+    pub async fn create_protocol(
+        &self,
+        code: String,
+        name: String,
+        parameters: ProtocolParameters,
+    ) -> Result<Protocol, String> {
+        let code = code.trim().to_lowercase();
+        if code.is_empty() {
+            return Err("El código del protocolo es obligatorio".to_string());
+        }
+        if code == STANDARD_PROTOCOL_ID
+            || self.db.get_protocol(&code, None).map_err(|e| e.to_string())?.is_some()
+        {
+            return Err(format!("Ya existe un protocolo con el código {}", code));
+        }
+        self.save_protocol_version(&code, name, parameters)
+    }
+
+    // Protocols are never modified in place, editing one stores a new version
+    pub async fn update_protocol(
+        &self,
+        code: String,
+        name: String,
+        parameters: ProtocolParameters,
+    ) -> Result<Protocol, String> {
+        if code == STANDARD_PROTOCOL_ID {
+            return Err("El protocolo estándar no se puede modificar".to_string());
+        }
+        if self.db.get_protocol(&code, None).map_err(|e| e.to_string())?.is_none() {
+            return Err(format!("Protocolo desconocido: {}", code));
+        }
+        self.save_protocol_version(&code, name, parameters)
+    }
+
+    fn save_protocol_version(
+        &self,
+        code: &str,
+        name: String,
+        parameters: ProtocolParameters,
+    ) -> Result<Protocol, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("El nombre del protocolo es obligatorio".to_string());
+        }
+        parameters.validate()?;
+        // Check the generated table before storing anything
+        Protocol::generate(code, 1, name, &parameters).validate()?;
+
+        let created_at = chrono::Local::now().to_rfc3339();
+        let version = self.db.save_protocol_version(code, name, &parameters, &created_at)
+            .map_err(|e| e.to_string())?;
+        Ok(Protocol::generate(code, version, name, &parameters))
+    }
+
+    // Rebuilds the speeds and distances of a stored evaluation from the
+    // protocol it was run with
+    pub async fn recompute_evaluation(
+        &self,
+        evaluation_id: i64,
+    ) -> Result<(EvaluationTemplate, Vec<PeriodResult>), String> {
+        let (mut template, period_results) = self.db.get_evaluation_period_results(evaluation_id)
+            .map_err(|e| e.to_string())?;
+        let protocol = self.get_protocol(
+            Some(&template.config.protocol_id),
+            Some(template.config.protocol_version),
+            Some(template.config.geometry()),
+        ).await?;
+        let period_results = protocol.recompute_period_results(&period_results)?;
 
-        let athlete_evaluation = AthleteEvaluation::new(
-            None,
-            0, // Will be set by the database
-            0, // Will be set by the database
-            status,
+        if let Some(last) = period_results.last() {
+            template.total_distance = last.cumulative_distance;
+        }
+        let completed_periods: Vec<i32> = serde_json::from_str(&template.completed_periods)
+            .map_err(|_| "Los periodos completados no son válidos".to_string())?;
+        template.aerobic = performance::estimate(
+            &protocol,
+            &completed_periods,
+            template.total_distance,
+            &template.config.vo2max_equation,
         );
 
-        self.db.save_evaluation_data(&athlete.into(), &template.into(), &athlete_evaluation.into())
-            .map_err(|e| e.to_string())
+        let template_id = template.id.ok_or("La evaluación no tiene plantilla")?;
+        self.db.update_period_results(evaluation_id, template_id, &period_results, template.aerobic)
+            .map_err(|e| e.to_string())?;
+        Ok((template.into(), period_results.into_iter().map(Into::into).collect()))
     }
 
     pub async fn get_athlete_evaluations(
         &self,
         athlete_id: i64,
-    ) -> Result<Vec<(AthleteEvaluation, EvaluationTemplate)>, String> {
-        self.db.get_athlete_evaluations(athlete_id)
-            .map(|evals| evals.into_iter().map(|(eval, template)| (eval.into(), template.into())).collect())
-            .map_err(|e| e.to_string())
+    ) -> Result<Vec<(AthleteEvaluation, EvaluationTemplate, Vec<PeriodResult>)>, String> {
+        let evals = self.db.get_athlete_evaluations(athlete_id)
+            .map_err(|e| e.to_string())?;
+        if evals.is_empty() {
+            return Ok(Vec::new());
+        }
+        let athlete = self.db.get_athlete(athlete_id).map_err(|e| e.to_string())?;
+        let norms = self.db.list_norms().map_err(|e| e.to_string())?;
+
+        Ok(evals.into_iter().map(|(eval, template, period_results)| {
+            let (eval, template, _) = classify_record(&norms, (eval, template, athlete.clone()));
+            (eval, template, period_results.into_iter().map(PeriodResult::from).collect())
+        }).collect())
     }
 
     pub async fn export_all_evaluations(
@@ -104,10 +770,135 @@ impl EvaluationService {
             .map_err(|e| e.to_string())
     }
 
-    pub async fn get_all_evaluations(&self) -> Result<Vec<(AthleteEvaluation, EvaluationTemplate, Athlete)>, String> {
+    pub async fn get_all_evaluations(&self) -> Result<Vec<ClassifiedRecord>, String> {
+        let norms = self.db.list_norms().map_err(|e| e.to_string())?;
         self.db.get_all_evaluations()
-            .map(|evals| evals.into_iter().map(|(eval, template, athlete)| 
-                (eval.into(), template.into(), athlete.into())).collect())
+            .map(|evals| evals.into_iter().map(|record| classify_record(&norms, record)).collect())
+            .map_err(|e| e.to_string())
+    }
+
+    // Imports the norms of a CSV file, see `norms::read_csv` for its columns
+    pub async fn import_norms(&self, path: PathBuf, replace: bool) -> Result<usize, String> {
+        let file = std::fs::File::open(&path)
+            .map_err(|e| format!("No se pudo abrir {}: {}", path.display(), e))?;
+        let norms = norms::read_csv(file)?;
+        if norms.is_empty() {
+            return Err("El archivo no contiene normas".to_string());
+        }
+        self.db.import_norms(&norms, replace)
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn list_norms(&self) -> Result<Vec<Norm>, String> {
+        self.db.list_norms()
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn delete_norm(&self, norm_id: i64) -> Result<(), String> {
+        self.db.delete_norm(norm_id)
+            .map_err(|e| e.to_string())
+    }
+
+    // Imports a heart rate file (CSV, TCX or FIT, from its extension) into an
+    // evaluation, replacing any previous one. `offset` places the start of
+    // the recording on the test timeline when the file timestamps cannot.
+    pub async fn import_heart_rate(
+        &self,
+        evaluation_id: i64,
+        path: PathBuf,
+        offset: Option<f32>,
+    ) -> Result<HeartRateAnalysis, String> {
+        let format = HeartRateFormat::from_path(&path)
+            .ok_or("El archivo debe ser CSV, TCX o FIT")?;
+        let bytes = std::fs::read(&path)
+            .map_err(|e| format!("No se pudo abrir {}: {}", path.display(), e))?;
+        let recording = heart_rate::parse(format, &bytes)?;
+
+        let timeline = self.db.get_evaluation_timeline(evaluation_id)
+            .map_err(|e| e.to_string())?;
+        let (start_offset, samples) =
+            heart_rate::align(&recording, timeline.as_ref().map(|t| t.start), offset);
+        let recording = HeartRateRecording {
+            evaluation_id,
+            source: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            format,
+            start_offset,
+            imported_at: chrono::Local::now().to_rfc3339(),
+            samples,
+        };
+        self.db.save_heart_rate(&recording)
+            .map_err(|e| e.to_string())?;
+        Ok(HeartRateAnalysis::new(recording, timeline))
+    }
+
+    pub async fn get_heart_rate(&self, evaluation_id: i64) -> Result<Option<HeartRateAnalysis>, String> {
+        self.db.get_heart_rate(evaluation_id)
+            .map(|stored| stored.map(|(recording, timeline)| HeartRateAnalysis::new(recording, timeline)))
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn delete_heart_rate(&self, evaluation_id: i64) -> Result<(), String> {
+        self.db.delete_heart_rate(evaluation_id)
+            .map_err(|e| e.to_string())
+    }
+
+    // Starts journaling a test run with `protocol`, its UI state is added by
+    // `update_test_journal`
+    pub async fn start_test_journal(
+        &self,
+        protocol: &Protocol,
+        start_period: i32,
+        recovery_time: i32,
+    ) -> Result<(), String> {
+        let now = chrono::Local::now().to_rfc3339();
+        self.db.start_test_journal(&TestJournal {
+            protocol_id: protocol.id.clone(),
+            protocol_version: protocol.version,
+            geometry: protocol.geometry(),
+            start_period,
+            recovery_time,
+            started_at: now.clone(),
+            updated_at: now,
+            elapsed: 0.0,
+            state: None,
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn update_test_journal(&self, elapsed: f32, state: serde_json::Value) -> Result<(), String> {
+        let updated_at = chrono::Local::now().to_rfc3339();
+        self.db.update_test_journal(elapsed, &state, &updated_at)
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn get_test_journal(&self) -> Result<Option<TestJournal>, String> {
+        self.db.get_test_journal()
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn discard_test_journal(&self) -> Result<(), String> {
+        self.db.delete_test_journal()
+            .map_err(|e| e.to_string())
+    }
+
+    // Replaces the lactate and RPE taken after the periods of an evaluation
+    pub async fn save_period_measurements(
+        &self,
+        evaluation_id: i64,
+        measurements: Vec<PeriodMeasurement>,
+    ) -> Result<LactateAnalysis, String> {
+        lactate::validate(&measurements)?;
+        self.db.save_period_measurements(evaluation_id, &measurements)
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn get_warnings(&self, evaluation_id: i64) -> Result<Vec<Warning>, String> {
+        self.db.get_warnings(evaluation_id)
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn get_period_measurements(&self, evaluation_id: i64) -> Result<LactateAnalysis, String> {
+        self.db.get_lactate_analysis(evaluation_id)
             .map_err(|e| e.to_string())
     }
 
@@ -115,8 +906,71 @@ impl EvaluationService {
         &self,
         evaluation_id: i64,
         observations: String,
+        author: Option<String>,
+    ) -> Result<(), String> {
+        let updated_at = chrono::Local::now().to_rfc3339();
+        self.db.update_evaluation_observations(
+            evaluation_id,
+            normalize_text(Some(observations)),
+            normalize_text(author),
+            &updated_at,
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    // Training zones of each athlete from their last valid test. The track
+    // of that test is used unless another one is given.
+    pub async fn get_training_plans(
+        &self,
+        athlete_ids: &[i64],
+        geometry: Option<TrackGeometry>,
+        zones: Option<Vec<TrainingZone>>,
+    ) -> Result<Vec<TrainingPlan>, String> {
+        if let Some(geometry) = &geometry {
+            geometry.validate()?;
+        }
+        let zones = zones.unwrap_or_else(TrainingZone::defaults);
+        training::validate_zones(&zones)?;
+
+        athlete_ids.iter().map(|athlete_id| {
+            let (evaluation, template, athlete) = self.db.get_latest_test(*athlete_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("El atleta {} no tiene tests con VAM estimada", athlete_id))?;
+            let aerobic = template.aerobic.ok_or("La evaluación no tiene VAM estimada")?;
+            let geometry = geometry.unwrap_or_else(|| template.config.geometry());
+            Ok(TrainingPlan {
+                athlete_id: *athlete_id,
+                athlete_name: athlete.name,
+                evaluation_id: evaluation.id.unwrap_or_default(),
+                date: evaluation.date,
+                mas: aerobic.mas,
+                geometry,
+                targets: training::prescribe(aerobic.mas, &geometry, &zones),
+            })
+        }).collect()
+    }
+
+    pub async fn export_training_plans(
+        &self,
+        athlete_ids: &[i64],
+        geometry: Option<TrackGeometry>,
+        zones: Option<Vec<TrainingZone>>,
+        path: PathBuf,
     ) -> Result<(), String> {
-        self.db.update_evaluation_observations(evaluation_id, observations)
+        let plans = self.get_training_plans(athlete_ids, geometry, zones).await?;
+        training::export_csv(path, &plans)
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn export_training_plans_to_xlsx(
+        &self,
+        athlete_ids: &[i64],
+        geometry: Option<TrackGeometry>,
+        zones: Option<Vec<TrainingZone>>,
+        path: PathBuf,
+    ) -> Result<(), String> {
+        let plans = self.get_training_plans(athlete_ids, geometry, zones).await?;
+        training::export_xlsx(path, &plans)
             .map_err(|e| e.to_string())
     }
 
@@ -139,61 +993,372 @@ impl EvaluationService {
 
     pub async fn save_batch_evaluations(
     &self,
-    evaluations: Vec<(Athlete, String, i32, f32, String)>,
+    evaluations: Vec<EvaluationInput>,
 ) -> Result<Vec<(i64, i64, i64)>, String> {
     let current_date = chrono::Local::now().to_rfc3339();
+
+    // Validate every evaluation before touching the database
+    let mut prepared = Vec::with_capacity(evaluations.len());
+    for input in evaluations {
+        self.check_athlete_exists(input.athlete.id)?;
+        let config = self.session_config(&input)?;
+        let protocol = self.protocol_for(&config).await?;
+        prepared.push(build_evaluation_data(input, config, &protocol, &current_date)?);
+    }
+
     let mut conn = self.db.connection.lock().unwrap();
     let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    let mut results = Vec::with_capacity(evaluations.len());
-
-    for (athlete, completed_periods, total_time, total_distance, status) in evaluations {
-        // Validate athlete data
-        if athlete.age <= 0 || athlete.age >= 150 {
-            tx.rollback().map_err(|e| e.to_string())?;
-            return Err("Invalid age".to_string());
-        }
-        if athlete.weight <= 0.0 || athlete.height <= 0.0 {
-            tx.rollback().map_err(|e| e.to_string())?;
-            return Err("Invalid weight or height".to_string());
-        }
-
-        // Insert athlete
-        tx.execute(
-            "INSERT INTO athletes (name, age, weight, height, observations) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                athlete.name,
-                athlete.age,
-                athlete.weight,
-                athlete.height,
-                athlete.observations,
-            ],
-        ).map_err(|e| e.to_string())?;
-        let athlete_id = tx.last_insert_rowid();
-
-        // Insert template
-        tx.execute(
-            "INSERT INTO evaluation_templates (completed_periods, total_time, date, total_distance) VALUES (?1, ?2, ?3, ?4)",
-            params![
-                completed_periods,
-                total_time,
-                current_date,
-                total_distance,
-            ],
-        ).map_err(|e| e.to_string())?;
-        let template_id = tx.last_insert_rowid();
-
-        // Insert evaluation
-        tx.execute(
-            "INSERT INTO athlete_evaluations (athlete_id, template_id, status, date) VALUES (?1, ?2, ?3, ?4)",
-            params![athlete_id, template_id, status, current_date],
-        ).map_err(|e| e.to_string())?;
-        let eval_id = tx.last_insert_rowid();
-
-        results.push((athlete_id, template_id, eval_id));
+    let mut results = Vec::with_capacity(prepared.len());
+    for (athlete, template, athlete_evaluation, period_results, warnings) in prepared {
+        let ids = db::insert_evaluation_data(
+            &tx,
+            &athlete,
+            &template,
+            &athlete_evaluation,
+            &period_results,
+            &warnings,
+        )
+        .map_err(|e| e.to_string())?;
+        results.push(ids);
     }
 
     tx.commit().map_err(|e| e.to_string())?;
     Ok(results)
 }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EvaluationStatus;
+    use std::future::Future;
+    use std::task::{Context, Poll, Waker};
+
+    // The service only awaits its own methods, so nothing is ever pending
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the service future is pending"),
+        }
+    }
+
+    fn service() -> EvaluationService {
+        EvaluationService::new(Arc::new(Database::new(":memory:").unwrap()))
+    }
+
+    fn athlete(name: &str, age: i32, club: Option<&str>, license_number: Option<&str>) -> Athlete {
+        Athlete {
+            id: None,
+            name: name.to_string(),
+            age,
+            weight: 50.0,
+            height: 160.0,
+            observations: None,
+            birth_date: None,
+            sex: None,
+            discipline: None,
+            category: None,
+            club: club.map(str::to_string),
+            license_number: license_number.map(str::to_string),
+        }
+    }
+
+    fn evaluation(athlete: Athlete) -> EvaluationInput {
+        EvaluationInput {
+            athlete,
+            completed_periods: "[2]".to_string(),
+            total_time: 120,
+            total_distance: 800.0,
+            status: EvaluationStatus::Completed,
+            status_reason: None,
+            observations: None,
+            session_id: None,
+            config: None,
+            last_period_elapsed: None,
+            measurements: None,
+            period_results: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rejects_duplicate_athletes() {
+        let service = service();
+        let id = block_on(service.create_athlete(athlete("Ana García", 14, None, None))).unwrap();
+
+        let error = block_on(service.create_athlete(athlete(" ana garcía ", 15, None, None))).unwrap_err();
+        assert_eq!(error, "Ya existe un atleta con el nombre ana garcía");
+        // A namesake with a licence is a different athlete
+        block_on(service.create_athlete(athlete("Ana García", 15, None, Some("L-1")))).unwrap();
+        let error = block_on(service.create_athlete(athlete("Bea", 15, None, Some("L-1")))).unwrap_err();
+        assert_eq!(error, "Ya existe un atleta con la licencia L-1");
+
+        // Updating an athlete keeps its own name and licence
+        let bea = block_on(service.create_athlete(athlete("Bea", 15, None, None))).unwrap();
+        assert!(block_on(service.update_athlete(Athlete { id: Some(id), ..athlete("Ana García", 15, None, None) })).is_ok());
+        assert!(block_on(service.update_athlete(Athlete { id: Some(bea), ..athlete("ANA GARCÍA", 15, None, None) })).is_err());
+        assert!(block_on(service.update_athlete(athlete("Carla", 15, None, None))).is_err());
+    }
+
+    #[test]
+    fn matches_evaluations_to_athletes_by_licence() {
+        let service = service();
+        let save = |name: &str, license_number: Option<&str>| {
+            block_on(service.save_evaluation(evaluation(athlete(name, 14, None, license_number)))).unwrap().0
+        };
+
+        let licensed = save("Ana García", Some("L-1"));
+        let namesake = save("Ana García", Some("L-2"));
+        assert_ne!(licensed, namesake);
+        assert_eq!(save("ANA GARCÍA", Some("L-1")), licensed);
+
+        // Without a licence the name only matches unlicensed athletes
+        let unlicensed = save("Ana García", None);
+        assert!(unlicensed != licensed && unlicensed != namesake);
+        assert_eq!(save(" ana garcía ", None), unlicensed);
+        assert_eq!(block_on(service.list_athletes(None, AthleteSortField::Id, false)).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn deletes_athletes_with_evaluations_only_in_cascade() {
+        let service = service();
+        let (athlete_id, _, _) = block_on(service.save_evaluation(evaluation(athlete("Ana", 14, None, None)))).unwrap();
+
+        let error = block_on(service.delete_athlete(athlete_id, false)).unwrap_err();
+        assert_eq!(error, "El atleta tiene 1 evaluaciones registradas");
+        assert!(block_on(service.get_athlete(athlete_id)).is_ok());
+
+        block_on(service.delete_athlete(athlete_id, true)).unwrap();
+        assert!(block_on(service.get_athlete(athlete_id)).is_err());
+
+        // Athletes without evaluations need no cascade
+        let bea = block_on(service.create_athlete(athlete("Bea", 15, None, None))).unwrap();
+        block_on(service.delete_athlete(bea, false)).unwrap();
+    }
+
+    #[test]
+    fn validates_batches_like_single_evaluations() {
+        let service = service();
+        for invalid in [
+            Athlete { weight: 0.0, ..athlete("Ana", 14, None, None) },
+            Athlete { age: 0, ..athlete("Ana", 14, None, None) },
+        ] {
+            let single = block_on(service.save_evaluation(evaluation(invalid.clone()))).unwrap_err();
+            let batch = block_on(service.save_batch_evaluations(vec![evaluation(invalid)])).unwrap_err();
+            assert_eq!(single, batch);
+        }
+        let error = block_on(service.save_batch_evaluations(vec![evaluation(Athlete {
+            height: 0.0,
+            ..athlete("Ana", 14, None, None)
+        })]))
+        .unwrap_err();
+        assert_eq!(error, "La altura debe ser mayor que 0");
+    }
+
+    #[test]
+    fn rejects_evaluations_of_unknown_athletes() {
+        let service = service();
+        let unknown = Athlete { id: Some(42), ..athlete("Ana", 14, None, None) };
+        let error = block_on(service.save_evaluation(evaluation(unknown.clone()))).unwrap_err();
+        assert_eq!(error, "No existe el atleta 42");
+        assert!(block_on(service.save_batch_evaluations(vec![evaluation(unknown)])).is_err());
+        assert!(block_on(service.list_athletes(None, AthleteSortField::Id, false)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn runs_session_evaluations_on_the_session_track() {
+        let service = service();
+        let geometry = TrackGeometry { track_length: 250.0, segments_per_lap: 5 };
+        let session_id = block_on(service.create_session(Session {
+            id: None,
+            date: "2026-10-18".to_string(),
+            venue: None,
+            surface: None,
+            temperature: None,
+            humidity: None,
+            evaluator: None,
+            recovery_time: None,
+            notes: None,
+            track_geometry: Some(geometry),
+        }))
+        .unwrap();
+        let in_session = |name: &str, config: Option<TestConfiguration>| EvaluationInput {
+            session_id: Some(session_id),
+            config,
+            ..evaluation(athlete(name, 14, None, None))
+        };
+
+        block_on(service.save_evaluation(in_session("Ana", None))).unwrap();
+        let evaluations = service.db.get_all_evaluations().unwrap();
+        assert_eq!(evaluations[0].1.config.geometry(), geometry);
+
+        let standard = Some(TestConfiguration::default());
+        assert!(block_on(service.save_evaluation(in_session("Bea", standard))).is_err());
+        let on_track = Some(TestConfiguration { track_length: 250.0, segments_per_lap: 5, ..TestConfiguration::default() });
+        block_on(service.save_evaluation(in_session("Bea", on_track))).unwrap();
+
+        let error = block_on(service.save_evaluation(EvaluationInput {
+            session_id: Some(99),
+            ..evaluation(athlete("Carla", 14, None, None))
+        }))
+        .unwrap_err();
+        assert_eq!(error, "No existe la sesión 99");
+    }
+
+    #[test]
+    fn deletes_athletes_with_every_record_of_their_tests() {
+        let service = service();
+        let recorded_at = "2026-03-12T10:00:00+01:00".to_string();
+        let (athlete_id, _, evaluation_id) = block_on(service.save_evaluation(EvaluationInput {
+            period_results: vec![PeriodResult {
+                id: None,
+                evaluation_id: 0,
+                period: 2,
+                speed: 20.8,
+                lap_time: 34.61,
+                cumulative_distance: 800.0,
+                recorded_at: recorded_at.clone(),
+                completed: true,
+            }],
+            warnings: vec![Warning { period: 2, segment: 3, elapsed: 100.0, recorded_at }],
+            ..evaluation(athlete("Ana", 14, None, None))
+        }))
+        .unwrap();
+
+        let measurements = vec![PeriodMeasurement { period: 2, lactate: Some(2.1), rpe: Some(6.0) }];
+        block_on(service.save_period_measurements(evaluation_id, measurements)).unwrap();
+        let dir = std::env::temp_dir().join(format!("patinaje-delete-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("fc.csv");
+        std::fs::write(&path, "time,hr\n0,95\n60,150\n120,172\n").unwrap();
+        block_on(service.import_heart_rate(evaluation_id, path, None)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Foreign keys are enforced, so every record must go before the athlete
+        let conn = service.db.connection.lock().unwrap();
+        let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+        assert!(foreign_keys);
+        drop(conn);
+
+        block_on(service.delete_athlete(athlete_id, true)).unwrap();
+        let conn = service.db.connection.lock().unwrap();
+        for table in [
+            "athletes",
+            "athlete_evaluations",
+            "evaluation_templates",
+            "period_results",
+            "period_measurements",
+            "evaluation_warnings",
+            "heart_rate_recordings",
+            "heart_rate_samples",
+        ] {
+            let count: i64 = conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(count, 0, "{} still has rows", table);
+        }
+    }
+
+    #[test]
+    fn exports_the_records_of_each_evaluation() {
+        let service = service();
+        let recorded_at = "2026-03-12T10:00:00+01:00".to_string();
+        let (_, _, evaluation_id) = block_on(service.save_evaluation(EvaluationInput {
+            period_results: vec![PeriodResult {
+                id: None,
+                evaluation_id: 0,
+                period: 2,
+                speed: 20.8,
+                lap_time: 34.61,
+                cumulative_distance: 800.0,
+                recorded_at: recorded_at.clone(),
+                completed: true,
+            }],
+            warnings: vec![Warning { period: 2, segment: 3, elapsed: 100.0, recorded_at }],
+            ..evaluation(athlete("Ana", 14, None, None))
+        }))
+        .unwrap();
+        block_on(service.save_evaluation(evaluation(athlete("Bea", 14, None, None)))).unwrap();
+
+        let measurements = vec![PeriodMeasurement { period: 2, lactate: Some(2.1), rpe: Some(6.0) }];
+        block_on(service.save_period_measurements(evaluation_id, measurements)).unwrap();
+        let dir = std::env::temp_dir().join(format!("patinaje-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("fc.csv");
+        std::fs::write(&path, "time,hr\n0,95\n60,150\n120,172\n").unwrap();
+        block_on(service.import_heart_rate(evaluation_id, path, None)).unwrap();
+
+        let export = dir.join("export.csv");
+        block_on(service.export_all_evaluations(export.clone())).unwrap();
+        let mut reader = csv::Reader::from_path(&export).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let records: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Every record stays on the row of its own evaluation
+        let row = |name: &str| records.iter().find(|r| &r[column("Nombre del Atleta")] == name).unwrap();
+        let (ana, bea) = (row("Ana"), row("Bea"));
+        assert!(ana[column("Avisos")].starts_with("P2.3"));
+        assert_eq!(&ana[column("Lactato/RPE por Periodo")], "P2: 2.1/6");
+        assert!(!ana[column("FC Máx (lpm)")].is_empty());
+        for header in ["Avisos", "Lactato/RPE por Periodo", "FC Máx (lpm)"] {
+            assert!(bea[column(header)].is_empty(), "{} is exported for Bea", header);
+        }
+    }
+
+    #[test]
+    fn relocates_a_populated_database() {
+        let dir = std::env::temp_dir().join(format!("patinaje-relocate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("new")).unwrap();
+        let service = EvaluationService::new(Arc::new(Database::new(dir.join("patinaje.db")).unwrap()));
+        let (athlete_id, _, _) = block_on(service.save_evaluation(evaluation(athlete("Ana", 14, None, None)))).unwrap();
+
+        let target = dir.join("new").join("patinaje.db");
+        block_on(service.relocate_database(&target)).unwrap();
+        let moved = EvaluationService::new(Arc::new(Database::new(&target).unwrap()));
+        assert_eq!(block_on(moved.get_athlete(athlete_id)).unwrap().name, "Ana");
+        assert_eq!(block_on(moved.get_athlete_evaluations(athlete_id)).unwrap().len(), 1);
+
+        // A database already at the target is kept, anything else is refused
+        block_on(moved.create_athlete(athlete("Bea", 15, None, None))).unwrap();
+        block_on(service.relocate_database(&target)).unwrap();
+        assert_eq!(block_on(moved.list_athletes(None, AthleteSortField::Id, false)).unwrap().len(), 2);
+        std::fs::write(dir.join("notes.txt"), "not a database").unwrap();
+        assert!(block_on(service.relocate_database(&dir.join("notes.txt"))).is_err());
+        assert!(block_on(service.relocate_database(&dir.join("missing").join("patinaje.db"))).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn searches_and_sorts_athletes() {
+        let service = service();
+        for roster_athlete in [
+            athlete("carla", 12, None, None),
+            athlete("Ana", 16, Some("Rollers"), None),
+            athlete("Bea", 14, Some("Patín Sur"), Some("L-2")),
+            athlete("Dani", 13, None, Some("L_2%")),
+        ] {
+            block_on(service.create_athlete(roster_athlete)).unwrap();
+        }
+        let names = |search: Option<&str>, sort_by, descending| -> Vec<String> {
+            block_on(service.list_athletes(search.map(str::to_string), sort_by, descending))
+                .unwrap()
+                .into_iter()
+                .map(|athlete| athlete.name)
+                .collect()
+        };
+
+        assert_eq!(names(None, AthleteSortField::Name, false), ["Ana", "Bea", "carla", "Dani"]);
+        assert_eq!(names(None, AthleteSortField::Age, true), ["Ana", "Bea", "Dani", "carla"]);
+        assert_eq!(names(None, AthleteSortField::Age, false), ["carla", "Dani", "Bea", "Ana"]);
+        assert_eq!(names(Some("roll"), AthleteSortField::Name, false), ["Ana"]);
+        assert_eq!(names(Some(" L-2 "), AthleteSortField::Name, false), ["Bea"]);
+        // Wildcards in the term are matched literally
+        assert_eq!(names(Some("L_2"), AthleteSortField::Name, false), ["Dani"]);
+        assert_eq!(names(Some("%"), AthleteSortField::Name, false), ["Dani"]);
+        assert_eq!(names(Some("  "), AthleteSortField::Name, false).len(), 4);
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DATABASE_FILE: &str = "patinaje.db";
+pub const DATABASE_PATH_ENV: &str = "PATINAJE_DB_PATH";
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AppSettings {
+    pub database_path: Option<PathBuf>,
+}
+
+impl AppSettings {
+    pub fn load(config_dir: &Path) -> Self {
+        let path = config_dir.join(SETTINGS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Error reading settings {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, config_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(config_dir)?;
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(config_dir.join(SETTINGS_FILE), contents)
+    }
+}
+
+// The path chosen in the settings wins over the environment variable, which
+// wins over the default location inside the platform app data directory.
+pub fn resolve_database_path(settings: &AppSettings, data_dir: &Path) -> PathBuf {
+    resolve(settings, std::env::var_os(DATABASE_PATH_ENV).map(PathBuf::from), data_dir)
+}
+
+fn resolve(settings: &AppSettings, env_path: Option<PathBuf>, data_dir: &Path) -> PathBuf {
+    settings
+        .database_path
+        .clone()
+        .or(env_path.filter(|p| !p.as_os_str().is_empty()))
+        .unwrap_or_else(|| data_dir.join(DATABASE_FILE))
+}
+
+// A new database path must be a file inside an existing directory where the
+// app can write
+pub fn validate_database_path(path: &Path) -> Result<(), String> {
+    if path.is_dir() {
+        return Err(format!("{} es una carpeta, no un archivo", path.display()));
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => return Err(format!("La ruta {} no es válida", path.display())),
+    };
+    if !parent.is_dir() {
+        return Err(format!("La carpeta {} no existe", parent.display()));
+    }
+
+    // Permissions are not reliable across platforms, so try to write a file
+    let probe = parent.join(format!(".{}.tmp", DATABASE_FILE));
+    fs::write(&probe, b"")
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|_| format!("No se puede escribir en la carpeta {}", parent.display()))
+}
+
+// Files SQLite keeps next to a database while a transaction is not yet
+// written to it
+const SIDECAR_SUFFIXES: [&str; 3] = ["-journal", "-wal", "-shm"];
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// `rename` fails across filesystems, fall back to copying. A copy whose
+// original cannot be removed is still a move, the original is only left
+// behind.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    if let Err(e) = fs::remove_file(from) {
+        eprintln!("Warning: could not remove {} after copying it: {}", from.display(), e);
+    }
+    Ok(())
+}
+
+// Older versions created the database in the working directory `legacy_dir`.
+// Move it to `target` the first time the app starts with a database path that
+// does not exist yet, together with its journal so that no transaction is
+// lost. Returns whether a legacy database was moved.
+pub fn migrate_legacy_database(legacy_dir: &Path, target: &Path) -> io::Result<bool> {
+    let legacy = legacy_dir.join(DATABASE_FILE);
+    if target.exists() || !legacy.is_file() {
+        return Ok(false);
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // The database goes last, an interrupted move is retried on the next start
+    for suffix in SIDECAR_SUFFIXES {
+        let sidecar = with_suffix(&legacy, suffix);
+        if sidecar.is_file() {
+            move_file(&sidecar, &with_suffix(target, suffix))?;
+        }
+    }
+    move_file(&legacy, target)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("patinaje-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn resolves_settings_then_environment_then_app_data() {
+        let data_dir = Path::new("/data");
+        let env_path = Some(PathBuf::from("/env/patinaje.db"));
+        let settings = AppSettings { database_path: Some(PathBuf::from("/settings/patinaje.db")) };
+
+        assert_eq!(resolve(&settings, env_path.clone(), data_dir), PathBuf::from("/settings/patinaje.db"));
+        assert_eq!(resolve(&AppSettings::default(), env_path, data_dir), PathBuf::from("/env/patinaje.db"));
+        assert_eq!(resolve(&AppSettings::default(), Some(PathBuf::new()), data_dir), data_dir.join(DATABASE_FILE));
+        assert_eq!(resolve(&AppSettings::default(), None, data_dir), data_dir.join(DATABASE_FILE));
+    }
+
+    #[test]
+    fn moves_the_legacy_database_once() {
+        let dir = temp_dir("legacy");
+        let target = dir.join("data").join(DATABASE_FILE);
+        fs::write(dir.join(DATABASE_FILE), b"legacy").unwrap();
+
+        assert!(migrate_legacy_database(&dir, &target).unwrap());
+        assert_eq!(fs::read(&target).unwrap(), b"legacy");
+        assert!(!dir.join(DATABASE_FILE).exists());
+
+        // An existing database is never overwritten
+        fs::write(dir.join(DATABASE_FILE), b"other").unwrap();
+        assert!(!migrate_legacy_database(&dir, &target).unwrap());
+        assert_eq!(fs::read(&target).unwrap(), b"legacy");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn moves_the_journal_with_the_legacy_database() {
+        let dir = temp_dir("legacy-wal");
+        let target = dir.join("data").join(DATABASE_FILE);
+        {
+            let conn = rusqlite::Connection::open(dir.join(DATABASE_FILE)).unwrap();
+            conn.pragma_update(None, "journal_mode", "wal").unwrap();
+            conn.execute_batch("CREATE TABLE athletes (name TEXT); INSERT INTO athletes VALUES ('Ana');")
+                .unwrap();
+            // Left as after a crash, with the rows only in the WAL file
+            std::mem::forget(conn);
+        }
+        assert!(dir.join("patinaje.db-wal").is_file());
+
+        assert!(migrate_legacy_database(&dir, &target).unwrap());
+        assert!(!dir.join("patinaje.db-wal").exists());
+        let conn = rusqlite::Connection::open(&target).unwrap();
+        let name: String = conn.query_row("SELECT name FROM athletes", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "Ana");
+        drop(conn);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn settings_survive_a_restart() {
+        let dir = temp_dir("settings");
+        let settings = AppSettings { database_path: Some(dir.join(DATABASE_FILE)) };
+        settings.save(&dir.join("config")).unwrap();
+        assert_eq!(AppSettings::load(&dir.join("config")).database_path, settings.database_path);
+        assert_eq!(AppSettings::load(&dir).database_path, None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn validates_the_database_directory() {
+        let dir = temp_dir("validate");
+        assert!(validate_database_path(&dir.join(DATABASE_FILE)).is_ok());
+        assert!(validate_database_path(&dir).is_err());
+        assert!(validate_database_path(&dir.join("missing").join(DATABASE_FILE)).is_err());
+        assert!(validate_database_path(Path::new(DATABASE_FILE)).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
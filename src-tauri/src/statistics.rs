@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+
+use crate::performance::round_to;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatMetric {
+    FinalPeriod,
+    TotalDistance,
+    Vo2max,
+}
+
+// One valid evaluation of the group. The category is the athlete's current
+// one, not the one they had when tested.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub evaluation_id: i64,
+    pub athlete_id: i64,
+    pub athlete_name: String,
+    // RFC 3339
+    pub date: String,
+    pub category: Option<String>,
+    pub final_period: Option<i32>,
+    pub total_distance: f32,
+    pub vo2max: Option<f32>,
+}
+
+impl StatMetric {
+    pub const ALL: &'static [StatMetric] =
+        &[StatMetric::FinalPeriod, StatMetric::TotalDistance, StatMetric::Vo2max];
+
+    pub fn value(&self, sample: &Sample) -> Option<f32> {
+        match self {
+            StatMetric::FinalPeriod => sample.final_period.map(|period| period as f32),
+            StatMetric::TotalDistance => Some(sample.total_distance),
+            StatMetric::Vo2max => sample.vo2max,
+        }
+    }
+
+    // Periods get one bin each, other metrics use Sturges' rule
+    fn bin_width(&self) -> Option<f32> {
+        match self {
+            StatMetric::FinalPeriod => Some(1.0),
+            _ => None,
+        }
+    }
+}
+
+// Values in [from, to), the last bin also includes `to`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Bin {
+    pub from: f32,
+    pub to: f32,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Summary {
+    pub metric: StatMetric,
+    pub count: usize,
+    pub mean: f32,
+    // Sample standard deviation, None with a single value
+    pub sd: Option<f32>,
+    pub median: f32,
+    pub min: f32,
+    pub max: f32,
+    pub histogram: Vec<Bin>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RankEntry {
+    pub rank: usize,
+    pub evaluation_id: i64,
+    pub athlete_id: i64,
+    pub athlete_name: String,
+    pub value: f32,
+}
+
+// Best value first. Equal values share a rank and the next one is skipped.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Ranking {
+    pub metric: StatMetric,
+    pub entries: Vec<RankEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GroupStatistics {
+    // None for the whole group or the athletes without a category
+    pub category: Option<String>,
+    pub evaluations: usize,
+    // Only metrics with at least one value
+    pub summaries: Vec<Summary>,
+    pub rankings: Vec<Ranking>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SquadStatistics {
+    pub overall: GroupStatistics,
+    pub by_category: Vec<GroupStatistics>,
+}
+
+fn histogram(sorted: &[f32], bin_width: Option<f32>) -> Vec<Bin> {
+    let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+    let (bins, width) = match bin_width {
+        Some(width) => (((max - min) / width).floor() as usize + 1, width),
+        None if max > min => {
+            let bins = (sorted.len() as f32).log2().ceil() as usize + 1;
+            (bins, (max - min) / bins as f32)
+        }
+        None => (1, 0.0),
+    };
+
+    let mut counts = vec![0; bins];
+    for value in sorted {
+        let index = if width > 0.0 { ((value - min) / width).floor() as usize } else { 0 };
+        counts[index.min(bins - 1)] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(index, count)| Bin {
+            from: round_to(min + index as f32 * width, 2),
+            to: round_to(if width > 0.0 { min + (index + 1) as f32 * width } else { max }, 2),
+            count,
+        })
+        .collect()
+}
+
+pub fn summarize(metric: StatMetric, values: &[f32]) -> Option<Summary> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+
+    let count = sorted.len();
+    let mean = sorted.iter().map(|v| *v as f64).sum::<f64>() / count as f64;
+    let sd = (count > 1).then(|| {
+        let variance = sorted.iter().map(|v| (*v as f64 - mean).powi(2)).sum::<f64>()
+            / (count - 1) as f64;
+        round_to(variance.sqrt() as f32, 2)
+    });
+    let median = if count.is_multiple_of(2) {
+        (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+    } else {
+        sorted[count / 2]
+    };
+
+    Some(Summary {
+        metric,
+        count,
+        mean: round_to(mean as f32, 2),
+        sd,
+        median: round_to(median, 2),
+        min: sorted[0],
+        max: sorted[count - 1],
+        histogram: histogram(&sorted, metric.bin_width()),
+    })
+}
+
+fn rank(metric: StatMetric, samples: &[&Sample]) -> Ranking {
+    let mut ranked: Vec<(&Sample, f32)> = samples
+        .iter()
+        .filter_map(|sample| Some((*sample, metric.value(sample)?)))
+        .collect();
+    ranked.sort_by(|(a, a_value), (b, b_value)| {
+        b_value
+            .total_cmp(a_value)
+            .then_with(|| a.athlete_name.to_lowercase().cmp(&b.athlete_name.to_lowercase()))
+    });
+
+    let mut entries: Vec<RankEntry> = Vec::with_capacity(ranked.len());
+    for (index, (sample, value)) in ranked.into_iter().enumerate() {
+        let rank = match entries.last() {
+            Some(previous) if previous.value == value => previous.rank,
+            _ => index + 1,
+        };
+        entries.push(RankEntry {
+            rank,
+            evaluation_id: sample.evaluation_id,
+            athlete_id: sample.athlete_id,
+            athlete_name: sample.athlete_name.clone(),
+            value,
+        });
+    }
+    Ranking { metric, entries }
+}
+
+fn group(category: Option<String>, samples: &[&Sample]) -> GroupStatistics {
+    GroupStatistics {
+        category,
+        evaluations: samples.len(),
+        summaries: StatMetric::ALL
+            .iter()
+            .filter_map(|metric| {
+                let values: Vec<f32> = samples.iter().filter_map(|s| metric.value(s)).collect();
+                summarize(*metric, &values)
+            })
+            .collect(),
+        rankings: StatMetric::ALL.iter().map(|metric| rank(*metric, samples)).collect(),
+    }
+}
+
+// Each athlete counts once in the statistics of a group, with their latest
+// evaluation
+pub fn latest_per_athlete(samples: Vec<Sample>) -> Vec<Sample> {
+    let mut latest: Vec<Sample> = Vec::new();
+    for sample in samples {
+        match latest.iter_mut().find(|s| s.athlete_id == sample.athlete_id) {
+            Some(kept) if (&kept.date, kept.evaluation_id) < (&sample.date, sample.evaluation_id) => {
+                *kept = sample;
+            }
+            Some(_) => {}
+            None => latest.push(sample),
+        }
+    }
+    latest
+}
+
+// Statistics of the whole group and of each category, sorted by name with
+// the athletes without a category last
+pub fn analyze(samples: &[Sample]) -> SquadStatistics {
+    let all: Vec<&Sample> = samples.iter().collect();
+
+    let mut categories: Vec<Option<String>> = samples.iter().map(|s| s.category.clone()).collect();
+    categories.sort_by_key(|category| (category.is_none(), category.clone()));
+    categories.dedup();
+
+    SquadStatistics {
+        overall: group(None, &all),
+        by_category: categories
+            .into_iter()
+            .map(|category| {
+                let members: Vec<&Sample> = all
+                    .iter()
+                    .copied()
+                    .filter(|sample| sample.category == category)
+                    .collect();
+                group(category, &members)
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: i64, name: &str, category: Option<&str>, period: i32, distance: f32) -> Sample {
+        Sample {
+            evaluation_id: id,
+            athlete_id: id,
+            athlete_name: name.to_string(),
+            date: "2026-03-12T10:00:00+01:00".to_string(),
+            category: category.map(str::to_string),
+            final_period: Some(period),
+            total_distance: distance,
+            vo2max: None,
+        }
+    }
+
+    #[test]
+    fn summarizes_values() {
+        let summary = summarize(StatMetric::TotalDistance, &[800.0, 1200.0, 1000.0, 1400.0]).unwrap();
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.mean, 1100.0);
+        assert_eq!(summary.sd, Some(258.2));
+        assert_eq!(summary.median, 1100.0);
+        assert_eq!((summary.min, summary.max), (800.0, 1400.0));
+        // Sturges: 3 bins of 200 m
+        let counts: Vec<usize> = summary.histogram.iter().map(|bin| bin.count).collect();
+        assert_eq!(counts, vec![1, 1, 2]);
+        assert_eq!(summary.histogram[2].to, 1400.0);
+
+        let periods = summarize(StatMetric::FinalPeriod, &[3.0, 5.0, 5.0]).unwrap();
+        let bins: Vec<(f32, usize)> = periods.histogram.iter().map(|bin| (bin.from, bin.count)).collect();
+        assert_eq!(bins, vec![(3.0, 1), (4.0, 0), (5.0, 2)]);
+
+        let single = summarize(StatMetric::Vo2max, &[50.0]).unwrap();
+        assert_eq!(single.sd, None);
+        assert_eq!(single.histogram, vec![Bin { from: 50.0, to: 50.0, count: 1 }]);
+        assert!(summarize(StatMetric::Vo2max, &[]).is_none());
+    }
+
+    #[test]
+    fn ranks_and_groups_by_category() {
+        let samples = vec![
+            sample(1, "Bea", Some("Infantil"), 5, 1000.0),
+            sample(2, "Ana", Some("Infantil"), 5, 1000.0),
+            sample(3, "Carla", None, 4, 900.0),
+            sample(4, "Dani", Some("Cadete"), 6, 1200.0),
+        ];
+        let statistics = analyze(&samples);
+
+        let distance = &statistics.overall.rankings[1];
+        assert_eq!(distance.metric, StatMetric::TotalDistance);
+        let ranks: Vec<(usize, &str)> = distance
+            .entries
+            .iter()
+            .map(|entry| (entry.rank, entry.athlete_name.as_str()))
+            .collect();
+        assert_eq!(ranks, vec![(1, "Dani"), (2, "Ana"), (2, "Bea"), (4, "Carla")]);
+        // No VO2max estimates, so no summary for it
+        assert_eq!(statistics.overall.summaries.len(), 2);
+
+        let categories: Vec<(Option<&str>, usize)> = statistics
+            .by_category
+            .iter()
+            .map(|group| (group.category.as_deref(), group.evaluations))
+            .collect();
+        assert_eq!(categories, vec![(Some("Cadete"), 1), (Some("Infantil"), 2), (None, 1)]);
+    }
+
+    #[test]
+    fn keeps_the_latest_evaluation_of_each_athlete() {
+        let retest = Sample {
+            evaluation_id: 3,
+            athlete_id: 1,
+            date: "2026-05-20T10:00:00+02:00".to_string(),
+            ..sample(3, "Bea", Some("Infantil"), 7, 1400.0)
+        };
+        let samples = vec![
+            retest,
+            sample(1, "Bea", Some("Infantil"), 5, 1000.0),
+            sample(2, "Ana", Some("Infantil"), 6, 1200.0),
+        ];
+        let latest = latest_per_athlete(samples);
+        let kept: Vec<(i64, i64)> = latest.iter().map(|s| (s.athlete_id, s.evaluation_id)).collect();
+        assert_eq!(kept, vec![(1, 3), (2, 2)]);
+        assert_eq!(analyze(&latest).overall.evaluations, 2);
+    }
+}
@@ -0,0 +1,614 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::audio::ThreadSafeAudioPlayer;
+use crate::protocol::Protocol;
+use crate::warnings::{self, EliminationRules, Warning, WarningRecord};
+
+// Countdown before the first period, then the announcement of the period
+// ("work-start" and its number) before it starts
+const PREPARATION_TIME: f64 = 15.0;
+const WORK_ANNOUNCEMENT: f64 = 2.6;
+const PERIOD_NUMBER_DELAY: f64 = 1.8;
+// Announcements inside the recovery, in seconds since it started or before
+// the next period
+const RECOVERY_START_DELAY: f64 = 2.5;
+const RECOVERY_COUNTDOWN: f64 = 10.0;
+const RECOVERY_COMPLETE_LEAD: f64 = WORK_ANNOUNCEMENT + 2.0;
+
+// How often the UI gets a snapshot while the test runs
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Preparation,
+    Work,
+    Recovery,
+    Finished,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct TimerSettings {
+    pub start_period: i32,
+    pub recovery_time: i32,
+}
+
+// Something that happens at a fixed time of the test
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Cue {
+    // Played by the backend, not sent to the UI
+    #[serde(skip_serializing)]
+    Sound(String),
+    // The athletes covered the segment `segment` (from 1) of the period
+    Segment { period: i32, segment: i32 },
+    PeriodCompleted { period: i32, speed: f32, lap_time: f32 },
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PhaseWindow {
+    phase: Phase,
+    // The period being run, or the next one during preparation and recovery
+    period: i32,
+    start: f64,
+    end: f64,
+    partial_time: f64,
+}
+
+// Every phase and cue of a test, in seconds since it was started
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    phases: Vec<PhaseWindow>,
+    cues: Vec<(f64, Cue)>,
+    segments_per_lap: i32,
+    laps_per_period: i32,
+}
+
+impl Schedule {
+    pub fn new(protocol: &Protocol, settings: &TimerSettings) -> Result<Self, String> {
+        if settings.recovery_time < 0 {
+            return Err("El tiempo de recuperación no puede ser negativo".to_string());
+        }
+        let periods: Vec<_> = protocol
+            .periods
+            .iter()
+            .filter(|p| p.period >= settings.start_period)
+            .collect();
+        let first = periods
+            .first()
+            .ok_or_else(|| format!("El protocolo no tiene el periodo {}", settings.start_period))?;
+
+        let sound = |name: &str| Cue::Sound(name.to_string());
+        let announce = |cues: &mut Vec<(f64, Cue)>, at: f64, period: i32| {
+            cues.push((at, sound("work-start")));
+            cues.push((at + PERIOD_NUMBER_DELAY, Cue::Sound(period.to_string())));
+        };
+
+        let mut phases = Vec::new();
+        let mut cues = vec![(0.0, sound("fifteen-seconds"))];
+        announce(&mut cues, PREPARATION_TIME, first.period);
+        let mut time = PREPARATION_TIME + WORK_ANNOUNCEMENT;
+        phases.push(PhaseWindow {
+            phase: Phase::Preparation,
+            period: first.period,
+            start: 0.0,
+            end: time,
+            partial_time: 0.0,
+        });
+
+        let segments = protocol.segments_per_lap * protocol.laps_per_period;
+        let recovery = settings.recovery_time as f64;
+        for (index, period) in periods.iter().enumerate() {
+            let partial_time = period.partial_time as f64;
+            let end = time + partial_time * segments as f64;
+            phases.push(PhaseWindow { phase: Phase::Work, period: period.period, start: time, end, partial_time });
+            for segment in 0..segments {
+                let at = time + partial_time * segment as f64;
+                if segment > 0 {
+                    cues.push((at, Cue::Segment { period: period.period, segment }));
+                }
+                cues.push((at, sound("pi")));
+            }
+            cues.push((end, Cue::Segment { period: period.period, segment: segments }));
+            cues.push((end, sound("work-complete")));
+            cues.push((
+                end,
+                Cue::PeriodCompleted { period: period.period, speed: period.speed, lap_time: period.lap_time },
+            ));
+            time = end;
+
+            let Some(next) = periods.get(index + 1) else {
+                break;
+            };
+            phases.push(PhaseWindow {
+                phase: Phase::Recovery,
+                period: next.period,
+                start: time,
+                end: time + recovery,
+                partial_time: 0.0,
+            });
+            // Announcements that do not fit in a short recovery are dropped
+            let mut last = time;
+            let mut push = |cues: &mut Vec<(f64, Cue)>, offset: f64, name: &str| {
+                if offset >= 0.0 && time + offset >= last {
+                    last = time + offset;
+                    cues.push((last, sound(name)));
+                }
+            };
+            push(&mut cues, RECOVERY_START_DELAY, "recovery-start");
+            push(&mut cues, recovery - RECOVERY_COUNTDOWN, "ten-seconds");
+            push(&mut cues, recovery - RECOVERY_COMPLETE_LEAD, "recovery-complete");
+            if recovery >= WORK_ANNOUNCEMENT && time + recovery - WORK_ANNOUNCEMENT >= last {
+                announce(&mut cues, time + recovery - WORK_ANNOUNCEMENT, next.period);
+            }
+            time += recovery;
+        }
+
+        let last = phases.last().map_or(0.0, |phase| phase.end);
+        cues.push((last, Cue::Finished));
+        // Stable, so cues at the same time keep their order
+        cues.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Schedule {
+            phases,
+            cues,
+            segments_per_lap: protocol.segments_per_lap,
+            laps_per_period: protocol.laps_per_period,
+        })
+    }
+
+    pub fn segments_per_period(&self) -> i32 {
+        self.segments_per_lap * self.laps_per_period
+    }
+
+    // Seconds from the start of the test to the start of the first period
+    pub fn preparation_time(&self) -> f64 {
+        PREPARATION_TIME + WORK_ANNOUNCEMENT
+    }
+
+    pub fn snapshot(&self, elapsed: f64, paused: bool) -> TimerSnapshot {
+        let window = self
+            .phases
+            .iter()
+            .find(|window| elapsed < window.end)
+            .copied();
+        let last_period = self.phases.last().map_or(0, |window| window.period);
+        let mut snapshot = TimerSnapshot {
+            phase: window.map_or(Phase::Finished, |w| w.phase),
+            period: window.map_or(last_period, |w| w.period),
+            paused,
+            elapsed: elapsed as f32,
+            total_time: (elapsed - self.preparation_time()).max(0.0) as f32,
+            work_time: 0.0,
+            recovery_remaining: 0.0,
+            countdown: None,
+            lap: 0,
+            segment: 0,
+            expected_segment: 0,
+        };
+        let Some(window) = window else {
+            return snapshot;
+        };
+
+        let phase_elapsed = elapsed - window.start;
+        match window.phase {
+            Phase::Preparation => {
+                snapshot.countdown = Some((PREPARATION_TIME - elapsed).ceil().max(0.0) as i32);
+            }
+            Phase::Work => {
+                snapshot.work_time = phase_elapsed as f32;
+                let interval = (phase_elapsed / window.partial_time).floor() as i32;
+                snapshot.segment = interval % self.segments_per_lap;
+                snapshot.lap = interval / self.segments_per_lap;
+                snapshot.expected_segment = interval % (self.segments_per_lap * self.laps_per_period);
+            }
+            Phase::Recovery => {
+                snapshot.recovery_remaining = (window.end - elapsed).ceil() as f32;
+            }
+            Phase::Finished => {}
+        }
+        snapshot
+    }
+}
+
+// State of the test clock as shown by the UI. Times are in seconds; `total_time`
+// counts from the start of the first period.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct TimerSnapshot {
+    pub phase: Phase,
+    pub period: i32,
+    pub paused: bool,
+    pub elapsed: f32,
+    pub total_time: f32,
+    pub work_time: f32,
+    pub recovery_remaining: f32,
+    // Seconds left of the preparation countdown
+    pub countdown: Option<i32>,
+    pub lap: i32,
+    pub segment: i32,
+    pub expected_segment: i32,
+}
+
+// Monotonic clock that stops while the test is paused
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    running_since: Option<Instant>,
+    accumulated: Duration,
+}
+
+impl Clock {
+    pub fn started(now: Instant, elapsed: Duration) -> Self {
+        Clock { running_since: Some(now), accumulated: elapsed }
+    }
+
+    pub fn paused(elapsed: Duration) -> Self {
+        Clock { running_since: None, accumulated: elapsed }
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        self.accumulated + self.running_since.map_or(Duration::ZERO, |since| now - since)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.running_since.is_none()
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        self.accumulated = self.elapsed(now);
+        self.running_since = None;
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        self.running_since.get_or_insert(now);
+    }
+}
+
+// A schedule played against a clock. Each cue is returned once, when the
+// clock reaches it.
+pub struct Engine {
+    schedule: Schedule,
+    clock: Clock,
+    next_cue: usize,
+}
+
+impl Engine {
+    pub fn new(schedule: Schedule, clock: Clock) -> Self {
+        Engine { schedule, clock, next_cue: 0 }
+    }
+
+    // A test interrupted at `elapsed`, paused. The cues up to that time were
+    // already handled and are not returned again.
+    pub fn interrupted(schedule: Schedule, elapsed: Duration) -> Self {
+        let seconds = elapsed.as_secs_f64();
+        let next_cue = schedule.cues.iter().take_while(|(at, _)| *at <= seconds).count();
+        Engine { schedule, clock: Clock::paused(elapsed), next_cue }
+    }
+
+    pub fn advance(&mut self, now: Instant) -> (Vec<Cue>, TimerSnapshot) {
+        let elapsed = self.clock.elapsed(now).as_secs_f64();
+        let due = self.schedule.cues[self.next_cue..]
+            .iter()
+            .take_while(|(at, _)| *at <= elapsed)
+            .count();
+        let cues = self.schedule.cues[self.next_cue..self.next_cue + due]
+            .iter()
+            .map(|(_, cue)| cue.clone())
+            .collect();
+        self.next_cue += due;
+        (cues, self.snapshot(now))
+    }
+
+    pub fn snapshot(&self, now: Instant) -> TimerSnapshot {
+        self.schedule.snapshot(self.clock.elapsed(now).as_secs_f64(), self.clock.is_paused())
+    }
+
+    // Warning on the last segment the athletes had to reach, None before the
+    // first one
+    pub fn warning(&self, now: Instant) -> Option<Warning> {
+        let segment = self.schedule.cues[..self.next_cue].iter().rev().find_map(|(_, cue)| match cue {
+            Cue::Segment { period, segment } => Some((*period, *segment)),
+            _ => None,
+        });
+        segment.map(|(period, segment)| Warning {
+            period,
+            segment,
+            elapsed: self.clock.elapsed(now).as_secs_f32(),
+            recorded_at: chrono::Local::now().to_rfc3339(),
+        })
+    }
+
+    // Time until the next cue, None once every cue was returned
+    pub fn until_next_cue(&self, now: Instant) -> Option<Duration> {
+        let (at, _) = self.schedule.cues.get(self.next_cue)?;
+        let elapsed = self.clock.elapsed(now).as_secs_f64();
+        Some(Duration::from_secs_f64((at - elapsed).max(0.0)))
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        self.clock.pause(now);
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        self.clock.resume(now);
+    }
+}
+
+// What the UI receives on the "timer-event" channel
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimerEvent {
+    Tick(TimerSnapshot),
+    Cue(Cue),
+    Stopped(TimerSnapshot),
+}
+
+struct Run {
+    engine: Mutex<Engine>,
+    stopped: AtomicBool,
+}
+
+// Runs the test clock on its own thread, playing the sounds of the test and
+// emitting its progress to the UI
+pub struct TestTimer {
+    app: AppHandle,
+    audio: Arc<ThreadSafeAudioPlayer>,
+    current: Mutex<Option<Arc<Run>>>,
+}
+
+impl TestTimer {
+    pub fn new(app: AppHandle, audio: Arc<ThreadSafeAudioPlayer>) -> Self {
+        TestTimer { app, audio, current: Mutex::new(None) }
+    }
+
+    fn emit(&self, event: TimerEvent) {
+        emit(&self.app, event);
+    }
+
+    // Replaces any test in progress
+    pub fn start(&self, schedule: Schedule) -> TimerSnapshot {
+        self.run(Engine::new(schedule, Clock::started(Instant::now(), Duration::ZERO)))
+    }
+
+    // Continues an interrupted test, paused at `elapsed` seconds
+    pub fn restore(&self, schedule: Schedule, elapsed: f32) -> TimerSnapshot {
+        self.run(Engine::interrupted(schedule, Duration::from_secs_f32(elapsed.max(0.0))))
+    }
+
+    fn run(&self, engine: Engine) -> TimerSnapshot {
+        let snapshot = engine.snapshot(Instant::now());
+        let run = Arc::new(Run { engine: Mutex::new(engine), stopped: AtomicBool::new(false) });
+        if let Some(previous) = self.current.lock().unwrap().replace(run.clone()) {
+            previous.stopped.store(true, Ordering::SeqCst);
+        }
+
+        let app = self.app.clone();
+        let audio = self.audio.clone();
+        std::thread::spawn(move || run_loop(&app, &audio, &run));
+        snapshot
+    }
+
+    fn with_engine<T>(&self, f: impl FnOnce(&mut Engine) -> T) -> Result<T, String> {
+        let current = self.current.lock().unwrap();
+        let run = current.as_ref().ok_or("No hay ningún test en curso")?;
+        let mut engine = run.engine.lock().unwrap();
+        Ok(f(&mut engine))
+    }
+
+    pub fn pause(&self) -> Result<TimerSnapshot, String> {
+        let snapshot = self.with_engine(|engine| {
+            let now = Instant::now();
+            engine.pause(now);
+            engine.snapshot(now)
+        })?;
+        let _ = self.audio.stop_all();
+        let _ = self.audio.play_sound("test-paused");
+        self.emit(TimerEvent::Tick(snapshot));
+        Ok(snapshot)
+    }
+
+    pub fn resume(&self) -> Result<TimerSnapshot, String> {
+        let snapshot = self.with_engine(|engine| {
+            let now = Instant::now();
+            engine.resume(now);
+            engine.snapshot(now)
+        })?;
+        self.emit(TimerEvent::Tick(snapshot));
+        Ok(snapshot)
+    }
+
+    pub fn stop(&self) -> Result<TimerSnapshot, String> {
+        let run = self.current.lock().unwrap().take().ok_or("No hay ningún test en curso")?;
+        run.stopped.store(true, Ordering::SeqCst);
+        let snapshot = run.engine.lock().unwrap().snapshot(Instant::now());
+        let _ = self.audio.stop_all();
+        self.emit(TimerEvent::Stopped(snapshot));
+        Ok(snapshot)
+    }
+
+    pub fn snapshot(&self) -> Option<TimerSnapshot> {
+        self.with_engine(|engine| engine.snapshot(Instant::now())).ok()
+    }
+
+    // Adds a warning on the last beep to the `warnings` of an athlete
+    pub fn record_warning(
+        &self,
+        warnings: Vec<Warning>,
+        rules: &EliminationRules,
+    ) -> Result<WarningRecord, String> {
+        let (warning, segments_per_period) = self.with_engine(|engine| {
+            let warning = engine.warning(Instant::now());
+            (warning, engine.schedule.segments_per_period())
+        })?;
+        let warning = warning.ok_or("Todavía no ha sonado ningún pitido de segmento")?;
+        warnings::record(warnings, warning, rules, segments_per_period)
+    }
+}
+
+fn emit(app: &AppHandle, event: TimerEvent) {
+    if let Err(e) = app.emit("timer-event", event) {
+        eprintln!("Error emitting timer event: {}", e);
+    }
+}
+
+fn run_loop(app: &AppHandle, audio: &ThreadSafeAudioPlayer, run: &Run) {
+    while !run.stopped.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        let (cues, snapshot, wait) = {
+            let mut engine = run.engine.lock().unwrap();
+            let (cues, snapshot) = engine.advance(now);
+            (cues, snapshot, engine.until_next_cue(now))
+        };
+        if run.stopped.load(Ordering::SeqCst) {
+            break;
+        }
+
+        for cue in cues {
+            match cue {
+                Cue::Sound(name) => {
+                    if let Err(e) = audio.play_sound(&name) {
+                        eprintln!("Error playing sound {}: {}", name, e);
+                    }
+                }
+                cue => emit(app, TimerEvent::Cue(cue)),
+            }
+        }
+        if !snapshot.paused {
+            emit(app, TimerEvent::Tick(snapshot));
+        }
+        let Some(wait) = wait else {
+            // Every cue was played, the test is over
+            break;
+        };
+        // Wake up for the next cue even between ticks, and keep ticking while
+        // paused so that a resume is noticed
+        std::thread::sleep(if snapshot.paused { TICK_INTERVAL } else { wait.min(TICK_INTERVAL) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standard_schedule(recovery_time: i32) -> Schedule {
+        let protocol = Protocol::standard();
+        let start_period = protocol.periods[protocol.periods.len() - 2].period;
+        Schedule::new(&protocol, &TimerSettings { start_period, recovery_time }).unwrap()
+    }
+
+    fn at(schedule: &Schedule, cue: &Cue) -> f64 {
+        schedule.cues.iter().find(|(_, c)| c == cue).unwrap().0
+    }
+
+    #[test]
+    fn schedules_periods_and_recoveries() {
+        let protocol = Protocol::standard();
+        let last = &protocol.periods[protocol.periods.len() - 1];
+        let previous = &protocol.periods[protocol.periods.len() - 2];
+        let schedule = standard_schedule(45);
+
+        let start = PREPARATION_TIME + WORK_ANNOUNCEMENT;
+        let segments = protocol.segments_per_lap * protocol.laps_per_period;
+        let first_end = start + previous.partial_time as f64 * segments as f64;
+        let first = Cue::Segment { period: previous.period, segment: 1 };
+        assert!((at(&schedule, &first) - (start + previous.partial_time as f64)).abs() < 1e-9);
+        let completed = Cue::PeriodCompleted { period: previous.period, speed: previous.speed, lap_time: previous.lap_time };
+        assert!((at(&schedule, &completed) - first_end).abs() < 1e-9);
+        let segment_cues = schedule.cues.iter().filter(|(_, c)| matches!(c, Cue::Segment { .. })).count();
+        assert_eq!(segment_cues as i32, 2 * segments);
+
+        // The next period starts after the recovery, announced before it ends
+        let next = Cue::Segment { period: last.period, segment: 1 };
+        let next_start = first_end + 45.0;
+        assert!((at(&schedule, &next) - (next_start + last.partial_time as f64)).abs() < 1e-9);
+        let number = Cue::Sound(last.period.to_string());
+        assert!((at(&schedule, &number) - (next_start - WORK_ANNOUNCEMENT + PERIOD_NUMBER_DELAY)).abs() < 1e-9);
+        assert_eq!(schedule.cues.last().unwrap().1, Cue::Finished);
+
+        // Short recoveries drop the announcements that do not fit
+        let short = standard_schedule(3);
+        assert!(short.cues.iter().all(|(_, c)| *c != Cue::Sound("ten-seconds".to_string())));
+        assert!(short.cues.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    }
+
+    #[test]
+    fn snapshots_follow_the_phases() {
+        let protocol = Protocol::standard();
+        let period = &protocol.periods[protocol.periods.len() - 2];
+        let schedule = standard_schedule(45);
+        let start = schedule.preparation_time();
+
+        let preparation = schedule.snapshot(1.2, false);
+        assert_eq!((preparation.phase, preparation.countdown), (Phase::Preparation, Some(14)));
+        assert_eq!(preparation.total_time, 0.0);
+
+        let work = schedule.snapshot(start + period.partial_time as f64 * 5.5, false);
+        assert_eq!((work.phase, work.period), (Phase::Work, period.period));
+        assert_eq!((work.lap, work.segment, work.expected_segment), (1, 1, 5));
+
+        let end = start + period.total_time as f64;
+        let recovery = schedule.snapshot(end + 10.2, true);
+        assert_eq!((recovery.phase, recovery.period), (Phase::Recovery, period.period + 1));
+        assert_eq!(recovery.recovery_remaining, 35.0);
+        assert!(recovery.paused);
+
+        assert_eq!(schedule.snapshot(1e6, false).phase, Phase::Finished);
+    }
+
+    #[test]
+    fn engine_returns_each_cue_once_and_stops_while_paused() {
+        let now = Instant::now();
+        let mut engine = Engine::new(standard_schedule(45), Clock::started(now, Duration::ZERO));
+
+        let (cues, _) = engine.advance(now);
+        assert_eq!(cues, vec![Cue::Sound("fifteen-seconds".to_string())]);
+        assert!(engine.advance(now).0.is_empty());
+
+        engine.pause(now + Duration::from_secs(5));
+        let (cues, snapshot) = engine.advance(now + Duration::from_secs(60));
+        assert!(cues.is_empty());
+        assert_eq!(snapshot.elapsed, 5.0);
+        assert_eq!(engine.until_next_cue(now + Duration::from_secs(60)), Some(Duration::from_secs(10)));
+
+        engine.resume(now + Duration::from_secs(60));
+        let (cues, snapshot) = engine.advance(now + Duration::from_secs(70));
+        assert_eq!(cues, vec![Cue::Sound("work-start".to_string())]);
+        assert_eq!(snapshot.countdown, Some(0));
+    }
+
+    #[test]
+    fn interrupted_engine_skips_handled_cues() {
+        let schedule = standard_schedule(45);
+        let (segment_at, _) = schedule.cues.iter().find(|(_, c)| matches!(c, Cue::Segment { .. })).unwrap().clone();
+        let now = Instant::now();
+        let mut engine = Engine::interrupted(schedule, Duration::from_secs_f64(segment_at));
+
+        let (cues, snapshot) = engine.advance(now + Duration::from_secs(10));
+        assert!(cues.is_empty() && snapshot.paused);
+        engine.resume(now + Duration::from_secs(10));
+        let (cues, _) = engine.advance(now + Duration::from_secs(10));
+        assert!(cues.is_empty());
+    }
+
+    #[test]
+    fn warns_on_the_last_segment() {
+        let schedule = standard_schedule(45);
+        let period = schedule.phases[1];
+        let now = Instant::now();
+        let mut engine = Engine::new(schedule, Clock::started(now, Duration::ZERO));
+
+        engine.advance(now + Duration::from_secs_f64(period.start + period.partial_time * 0.5));
+        assert_eq!(engine.warning(now), None);
+
+        // During the recovery the last beep is the end of the period
+        let later = now + Duration::from_secs_f64(period.end + 5.0);
+        engine.advance(later);
+        let warning = engine.warning(later).unwrap();
+        let segments = engine.schedule.segments_per_period();
+        assert_eq!((warning.period, warning.segment), (period.period, segments));
+        assert!((warning.elapsed as f64 - (period.end + 5.0)).abs() < 1e-3);
+    }
+}
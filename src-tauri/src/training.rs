@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::db::{decimal, write_table_csv, write_table_xlsx, ExportCell};
+use crate::performance::round_to;
+use crate::protocol::TrackGeometry;
+
+// Intensity band as a percentage of the maximal aerobic speed
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TrainingZone {
+    pub name: String,
+    pub min_percent: f32,
+    pub max_percent: f32,
+}
+
+impl TrainingZone {
+    fn new(name: &str, min_percent: f32, max_percent: f32) -> Self {
+        TrainingZone { name: name.to_string(), min_percent, max_percent }
+    }
+
+    // Zones commonly used to prescribe intervals from a shuttle or track test
+    pub fn defaults() -> Vec<TrainingZone> {
+        vec![
+            TrainingZone::new("Z1 Regenerativo", 60.0, 70.0),
+            TrainingZone::new("Z2 Aeróbico extensivo", 70.0, 85.0),
+            TrainingZone::new("Z3 Umbral", 85.0, 95.0),
+            TrainingZone::new("Z4 VAM", 95.0, 105.0),
+            TrainingZone::new("Z5 Anaeróbico", 105.0, 120.0),
+        ]
+    }
+}
+
+pub fn validate_zones(zones: &[TrainingZone]) -> Result<(), String> {
+    if zones.is_empty() {
+        return Err("Debe definir al menos una zona de entrenamiento".to_string());
+    }
+    for zone in zones {
+        if zone.name.trim().is_empty() {
+            return Err("Cada zona de entrenamiento debe tener un nombre".to_string());
+        }
+        if !(zone.min_percent > 0.0 && zone.min_percent < zone.max_percent) {
+            return Err(format!("El rango de la zona {} no es válido", zone.name));
+        }
+    }
+    Ok(())
+}
+
+// Target speeds (km/h) and times (s) of a zone. The shortest times match
+// the top of the zone.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ZoneTarget {
+    pub zone: TrainingZone,
+    pub speed_min: f32,
+    pub speed_max: f32,
+    pub lap_time_min: f32,
+    pub lap_time_max: f32,
+    pub segment_time_min: f32,
+    pub segment_time_max: f32,
+}
+
+// Zones of one athlete, from the last valid test with an aerobic estimate
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TrainingPlan {
+    pub athlete_id: i64,
+    pub athlete_name: String,
+    pub evaluation_id: i64,
+    pub date: String,
+    pub mas: f32,
+    pub geometry: TrackGeometry,
+    pub targets: Vec<ZoneTarget>,
+}
+
+fn seconds(distance: f32, speed: f32) -> f32 {
+    round_to(distance / (speed / 3.6), 1)
+}
+
+pub fn prescribe(mas: f32, geometry: &TrackGeometry, zones: &[TrainingZone]) -> Vec<ZoneTarget> {
+    zones
+        .iter()
+        .map(|zone| {
+            let speed_min = mas * zone.min_percent / 100.0;
+            let speed_max = mas * zone.max_percent / 100.0;
+            ZoneTarget {
+                zone: zone.clone(),
+                speed_min: round_to(speed_min, 2),
+                speed_max: round_to(speed_max, 2),
+                lap_time_min: seconds(geometry.track_length, speed_max),
+                lap_time_max: seconds(geometry.track_length, speed_min),
+                segment_time_min: seconds(geometry.segment_length(), speed_max),
+                segment_time_max: seconds(geometry.segment_length(), speed_min),
+            }
+        })
+        .collect()
+}
+
+const TRAINING_HEADERS: [&str; 14] = [
+    "Atleta ID",
+    "Nombre del Atleta",
+    "Fecha del Test",
+    "VAM (km/h)",
+    "Pista (m)",
+    "Segmentos por Vuelta",
+    "Zona",
+    "% VAM Mín",
+    "% VAM Máx",
+    "Velocidad Mín (km/h)",
+    "Velocidad Máx (km/h)",
+    "Tiempo por Vuelta (s)",
+    "Tiempo por Segmento (s)",
+    "Evaluación ID",
+];
+
+// Fastest time first
+fn time_range(min: f32, max: f32) -> String {
+    format!("{}–{}", min, max)
+}
+
+fn rows(plans: &[TrainingPlan]) -> impl Iterator<Item = Vec<ExportCell>> + '_ {
+    plans.iter().flat_map(|plan| {
+        plan.targets.iter().map(move |target| {
+            vec![
+                ExportCell::Number(plan.athlete_id as f64),
+                ExportCell::Text(plan.athlete_name.clone()),
+                ExportCell::Text(plan.date.clone()),
+                ExportCell::Number(decimal(plan.mas, 2)),
+                ExportCell::Number(decimal(plan.geometry.track_length, 2)),
+                ExportCell::Number(plan.geometry.segments_per_lap as f64),
+                ExportCell::Text(target.zone.name.clone()),
+                ExportCell::Number(decimal(target.zone.min_percent, 1)),
+                ExportCell::Number(decimal(target.zone.max_percent, 1)),
+                ExportCell::Number(decimal(target.speed_min, 2)),
+                ExportCell::Number(decimal(target.speed_max, 2)),
+                ExportCell::Text(time_range(target.lap_time_min, target.lap_time_max)),
+                ExportCell::Text(time_range(target.segment_time_min, target.segment_time_max)),
+                ExportCell::Number(plan.evaluation_id as f64),
+            ]
+        })
+    })
+}
+
+pub fn export_csv<P: AsRef<Path>>(path: P, plans: &[TrainingPlan]) -> Result<(), Box<dyn std::error::Error>> {
+    write_table_csv(path, &TRAINING_HEADERS, rows(plans))
+}
+
+pub fn export_xlsx<P: AsRef<Path>>(path: P, plans: &[TrainingPlan]) -> Result<(), Box<dyn std::error::Error>> {
+    write_table_xlsx(path, &TRAINING_HEADERS, rows(plans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prescribes_speeds_and_times() {
+        let geometry = TrackGeometry { track_length: 200.0, segments_per_lap: 4 };
+        let zones = vec![TrainingZone::new("VAM", 90.0, 100.0)];
+        let targets = prescribe(20.0, &geometry, &zones);
+
+        assert_eq!(targets.len(), 1);
+        assert_eq!((targets[0].speed_min, targets[0].speed_max), (18.0, 20.0));
+        // 200 m at 20 km/h = 36 s, at 18 km/h = 40 s
+        assert_eq!((targets[0].lap_time_min, targets[0].lap_time_max), (36.0, 40.0));
+        assert_eq!((targets[0].segment_time_min, targets[0].segment_time_max), (9.0, 10.0));
+    }
+
+    #[test]
+    fn validates_zones() {
+        assert!(validate_zones(&TrainingZone::defaults()).is_ok());
+        assert!(validate_zones(&[]).is_err());
+        assert!(validate_zones(&[TrainingZone::new("Z1", 80.0, 70.0)]).is_err());
+        assert!(validate_zones(&[TrainingZone::new(" ", 60.0, 70.0)]).is_err());
+    }
+}
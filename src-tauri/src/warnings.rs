@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+// A beep on which the athlete had not reached the cone of the segment
+// `segment` (from 1 to the segments of a period) of the period. `elapsed` is the clock time of the test
+// when it was recorded, in seconds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Warning {
+    pub period: i32,
+    pub segment: i32,
+    pub elapsed: f32,
+    pub recorded_at: String,
+}
+
+// An athlete is eliminated after `consecutive_warnings` warnings on beeps
+// that follow each other, or after `max_warnings` in the whole test
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct EliminationRules {
+    pub consecutive_warnings: i32,
+    pub max_warnings: Option<i32>,
+}
+
+impl Default for EliminationRules {
+    fn default() -> Self {
+        EliminationRules { consecutive_warnings: 2, max_warnings: None }
+    }
+}
+
+impl EliminationRules {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.consecutive_warnings < 1 {
+            return Err("Los avisos consecutivos para eliminar deben ser al menos 1".to_string());
+        }
+        if self.max_warnings.is_some_and(|max| max < 1) {
+            return Err("El máximo de avisos debe ser al menos 1".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn label(&self) -> String {
+        match self.max_warnings {
+            Some(max) => format!("{} seguidos o {} en total", self.consecutive_warnings, max),
+            None => format!("{} seguidos", self.consecutive_warnings),
+        }
+    }
+}
+
+pub fn validate(warnings: &[Warning], segments_per_period: i32) -> Result<(), String> {
+    for (index, warning) in warnings.iter().enumerate() {
+        if warning.period < 1 || warning.segment < 1 || warning.segment > segments_per_period {
+            return Err(format!(
+                "El aviso del periodo {}, segmento {} no es válido",
+                warning.period, warning.segment
+            ));
+        }
+        if warning.elapsed < 0.0 {
+            return Err("El tiempo de un aviso no puede ser negativo".to_string());
+        }
+        if warnings[..index]
+            .iter()
+            .any(|w| w.period == warning.period && w.segment == warning.segment)
+        {
+            return Err(format!(
+                "El aviso del periodo {}, segmento {} está repetido",
+                warning.period, warning.segment
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct WarningStatus {
+    pub total: i32,
+    // Warnings in a row up to the last one
+    pub consecutive: i32,
+    pub eliminated: bool,
+}
+
+// Beeps are numbered across periods, so the last segment of a period and the
+// first of the next one follow each other
+fn beep(warning: &Warning, segments_per_period: i32) -> i32 {
+    warning.period * segments_per_period + warning.segment
+}
+
+pub fn status(warnings: &[Warning], rules: &EliminationRules, segments_per_period: i32) -> WarningStatus {
+    let mut beeps: Vec<i32> = warnings.iter().map(|w| beep(w, segments_per_period)).collect();
+    beeps.sort_unstable();
+    beeps.dedup();
+
+    let mut longest = 0;
+    let mut consecutive = 0;
+    for (index, beep) in beeps.iter().enumerate() {
+        consecutive = match index.checked_sub(1).map(|previous| beeps[previous]) {
+            Some(previous) if previous + 1 == *beep => consecutive + 1,
+            _ => 1,
+        };
+        longest = longest.max(consecutive);
+    }
+
+    let total = beeps.len() as i32;
+    WarningStatus {
+        total,
+        consecutive,
+        eliminated: longest >= rules.consecutive_warnings
+            || rules.max_warnings.is_some_and(|max| total >= max),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WarningRecord {
+    pub warning: Warning,
+    pub warnings: Vec<Warning>,
+    pub status: WarningStatus,
+}
+
+// Adds a warning to the history of an athlete and applies the rules to it
+pub fn record(
+    mut warnings: Vec<Warning>,
+    warning: Warning,
+    rules: &EliminationRules,
+    segments_per_period: i32,
+) -> Result<WarningRecord, String> {
+    rules.validate()?;
+    warnings.push(warning.clone());
+    validate(&warnings, segments_per_period)?;
+    let status = status(&warnings, rules, segments_per_period);
+    Ok(WarningRecord { warning, warnings, status })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warning(period: i32, segment: i32) -> Warning {
+        Warning { period, segment, elapsed: 0.0, recorded_at: String::new() }
+    }
+
+    #[test]
+    fn counts_consecutive_warnings_across_periods() {
+        let rules = EliminationRules { consecutive_warnings: 3, max_warnings: None };
+        // 8 segments per period, 5.8 and 6.1 follow each other
+        let warnings = [warning(5, 2), warning(5, 7), warning(5, 8), warning(6, 1)];
+        assert_eq!(
+            status(&warnings, &rules, 8),
+            WarningStatus { total: 4, consecutive: 3, eliminated: true }
+        );
+
+        // Reaching the cone of 5.8 breaks the run
+        let reached = [warning(5, 7), warning(6, 1), warning(6, 2)];
+        assert_eq!(
+            status(&reached, &rules, 8),
+            WarningStatus { total: 3, consecutive: 2, eliminated: false }
+        );
+    }
+
+    #[test]
+    fn eliminates_on_the_total_warnings() {
+        let rules = EliminationRules { consecutive_warnings: 2, max_warnings: Some(3) };
+        let warnings = vec![warning(3, 1), warning(3, 4)];
+        assert!(!status(&warnings, &rules, 8).eliminated);
+
+        let record = record(warnings, warning(4, 2), &rules, 8).unwrap();
+        assert_eq!(record.warnings.len(), 3);
+        assert_eq!(record.status.consecutive, 1);
+        assert!(record.status.eliminated);
+    }
+
+    #[test]
+    fn rejects_repeated_warnings() {
+        let rules = EliminationRules::default();
+        assert!(record(vec![warning(3, 1)], warning(3, 1), &rules, 8).is_err());
+        assert!(record(Vec::new(), warning(3, 0), &rules, 8).is_err());
+        // 5.9 would be taken for 6.1 with 8 segments per period
+        assert!(record(Vec::new(), warning(5, 9), &rules, 8).is_err());
+        assert!(validate(&[warning(5, 8)], 8).is_ok());
+        let invalid = EliminationRules { consecutive_warnings: 0, max_warnings: None };
+        assert!(record(Vec::new(), warning(3, 1), &invalid, 8).is_err());
+    }
+}
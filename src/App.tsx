@@ -1,14 +1,14 @@
+import { useEffect } from "react";
 import { Toaster } from "sonner";
 import "./App.css";
 import { AthleteManager } from "./components/AthleteManager";
 import { ControlPanel } from "./components/ControlPanel";
 import { FinishModal } from "./components/FinishModal";
+import { RecoverTestModal } from "./components/RecoverTestModal";
 import { StatusDisplay } from "./components/StatusDisplay";
 import { Timer } from "./components/Timer";
 import { Track } from "./components/Track";
-import { useRecoveryTimer } from "./hooks/useRecoveryTimer";
-import { useTotalTimer } from "./hooks/useTotalTimer";
-import { useWorkTimer } from "./hooks/useWorkTimer";
+import { useTestTimer } from "./hooks/useTestTimer";
 import { useStore } from "./store";
 
 function App() {
@@ -23,21 +23,31 @@ function App() {
 		prepCountdown,
 		showFinishModal,
 		setShowFinishModal,
+		protocolLoaded,
+		loadInitialProtocol,
 		finishTest,
 		updatePeriod,
+		selectSession,
+		selectProtocol,
+		updateTrackGeometry,
 		updateRecoveryTime,
+		updateVo2maxEquation,
+		updateEliminationRules,
 		updateAthleteCount,
 		startTest,
 		pauseTest,
 		resetApp,
 		updateAthlete,
 		finishAthlete,
+		recordWarning,
 	} = useStore();
 
-	// Use the custom hooks for timers
-	useWorkTimer();
-	useRecoveryTimer();
-	useTotalTimer();
+	useEffect(() => {
+		loadInitialProtocol();
+	}, [loadInitialProtocol]);
+
+	// The test clock runs in the backend
+	useTestTimer();
 
 	return (
 		<div className="container mx-auto px-4 py-8">
@@ -72,11 +82,17 @@ function App() {
 
 				<ControlPanel
 					config={config}
+					protocolLoaded={protocolLoaded}
 					onStart={startTest}
 					onPause={pauseTest}
 					onReset={resetApp}
 					onPeriodChange={updatePeriod}
+					onSessionChange={selectSession}
+					onProtocolChange={selectProtocol}
+					onTrackGeometryChange={updateTrackGeometry}
 					onRecoveryTimeChange={updateRecoveryTime}
+					onVo2maxEquationChange={updateVo2maxEquation}
+					onEliminationRulesChange={updateEliminationRules}
 					onAthleteCountChange={updateAthleteCount}
 				/>
 
@@ -84,6 +100,7 @@ function App() {
 					athletes={athletes}
 					onAthleteUpdate={updateAthlete}
 					onFinishAthlete={finishAthlete}
+					onRecordWarning={recordWarning}
 					isRunning={config.isRunning}
 				/>
 			</div>
@@ -94,6 +111,8 @@ function App() {
 				onConfirm={finishTest}
 			/>
 
+			<RecoverTestModal />
+
 			<Toaster position="top-center" richColors />
 		</div>
 	);
@@ -1,12 +1,45 @@
 import { User } from "lucide-react";
-import type { Athlete } from "../types";
+import type { Athlete, Discipline, Sex } from "../types";
 import { EvaluationHistory } from "./EvaluationHistory";
 import { ExportEvaluations } from "./ExportEvaluations";
+import { ImportNorms } from "./ImportNorms";
+import { SquadStatistics } from "./SquadStatistics";
+
+const sexLabels: Record<Sex, string> = {
+	female: "Femenino",
+	male: "Masculino",
+};
+
+const disciplineLabels: Record<Discipline, string> = {
+	inline_speed: "Velocidad en línea",
+	quad: "Patín tradicional",
+	artistic: "Artístico",
+	hockey: "Hockey",
+};
+
+// Optional measurements recorded with the evaluation
+const measurementFields: {
+	key: "bodyFat" | "legLength";
+	label: string;
+}[] = [
+	{ key: "bodyFat", label: "Grasa corporal (%)" },
+	{ key: "legLength", label: "Longitud de pierna (cm)" },
+];
+
+const profileTextFields: {
+	key: "category" | "club" | "licenseNumber";
+	label: string;
+}[] = [
+	{ key: "category", label: "Categoría" },
+	{ key: "club", label: "Club" },
+	{ key: "licenseNumber", label: "Licencia" },
+];
 
 interface AthleteManagerProps {
 	athletes: Athlete[];
 	onAthleteUpdate: (athlete: Athlete) => void;
 	onFinishAthlete: (athleteId: number) => void;
+	onRecordWarning: (athleteId: number) => void;
 	isRunning: boolean;
 }
 
@@ -14,13 +47,17 @@ export const AthleteManager: React.FC<AthleteManagerProps> = ({
 	athletes,
 	onAthleteUpdate,
 	onFinishAthlete,
+	onRecordWarning,
 	isRunning,
 }) => {
 	return (
 		<div className="container mx-auto px-4 py-8">
 			<div className="mb-8 flex justify-between items-center">
 				<h1 className="text-2xl font-bold text-gray-800">Gestión de Atletas</h1>
-				<ExportEvaluations />
+				<div className="flex gap-2 items-center">
+					<ImportNorms />
+					<ExportEvaluations />
+				</div>
 			</div>
 
 			<div className="grid gap-8">
@@ -73,9 +110,10 @@ export const AthleteManager: React.FC<AthleteManagerProps> = ({
 														age: Number.parseInt(e.target.value) || 0,
 													})
 												}
-												placeholder="Edad *"
+												placeholder={athlete.birthDate ? "Edad" : "Edad *"}
 												className="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500 sm:text-sm"
-												required={athlete.active}
+												required={athlete.active && !athlete.birthDate}
+												disabled={Boolean(athlete.birthDate)}
 											/>
 										</div>
 										<div>
@@ -124,12 +162,129 @@ export const AthleteManager: React.FC<AthleteManagerProps> = ({
 												required={athlete.active}
 											/>
 										</div>
+										{measurementFields.map(({ key, label }) => (
+											<div key={key}>
+												<label
+													htmlFor={`${key}-${athlete.id}`}
+													className="block text-sm font-medium text-gray-700"
+												>
+													{label}
+												</label>
+												<input
+													id={`${key}-${athlete.id}`}
+													type="number"
+													step="0.1"
+													value={athlete[key] || ""}
+													onChange={(e) =>
+														onAthleteUpdate({
+															...athlete,
+															[key]: Number.parseFloat(e.target.value) || undefined,
+														})
+													}
+													placeholder={label}
+													className="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500 sm:text-sm"
+												/>
+											</div>
+										))}
+										<div>
+											<label
+												htmlFor={`birthDate-${athlete.id}`}
+												className="block text-sm font-medium text-gray-700"
+											>
+												Fecha de nacimiento
+											</label>
+											<input
+												id={`birthDate-${athlete.id}`}
+												type="date"
+												value={athlete.birthDate || ""}
+												onChange={(e) =>
+													onAthleteUpdate({
+														...athlete,
+														birthDate: e.target.value || undefined,
+													})
+												}
+												className="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500 sm:text-sm"
+											/>
+										</div>
+										<div>
+											<label
+												htmlFor={`sex-${athlete.id}`}
+												className="block text-sm font-medium text-gray-700"
+											>
+												Sexo
+											</label>
+											<select
+												id={`sex-${athlete.id}`}
+												value={athlete.sex || ""}
+												onChange={(e) =>
+													onAthleteUpdate({
+														...athlete,
+														sex: (e.target.value as Sex) || undefined,
+													})
+												}
+												className="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500 sm:text-sm"
+											>
+												<option value="">Sin especificar</option>
+												{Object.entries(sexLabels).map(([value, label]) => (
+													<option key={value} value={value}>
+														{label}
+													</option>
+												))}
+											</select>
+										</div>
+										<div>
+											<label
+												htmlFor={`discipline-${athlete.id}`}
+												className="block text-sm font-medium text-gray-700"
+											>
+												Disciplina
+											</label>
+											<select
+												id={`discipline-${athlete.id}`}
+												value={athlete.discipline || ""}
+												onChange={(e) =>
+													onAthleteUpdate({
+														...athlete,
+														discipline:
+															(e.target.value as Discipline) || undefined,
+													})
+												}
+												className="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500 sm:text-sm"
+											>
+												<option value="">Sin especificar</option>
+												{Object.entries(disciplineLabels).map(([value, label]) => (
+													<option key={value} value={value}>
+														{label}
+													</option>
+												))}
+											</select>
+										</div>
+										{profileTextFields.map(({ key, label }) => (
+											<div key={key}>
+												<label
+													htmlFor={`${key}-${athlete.id}`}
+													className="block text-sm font-medium text-gray-700"
+												>
+													{label}
+												</label>
+												<input
+													id={`${key}-${athlete.id}`}
+													type="text"
+													value={athlete[key] || ""}
+													onChange={(e) =>
+														onAthleteUpdate({ ...athlete, [key]: e.target.value })
+													}
+													placeholder={label}
+													className="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-500 focus:ring-blue-500 sm:text-sm"
+												/>
+											</div>
+										))}
 										<div>
 											<label
 												htmlFor={`observations-${athlete.id}`}
 												className="block text-sm font-medium text-gray-700"
 											>
-												Observaciones
+												Observaciones del test
 											</label>
 											<textarea
 												id={`observations-${athlete.id}`}
@@ -167,6 +322,11 @@ export const AthleteManager: React.FC<AthleteManagerProps> = ({
 											<span className="text-sm text-gray-500">
 												Completado: {athlete.completedPeriods.length}
 											</span>
+											{(athlete.warnings?.length ?? 0) > 0 && (
+												<span className="ml-4 text-sm text-amber-600">
+													Avisos: {athlete.warnings?.length}
+												</span>
+											)}
 										</div>
 										<button
 											type="button"
@@ -184,7 +344,16 @@ export const AthleteManager: React.FC<AthleteManagerProps> = ({
 									</div>
 								</div>
 
-								<div className="flex items-end">
+								<div className="flex items-end gap-2">
+									{athlete.active && isRunning && (
+										<button
+											type="button"
+											onClick={() => athlete.id && onRecordWarning(athlete.id)}
+											className="w-full px-4 py-2 bg-amber-500 text-white rounded-lg hover:bg-amber-600"
+										>
+											Aviso
+										</button>
+									)}
 									{athlete.active && isRunning && (
 										<button
 											type="button"
@@ -205,6 +374,10 @@ export const AthleteManager: React.FC<AthleteManagerProps> = ({
 					</div>
 				</div>
 
+				<div className="bg-white p-4 rounded-lg shadow-md">
+					<SquadStatistics />
+				</div>
+
 				{/* Historial de evaluaciones centralizado */}
 				<div className="bg-white p-4 rounded-lg shadow-md">
 					<EvaluationHistory />
@@ -0,0 +1,124 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+import { toast } from "sonner";
+import type { AthleteProgress, Trend } from "../types";
+
+interface AthleteProgressModalProps {
+	athleteId: number | null;
+	athleteName: string;
+	onClose: () => void;
+}
+
+const signed = (value: number | null, unit: string) =>
+	value === null ? "—" : `${value > 0 ? "+" : ""}${value} ${unit}`;
+
+const describeTrend = (trend: Trend | null, unit: string) =>
+	trend
+		? `${signed(trend.slope_per_month, unit)}/mes${
+				trend.r_squared !== null ? ` (R² ${trend.r_squared})` : ""
+			}`
+		: "Se necesitan al menos dos tests";
+
+export const AthleteProgressModal: React.FC<AthleteProgressModalProps> = ({
+	athleteId,
+	athleteName,
+	onClose,
+}) => {
+	const [progress, setProgress] = useState<AthleteProgress | null>(null);
+
+	useEffect(() => {
+		if (athleteId === null) return;
+		setProgress(null);
+		invoke<AthleteProgress>("get_athlete_progress", { athleteId })
+			.then(setProgress)
+			.catch((error) => {
+				console.error("Error fetching progress:", error);
+				toast.error("Error al cargar el progreso del atleta");
+				onClose();
+			});
+	}, [athleteId, onClose]);
+
+	if (athleteId === null) return null;
+
+	return (
+		<div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+			<div className="bg-white p-6 rounded-lg shadow-lg max-w-3xl w-full">
+				<h2 className="text-xl font-bold mb-4">Progreso de {athleteName}</h2>
+				{!progress ? (
+					<div className="flex justify-center items-center h-32">
+						<div className="animate-spin rounded-full h-8 w-8 border-b-2 border-blue-500" />
+					</div>
+				) : progress.points.length === 0 ? (
+					<p className="text-gray-500">No hay tests válidos para este atleta</p>
+				) : (
+					<>
+						<div className="grid grid-cols-3 gap-4 text-sm mb-4">
+							<div>
+								<span className="text-gray-500">Mejor marca:</span>
+								<p className="font-medium">
+									{progress.personal_best?.total_distance} m (
+									{progress.personal_best &&
+										new Date(progress.personal_best.date).toLocaleDateString()}
+									)
+								</p>
+							</div>
+							<div>
+								<span className="text-gray-500">Tendencia de distancia:</span>
+								<p className="font-medium">
+									{describeTrend(progress.distance_trend, "m")}
+								</p>
+							</div>
+							<div>
+								<span className="text-gray-500">Tendencia de velocidad final:</span>
+								<p className="font-medium">
+									{describeTrend(progress.speed_trend, "km/h")}
+								</p>
+							</div>
+						</div>
+						<table className="w-full text-sm">
+							<thead>
+								<tr className="text-left text-gray-500 border-b">
+									<th className="py-1">Fecha</th>
+									<th>Distancia</th>
+									<th>Δ anterior</th>
+									<th>Δ inicial</th>
+									<th>Velocidad final</th>
+									<th>Δ anterior</th>
+									<th>Δ inicial</th>
+								</tr>
+							</thead>
+							<tbody>
+								{progress.points.map((point) => (
+									<tr key={point.evaluation_id} className="border-b">
+										<td className="py-1">
+											{new Date(point.date).toLocaleDateString()}
+										</td>
+										<td>{point.total_distance} m</td>
+										<td>{signed(point.distance_delta, "m")}</td>
+										<td>{signed(point.distance_change, "%")}</td>
+										<td>
+											{point.final_speed !== null
+												? `${point.final_speed} km/h`
+												: "—"}
+										</td>
+										<td>{signed(point.speed_delta, "km/h")}</td>
+										<td>{signed(point.speed_change, "%")}</td>
+									</tr>
+								))}
+							</tbody>
+						</table>
+					</>
+				)}
+				<div className="flex justify-end mt-4">
+					<button
+						type="button"
+						onClick={onClose}
+						className="px-4 py-2 text-gray-600 hover:text-gray-800"
+					>
+						Cerrar
+					</button>
+				</div>
+			</div>
+		</div>
+	);
+};
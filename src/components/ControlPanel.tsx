@@ -1,27 +1,96 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { Pause, Play } from "lucide-react";
-import type { TestConfig } from "../types";
-import { getPeriodData } from "../utils/testData";
+import { useCallback, useEffect, useState } from "react";
+import type {
+	EliminationRules,
+	Session,
+	TestConfig,
+	Vo2maxEquation,
+} from "../types";
+import {
+	type Protocol,
+	getActivePeriods,
+	getPeriodData,
+} from "../utils/testData";
+import { ProtocolManager } from "./ProtocolManager";
+import { SessionModal } from "./SessionModal";
 
 interface ControlPanelProps {
 	config: TestConfig;
+	// Tests start once the backend protocol table is loaded
+	protocolLoaded: boolean;
 	onStart: () => void;
 	onPause: () => void;
 	onPeriodChange: (period: number) => void;
+	onSessionChange: (session: Session | null) => void;
+	onProtocolChange: (protocolId: string, version: number) => void;
+	onTrackGeometryChange: (trackLength: number, segmentsPerLap: number) => void;
 	onRecoveryTimeChange: (time: number) => void;
+	onVo2maxEquationChange: (equation: Vo2maxEquation) => void;
+	onEliminationRulesChange: (rules: EliminationRules) => void;
 	onAthleteCountChange: (count: number) => void;
 	onReset: () => void;
 }
 
 export const ControlPanel: React.FC<ControlPanelProps> = ({
 	config,
+	protocolLoaded,
 	onStart,
 	onPause,
 	onPeriodChange,
+	onSessionChange,
+	onProtocolChange,
+	onTrackGeometryChange,
 	onRecoveryTimeChange,
+	onVo2maxEquationChange,
+	onEliminationRulesChange,
 	onAthleteCountChange,
 	onReset,
 }) => {
-	getPeriodData(config.currentPeriod);
+	const [protocols, setProtocols] = useState<Protocol[]>([]);
+	const [editingProtocol, setEditingProtocol] = useState<
+		Protocol | null | undefined
+	>(undefined);
+	const [sessions, setSessions] = useState<Session[]>([]);
+	const [creatingSession, setCreatingSession] = useState(false);
+
+	const fetchProtocols = useCallback(async () => {
+		try {
+			setProtocols(await invoke<Protocol[]>("list_protocols"));
+		} catch (error) {
+			console.error("Error fetching protocols:", error);
+		}
+	}, []);
+
+	const fetchSessions = useCallback(async () => {
+		try {
+			const list = await invoke<[Session, number][]>("list_sessions");
+			setSessions(list.map(([session]) => session));
+		} catch (error) {
+			console.error("Error fetching sessions:", error);
+		}
+	}, []);
+
+	useEffect(() => {
+		fetchSessions();
+	}, [fetchSessions]);
+
+	useEffect(() => {
+		fetchProtocols();
+		const unlisten = listen("protocols-updated", fetchProtocols);
+		return () => {
+			unlisten.then((fn) => fn());
+		};
+	}, [fetchProtocols]);
+
+	const selectedProtocol = protocols.find(
+		(p) => p.id === config.protocolId && p.version === config.protocolVersion,
+	);
+	const selectedSession = sessions.find((s) => s.id === config.sessionId);
+	// The track of a session is fixed
+	const trackLocked =
+		config.isRunning || config.isPaused || !!selectedSession?.track_geometry;
 
 	return (
 		<div className="bg-white p-4 rounded-lg shadow-md">
@@ -30,7 +99,8 @@ export const ControlPanel: React.FC<ControlPanelProps> = ({
 					<button
 						type="button"
 						onClick={config.isRunning ? onPause : onStart}
-						className="flex items-center px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700"
+						disabled={!config.isRunning && !protocolLoaded}
+						className="flex items-center px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:bg-blue-300"
 					>
 						{config.isRunning ? (
 							<>
@@ -55,6 +125,237 @@ export const ControlPanel: React.FC<ControlPanelProps> = ({
 				</div>
 
 				<div className="flex items-center space-x-4">
+					<div>
+						<label
+							htmlFor="session"
+							className="block text-sm font-medium text-gray-700"
+						>
+							Sesión
+						</label>
+						<div className="flex items-center mt-1 space-x-2">
+							<select
+								id="session"
+								value={config.sessionId ?? ""}
+								onChange={(e) =>
+									onSessionChange(
+										sessions.find((s) => s.id === Number(e.target.value)) ??
+											null,
+									)
+								}
+								disabled={config.isRunning || config.isPaused}
+								className="block w-full border-1 rounded-md border-gray-300 py-1 px-3 bg-white focus:border-blue-500 focus:outline-none focus:ring-1 focus:ring-blue-500"
+							>
+								<option value="">Sin sesión</option>
+								{sessions.map((s) => (
+									<option key={s.id} value={s.id ?? ""}>
+										{s.date}
+										{s.venue ? ` · ${s.venue}` : ""}
+									</option>
+								))}
+							</select>
+							<button
+								type="button"
+								onClick={() => setCreatingSession(true)}
+								className="px-2 py-1 text-sm text-blue-600 hover:text-blue-800"
+								disabled={config.isRunning || config.isPaused}
+							>
+								Nueva
+							</button>
+						</div>
+					</div>
+
+					<div>
+						<label
+							htmlFor="protocol"
+							className="block text-sm font-medium text-gray-700"
+						>
+							Protocolo
+						</label>
+						<div className="flex items-center mt-1 space-x-2">
+							<select
+								id="protocol"
+								value={`${config.protocolId}@${config.protocolVersion}`}
+								onChange={(e) => {
+									const [id, version] = e.target.value.split("@");
+									onProtocolChange(id, Number(version));
+								}}
+								disabled={config.isRunning || config.isPaused}
+								className="block w-full border-1 rounded-md border-gray-300 py-1 px-3 bg-white focus:border-blue-500 focus:outline-none focus:ring-1 focus:ring-blue-500"
+							>
+								{protocols.map((p) => (
+									<option
+										key={`${p.id}@${p.version}`}
+										value={`${p.id}@${p.version}`}
+									>
+										{p.name} (v{p.version})
+									</option>
+								))}
+							</select>
+							<button
+								type="button"
+								onClick={() => setEditingProtocol(null)}
+								className="px-2 py-1 text-sm text-blue-600 hover:text-blue-800"
+								disabled={config.isRunning}
+							>
+								Nuevo
+							</button>
+							{selectedProtocol && selectedProtocol.id !== "standard" && (
+								<button
+									type="button"
+									onClick={() => setEditingProtocol(selectedProtocol)}
+									className="px-2 py-1 text-sm text-blue-600 hover:text-blue-800"
+									disabled={config.isRunning}
+								>
+									Editar
+								</button>
+							)}
+						</div>
+					</div>
+
+					<div>
+						<label
+							htmlFor="trackLength"
+							className="block text-sm font-medium text-gray-700"
+						>
+							Pista (m)
+						</label>
+						<input
+							id="trackLength"
+							type="number"
+							key={`track-${config.trackLength}`}
+							defaultValue={config.trackLength}
+							onBlur={(e) => {
+								const trackLength = Number(e.target.value);
+								if (trackLength !== config.trackLength) {
+									onTrackGeometryChange(trackLength, config.segmentsPerLap);
+								}
+							}}
+							min={1}
+							disabled={trackLocked}
+							className="py-1 px-2 mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-500"
+						/>
+					</div>
+
+					<div>
+						<label
+							htmlFor="segmentsPerLap"
+							className="block text-sm font-medium text-gray-700"
+						>
+							Conos por vuelta
+						</label>
+						<input
+							id="segmentsPerLap"
+							type="number"
+							key={`segments-${config.segmentsPerLap}`}
+							defaultValue={config.segmentsPerLap}
+							onBlur={(e) => {
+								const segmentsPerLap = Number(e.target.value);
+								if (segmentsPerLap !== config.segmentsPerLap) {
+									onTrackGeometryChange(config.trackLength, segmentsPerLap);
+								}
+							}}
+							min={1}
+							disabled={trackLocked}
+							className="py-1 px-2 mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-500"
+						/>
+					</div>
+
+					<div>
+						<label
+							htmlFor="vo2maxEquation"
+							className="block text-sm font-medium text-gray-700"
+						>
+							Ecuación VO2max
+						</label>
+						<select
+							id="vo2maxEquation"
+							value={config.vo2maxEquation.kind}
+							onChange={(e) =>
+								onVo2maxEquationChange(
+									e.target.value === "linear"
+										? { kind: "linear", intercept: 0, slope: 3.5 }
+										: { kind: "leger_mercier" },
+								)
+							}
+							disabled={config.isRunning || config.isPaused}
+							className="py-1 px-2 mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-500"
+						>
+							<option value="leger_mercier">Léger-Mercier (3,5 × VAM)</option>
+							<option value="linear">Lineal personalizada</option>
+						</select>
+						{config.vo2maxEquation.kind === "linear" && (
+							<div className="grid grid-cols-2 gap-2 mt-1">
+								{(["intercept", "slope"] as const).map((key) => (
+									<input
+										key={key}
+										type="number"
+										step={0.01}
+										aria-label={key === "intercept" ? "Constante" : "Pendiente"}
+										title={key === "intercept" ? "Constante" : "Pendiente"}
+										value={
+											config.vo2maxEquation.kind === "linear"
+												? config.vo2maxEquation[key]
+												: 0
+										}
+										onChange={(e) =>
+											config.vo2maxEquation.kind === "linear" &&
+											onVo2maxEquationChange({
+												...config.vo2maxEquation,
+												[key]: Number(e.target.value),
+											})
+										}
+										disabled={config.isRunning || config.isPaused}
+										className="py-1 px-2 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-500"
+									/>
+								))}
+							</div>
+						)}
+					</div>
+
+					<div>
+						<label
+							htmlFor="consecutiveWarnings"
+							className="block text-sm font-medium text-gray-700"
+						>
+							Avisos para Eliminar
+						</label>
+						<div className="grid grid-cols-2 gap-2 mt-1">
+							<input
+								id="consecutiveWarnings"
+								type="number"
+								title="Avisos seguidos"
+								value={config.eliminationRules.consecutive_warnings}
+								onChange={(e) =>
+									onEliminationRulesChange({
+										...config.eliminationRules,
+										consecutive_warnings: Number(e.target.value),
+									})
+								}
+								min={1}
+								disabled={config.isRunning || config.isPaused}
+								className="py-1 px-2 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-500"
+							/>
+							<input
+								type="number"
+								aria-label="Avisos en total"
+								title="Avisos en total"
+								placeholder="Total"
+								value={config.eliminationRules.max_warnings ?? ""}
+								onChange={(e) =>
+									onEliminationRulesChange({
+										...config.eliminationRules,
+										max_warnings: e.target.value
+											? Number(e.target.value)
+											: null,
+									})
+								}
+								min={1}
+								disabled={config.isRunning || config.isPaused}
+								className="py-1 px-2 block w-full rounded-md border-gray-300 shadow-sm focus:border-blue-300 focus:ring focus:ring-blue-500"
+							/>
+						</div>
+					</div>
+
 					<div>
 						<label
 							htmlFor="athleteCount"
@@ -84,16 +385,11 @@ export const ControlPanel: React.FC<ControlPanelProps> = ({
 							onChange={(e) => onPeriodChange(Number(e.target.value))}
 							className="mt-1 block w-full border-1 rounded-md border-gray-300 py-1 px-3 bg-white focus:border-blue-500 focus:outline-none focus:ring-1 focus:ring-blue-500"
 						>
-							{Array(34)
-								.fill(0)
-								.map((_, idx) => {
-									const period = idx + 2;
-									return (
-										<option key={`period-${period}`} value={period}>
-											{period} - {getPeriodData(period)?.speed} km/h
-										</option>
-									);
-								})}
+							{getActivePeriods().map(({ period }) => (
+								<option key={`period-${period}`} value={period}>
+									{period} - {getPeriodData(period)?.speed} km/h
+								</option>
+							))}
 						</select>
 					</div>
 
@@ -115,6 +411,29 @@ export const ControlPanel: React.FC<ControlPanelProps> = ({
 					</div>
 				</div>
 			</div>
+
+			{creatingSession && (
+				<SessionModal
+					show
+					config={config}
+					onClose={() => setCreatingSession(false)}
+					onCreated={(session) => {
+						setSessions((list) => [session, ...list]);
+						onSessionChange(session);
+					}}
+				/>
+			)}
+
+			{editingProtocol !== undefined && (
+				<ProtocolManager
+					show
+					protocol={editingProtocol}
+					onClose={() => setEditingProtocol(undefined)}
+					onSaved={(protocol) =>
+						onProtocolChange(protocol.id, protocol.version)
+					}
+				/>
+			)}
 		</div>
 	);
 };
@@ -2,9 +2,21 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useEffect, useState, useCallback } from "react";
 import { toast } from "sonner";
-import type { Athlete } from "../types";
+import type {
+	AerobicEstimate,
+	Anthropometrics,
+	Athlete,
+	Classification,
+	NormMetric,
+	Rating,
+	TestConfiguration,
+} from "../types";
+import { AthleteProgressModal } from "./AthleteProgressModal";
 import { EditObservationsModal } from "./EditObservationsModal";
-import { Pencil } from "lucide-react";
+import { HeartRateModal } from "./HeartRateModal";
+import { PeriodMeasurementsModal } from "./PeriodMeasurementsModal";
+import { TrainingZonesModal } from "./TrainingZonesModal";
+import { Droplet, Gauge, HeartPulse, Pencil, TrendingUp } from "lucide-react";
 
 interface RawEvaluation {
 	id: number;
@@ -12,14 +24,40 @@ interface RawEvaluation {
 	template_id: number;
 	status: string;
 	date: string;
+	observations?: string | null;
+	observations_author?: string | null;
+	observations_updated_at?: string | null;
+	// Age on the evaluation date
+	athlete_age: number | null;
+	measurements: Anthropometrics | null;
+	bmi: number | null;
+	classifications: Classification[];
 }
 
+const METRIC_LABELS: Record<NormMetric, string> = {
+	total_distance: "Distancia",
+	mas: "VAM",
+	vo2max: "VO2max",
+};
+
+const RATING_LABELS: Record<Rating, string> = {
+	very_poor: "Muy bajo",
+	poor: "Bajo",
+	average: "Medio",
+	good: "Bueno",
+	excellent: "Excelente",
+};
+
 interface EvaluationTemplate {
 	id: number;
 	completed_periods: string;
 	total_time: number;
 	date: string;
 	total_distance: number;
+	measured_distance: number | null;
+	distance_flagged: boolean;
+	config: TestConfiguration;
+	aerobic: AerobicEstimate | null;
 }
 
 interface TransformedEvaluation extends Omit<RawEvaluation, "template_id"> {
@@ -27,6 +65,10 @@ interface TransformedEvaluation extends Omit<RawEvaluation, "template_id"> {
 	total_time: number;
 	date: string;
 	total_distance: number;
+	measured_distance: number | null;
+	distance_flagged: boolean;
+	config: TestConfiguration;
+	aerobic: AerobicEstimate | null;
 	athlete: Athlete;
 }
 
@@ -35,6 +77,15 @@ export function EvaluationHistory() {
 	const [loading, setLoading] = useState(true);
 	const [selectedEvaluation, setSelectedEvaluation] =
 		useState<TransformedEvaluation | null>(null);
+	const [progressAthlete, setProgressAthlete] = useState<Athlete | null>(
+		null,
+	);
+	const closeProgress = useCallback(() => setProgressAthlete(null), []);
+	const [zonesAthlete, setZonesAthlete] = useState<Athlete | null>(null);
+	const [heartRateEvaluation, setHeartRateEvaluation] =
+		useState<TransformedEvaluation | null>(null);
+	const [lactateEvaluation, setLactateEvaluation] =
+		useState<TransformedEvaluation | null>(null);
 
 	const transformCallback = useCallback(
 		([evaluation, template, athlete]: [
@@ -48,6 +99,10 @@ export function EvaluationHistory() {
 				total_time: template.total_time,
 				date: new Date(evaluation.date).toLocaleDateString(),
 				total_distance: template.total_distance,
+				measured_distance: template.measured_distance,
+				distance_flagged: template.distance_flagged,
+				config: template.config,
+				aerobic: template.aerobic,
 				athlete,
 			};
 		},
@@ -114,14 +169,50 @@ export function EvaluationHistory() {
 									<h4 className="text-lg font-semibold">
 										Información del Atleta
 									</h4>
-									<button
-										type="button"
-										onClick={() => setSelectedEvaluation(evaluation)}
-										className="p-2 text-gray-600 hover:text-blue-600 rounded-full hover:bg-blue-50 transition-colors"
-										title="Editar observaciones"
-									>
-										<Pencil className="w-4 h-4" />
-									</button>
+									<div className="flex gap-1">
+										<button
+											type="button"
+											onClick={() => setProgressAthlete(evaluation.athlete)}
+											className="p-2 text-gray-600 hover:text-blue-600 rounded-full hover:bg-blue-50 transition-colors"
+											title="Ver progreso"
+										>
+											<TrendingUp className="w-4 h-4" />
+										</button>
+										<button
+											type="button"
+											onClick={() => setHeartRateEvaluation(evaluation)}
+											className="p-2 text-gray-600 hover:text-blue-600 rounded-full hover:bg-blue-50 transition-colors"
+											title="Frecuencia cardíaca"
+										>
+											<HeartPulse className="w-4 h-4" />
+										</button>
+										<button
+											type="button"
+											onClick={() => setLactateEvaluation(evaluation)}
+											className="p-2 text-gray-600 hover:text-blue-600 rounded-full hover:bg-blue-50 transition-colors"
+											title="Lactato y RPE"
+										>
+											<Droplet className="w-4 h-4" />
+										</button>
+										{evaluation.aerobic && (
+											<button
+												type="button"
+												onClick={() => setZonesAthlete(evaluation.athlete)}
+												className="p-2 text-gray-600 hover:text-blue-600 rounded-full hover:bg-blue-50 transition-colors"
+												title="Zonas de entrenamiento"
+											>
+												<Gauge className="w-4 h-4" />
+											</button>
+										)}
+										<button
+											type="button"
+											onClick={() => setSelectedEvaluation(evaluation)}
+											className="p-2 text-gray-600 hover:text-blue-600 rounded-full hover:bg-blue-50 transition-colors"
+											title="Editar observaciones"
+										>
+											<Pencil className="w-4 h-4" />
+										</button>
+									</div>
 								</div>
 								<div className="grid grid-cols-4 gap-4 text-sm">
 									<div>
@@ -130,29 +221,76 @@ export function EvaluationHistory() {
 									</div>
 									<div>
 										<span className="text-gray-500">Edad:</span>
-										<p className="font-medium">{evaluation.athlete.age} años</p>
+										<p className="font-medium">
+											{evaluation.athlete_age ?? evaluation.athlete.age} años
+										</p>
 									</div>
 									<div>
 										<span className="text-gray-500">Peso:</span>
 										<p className="font-medium">
-											{evaluation.athlete.weight} kg
+											{evaluation.measurements?.weight ??
+												evaluation.athlete.weight}{" "}
+											kg
 										</p>
 									</div>
 									<div>
 										<span className="text-gray-500">Altura:</span>
 										<p className="font-medium">
-											{evaluation.athlete.height} cm
+											{evaluation.measurements?.height ??
+												evaluation.athlete.height}{" "}
+											cm
 										</p>
 									</div>
+									{evaluation.bmi !== null && (
+										<div>
+											<span className="text-gray-500">IMC:</span>
+											<p className="font-medium">{evaluation.bmi.toFixed(1)}</p>
+										</div>
+									)}
+									{evaluation.measurements?.body_fat != null && (
+										<div>
+											<span className="text-gray-500">Grasa corporal:</span>
+											<p className="font-medium">
+												{evaluation.measurements.body_fat} %
+											</p>
+										</div>
+									)}
+									{evaluation.measurements?.leg_length != null && (
+										<div>
+											<span className="text-gray-500">Longitud de pierna:</span>
+											<p className="font-medium">
+												{evaluation.measurements.leg_length} cm
+											</p>
+										</div>
+									)}
 								</div>
 								{evaluation.athlete.observations && (
 									<div className="mt-2">
-										<span className="text-gray-500">Observaciones:</span>
+										<span className="text-gray-500">
+											Observaciones del atleta:
+										</span>
 										<p className="font-medium">
 											{evaluation.athlete.observations}
 										</p>
 									</div>
 								)}
+								{evaluation.observations && (
+									<div className="mt-2">
+										<span className="text-gray-500">
+											Observaciones de la evaluación:
+										</span>
+										<p className="font-medium">{evaluation.observations}</p>
+										{evaluation.observations_updated_at && (
+											<span className="text-xs text-gray-400">
+												{evaluation.observations_author &&
+													`${evaluation.observations_author} · `}
+												{new Date(
+													evaluation.observations_updated_at,
+												).toLocaleString()}
+											</span>
+										)}
+									</div>
+								)}
 							</div>
 							<div className="flex justify-between items-start mb-2">
 								<div>
@@ -171,6 +309,36 @@ export function EvaluationHistory() {
 									<span className="text-sm font-medium block">
 										Distancia total: {evaluation.total_distance} m
 									</span>
+									{evaluation.distance_flagged && (
+										<span className="text-xs text-red-600 block">
+											Distancia medida: {evaluation.measured_distance} m
+										</span>
+									)}
+									{evaluation.aerobic && (
+										<span className="text-sm font-medium block">
+											VAM: {evaluation.aerobic.mas} km/h · VO2max:{" "}
+											{evaluation.aerobic.vo2max} ml/kg/min
+										</span>
+									)}
+									{evaluation.classifications.map((classification) => (
+										<span
+											key={classification.metric}
+											className="text-xs text-gray-600 block"
+										>
+											{METRIC_LABELS[classification.metric]}: P
+											{classification.percentile} ·{" "}
+											{RATING_LABELS[classification.rating]}
+										</span>
+									))}
+									<span className="text-xs text-gray-500 block">
+										{evaluation.config.protocol_id} v
+										{evaluation.config.protocol_version} · pista{" "}
+										{evaluation.config.track_length} m
+										{evaluation.config.start_period !== null &&
+											` · inicio P${evaluation.config.start_period}`}
+										{evaluation.config.recovery_time !== null &&
+											` · recuperación ${evaluation.config.recovery_time}s`}
+									</span>
 								</div>
 							</div>
 							<div className="mt-2">
@@ -197,9 +365,29 @@ export function EvaluationHistory() {
 				show={selectedEvaluation !== null}
 				onClose={() => setSelectedEvaluation(null)}
 				evaluationId={selectedEvaluation?.id || 0}
-				initialObservations={selectedEvaluation?.athlete.observations || ""}
+				initialObservations={selectedEvaluation?.observations || ""}
 				onSuccess={fetchEvaluations}
 			/>
+			<AthleteProgressModal
+				athleteId={progressAthlete?.id ?? null}
+				athleteName={progressAthlete?.name ?? ""}
+				onClose={closeProgress}
+			/>
+			<TrainingZonesModal
+				athleteId={zonesAthlete?.id ?? null}
+				athleteName={zonesAthlete?.name ?? ""}
+				onClose={() => setZonesAthlete(null)}
+			/>
+			<HeartRateModal
+				evaluationId={heartRateEvaluation?.id ?? null}
+				athleteName={heartRateEvaluation?.athlete.name ?? ""}
+				onClose={() => setHeartRateEvaluation(null)}
+			/>
+			<PeriodMeasurementsModal
+				evaluationId={lactateEvaluation?.id ?? null}
+				athleteName={lactateEvaluation?.athlete.name ?? ""}
+				onClose={() => setLactateEvaluation(null)}
+			/>
 		</div>
 	);
 }
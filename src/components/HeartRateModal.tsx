@@ -0,0 +1,185 @@
+import { invoke } from "@tauri-apps/api/core";
+import { open } from "@tauri-apps/plugin-dialog";
+import { useEffect, useState } from "react";
+import { toast } from "sonner";
+import type { HeartRateAnalysis } from "../types";
+
+interface HeartRateModalProps {
+	evaluationId: number | null;
+	athleteName: string;
+	onClose: () => void;
+}
+
+export const HeartRateModal: React.FC<HeartRateModalProps> = ({
+	evaluationId,
+	athleteName,
+	onClose,
+}) => {
+	const [analysis, setAnalysis] = useState<HeartRateAnalysis | null>(null);
+	// Seconds after the start of the test when the recording started. Empty
+	// to use the timestamps of the file.
+	const [offset, setOffset] = useState("");
+	const [importing, setImporting] = useState(false);
+
+	useEffect(() => {
+		setAnalysis(null);
+		setOffset("");
+		if (evaluationId === null) return;
+		invoke<HeartRateAnalysis | null>("get_heart_rate", { evaluationId })
+			.then(setAnalysis)
+			.catch((error) => {
+				console.error("Error fetching heart rate:", error);
+				toast.error("Error al cargar la frecuencia cardíaca");
+			});
+	}, [evaluationId]);
+
+	if (evaluationId === null) return null;
+
+	const handleImport = async () => {
+		try {
+			setImporting(true);
+			const path = await open({
+				multiple: false,
+				filters: [
+					{ name: "Frecuencia cardíaca", extensions: ["csv", "tcx", "fit"] },
+				],
+			});
+			if (!path) {
+				return; // User cancelled
+			}
+			const parsedOffset = Number.parseFloat(offset);
+			setAnalysis(
+				await invoke<HeartRateAnalysis>("import_heart_rate", {
+					evaluationId,
+					path,
+					offset: Number.isNaN(parsedOffset) ? null : parsedOffset,
+				}),
+			);
+			toast.success("Frecuencia cardíaca importada exitosamente");
+		} catch (error) {
+			console.error("Error importing heart rate:", error);
+			toast.error(`Error al importar la frecuencia cardíaca: ${error}`);
+		} finally {
+			setImporting(false);
+		}
+	};
+
+	const handleDelete = async () => {
+		try {
+			await invoke("delete_heart_rate", { evaluationId });
+			setAnalysis(null);
+			toast.success("Registro de frecuencia cardíaca eliminado");
+		} catch (error) {
+			console.error("Error deleting heart rate:", error);
+			toast.error("Error al eliminar la frecuencia cardíaca");
+		}
+	};
+
+	const summary = analysis?.summary;
+
+	return (
+		<div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+			<div className="bg-white p-6 rounded-lg shadow-lg max-w-2xl w-full">
+				<h2 className="text-xl font-bold mb-4">
+					Frecuencia cardíaca de {athleteName}
+				</h2>
+				{analysis ? (
+					<div className="space-y-4 text-sm">
+						<p className="text-gray-500">
+							{analysis.recording.source} ·{" "}
+							{analysis.recording.samples.length} registros · inicio{" "}
+							{analysis.recording.start_offset} s respecto al test
+						</p>
+						{summary ? (
+							<>
+								<div className="grid grid-cols-3 gap-4">
+									<div>
+										<span className="text-gray-500">FC máx:</span>
+										<p className="font-medium">{summary.max} lpm</p>
+									</div>
+									<div>
+										<span className="text-gray-500">FC media:</span>
+										<p className="font-medium">{summary.mean} lpm</p>
+									</div>
+									<div>
+										<span className="text-gray-500">Recuperación:</span>
+										{summary.recovery.length === 0 ? (
+											<p className="font-medium">—</p>
+										) : (
+											summary.recovery.map((recovery) => (
+												<p key={recovery.after} className="font-medium">
+													{recovery.after / 60} min: −{recovery.drop} lpm
+												</p>
+											))
+										)}
+									</div>
+								</div>
+								<table className="w-full">
+									<thead>
+										<tr className="text-left text-gray-500 border-b">
+											<th className="py-1">Periodo</th>
+											<th>FC media</th>
+											<th>FC máx</th>
+										</tr>
+									</thead>
+									<tbody>
+										{summary.periods.map((period) => (
+											<tr key={period.period} className="border-b">
+												<td className="py-1">{period.period}</td>
+												<td>{period.mean} lpm</td>
+												<td>{period.max} lpm</td>
+											</tr>
+										))}
+									</tbody>
+								</table>
+							</>
+						) : (
+							<p className="text-gray-500">
+								El registro no coincide con el tiempo del test
+							</p>
+						)}
+					</div>
+				) : (
+					<p className="text-gray-500">
+						Esta evaluación no tiene registro de frecuencia cardíaca
+					</p>
+				)}
+				<div className="flex justify-end items-center gap-2 mt-4">
+					<input
+						type="number"
+						step="0.1"
+						value={offset}
+						onChange={(e) => setOffset(e.target.value)}
+						placeholder="Desfase (s)"
+						title="Segundos tras el inicio del test en que empezó el registro"
+						className="w-32 rounded-md border-gray-300 shadow-sm sm:text-sm"
+					/>
+					<button
+						type="button"
+						onClick={handleImport}
+						disabled={importing}
+						className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:bg-blue-300 text-sm"
+					>
+						{importing ? "Importando..." : "Importar archivo"}
+					</button>
+					{analysis && (
+						<button
+							type="button"
+							onClick={handleDelete}
+							className="px-4 py-2 text-red-600 hover:text-red-800 text-sm"
+						>
+							Eliminar
+						</button>
+					)}
+					<button
+						type="button"
+						onClick={onClose}
+						className="px-4 py-2 text-gray-600 hover:text-gray-800"
+					>
+						Cerrar
+					</button>
+				</div>
+			</div>
+		</div>
+	);
+};
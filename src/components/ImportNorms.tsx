@@ -0,0 +1,73 @@
+import { invoke } from "@tauri-apps/api/core";
+import { open } from "@tauri-apps/plugin-dialog";
+import { useState } from "react";
+import { toast } from "sonner";
+
+export function ImportNorms() {
+	const [importing, setImporting] = useState(false);
+	const [replace, setReplace] = useState(false);
+
+	const handleImport = async () => {
+		try {
+			setImporting(true);
+
+			const filePath = await open({
+				multiple: false,
+				filters: [
+					{
+						name: "CSV",
+						extensions: ["csv"],
+					},
+				],
+			});
+
+			if (!filePath) {
+				return; // User cancelled
+			}
+
+			const imported = await invoke<number>("import_norms", {
+				path: filePath,
+				replace,
+			});
+
+			toast.success(`${imported} normas importadas exitosamente`);
+		} catch (error) {
+			console.error("Error importing norms:", error);
+			toast.error(`Error al importar las normas: ${error}`);
+		} finally {
+			setImporting(false);
+		}
+	};
+
+	return (
+		<div className="flex gap-2 items-center">
+			<label className="flex items-center gap-1 text-sm text-gray-700">
+				<input
+					type="checkbox"
+					checked={replace}
+					onChange={(e) => setReplace(e.target.checked)}
+					disabled={importing}
+				/>
+				Reemplazar existentes
+			</label>
+			<button
+				type="button"
+				onClick={handleImport}
+				disabled={importing}
+				className={`
+					inline-flex items-center px-4 py-2 rounded-lg
+					${
+						importing
+							? "bg-gray-300 cursor-not-allowed"
+							: "bg-gray-600 hover:bg-gray-700"
+					}
+					text-white font-medium text-sm
+					transition-colors duration-200
+					focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-gray-500
+				`}
+			>
+				{importing ? "Importando..." : "Importar normas"}
+			</button>
+		</div>
+	);
+}
@@ -0,0 +1,194 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+import { toast } from "sonner";
+import type { LactateAnalysis, PeriodMeasurement } from "../types";
+
+interface PeriodMeasurementsModalProps {
+	evaluationId: number | null;
+	athleteName: string;
+	onClose: () => void;
+}
+
+interface Row {
+	period: number;
+	speed: number | null;
+	lactate: string;
+	rpe: string;
+}
+
+const toRows = (analysis: LactateAnalysis): Row[] =>
+	analysis.periods.map((entry) => ({
+		period: entry.period,
+		speed: entry.speed,
+		lactate: entry.lactate?.toString() ?? "",
+		rpe: entry.rpe?.toString() ?? "",
+	}));
+
+const parse = (value: string): number | null => {
+	const number = Number.parseFloat(value.replace(",", "."));
+	return Number.isNaN(number) ? null : number;
+};
+
+const formatSpeed = (speed: number | null) =>
+	speed === null ? "—" : `${speed.toFixed(2)} km/h`;
+
+export const PeriodMeasurementsModal: React.FC<PeriodMeasurementsModalProps> = ({
+	evaluationId,
+	athleteName,
+	onClose,
+}) => {
+	const [analysis, setAnalysis] = useState<LactateAnalysis | null>(null);
+	const [rows, setRows] = useState<Row[]>([]);
+	const [saving, setSaving] = useState(false);
+
+	useEffect(() => {
+		setAnalysis(null);
+		setRows([]);
+		if (evaluationId === null) return;
+		invoke<LactateAnalysis>("get_period_measurements", { evaluationId })
+			.then((analysis) => {
+				setAnalysis(analysis);
+				setRows(toRows(analysis));
+			})
+			.catch((error) => {
+				console.error("Error fetching period measurements:", error);
+				toast.error("Error al cargar el lactato y el RPE");
+			});
+	}, [evaluationId]);
+
+	if (evaluationId === null) return null;
+
+	const updateRow = (period: number, field: "lactate" | "rpe", value: string) =>
+		setRows((rows) =>
+			rows.map((row) => (row.period === period ? { ...row, [field]: value } : row)),
+		);
+
+	const handleSubmit = async (e: React.FormEvent) => {
+		e.preventDefault();
+		const measurements: PeriodMeasurement[] = rows
+			.map((row) => ({
+				period: row.period,
+				lactate: parse(row.lactate),
+				rpe: parse(row.rpe),
+			}))
+			.filter((m) => m.lactate !== null || m.rpe !== null);
+		try {
+			setSaving(true);
+			const analysis = await invoke<LactateAnalysis>("save_period_measurements", {
+				evaluationId,
+				measurements,
+			});
+			setAnalysis(analysis);
+			setRows(toRows(analysis));
+			toast.success("Lactato y RPE guardados exitosamente");
+		} catch (error) {
+			console.error("Error saving period measurements:", error);
+			toast.error(`Error al guardar el lactato y el RPE: ${error}`);
+		} finally {
+			setSaving(false);
+		}
+	};
+
+	return (
+		<div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+			<div className="bg-white p-6 rounded-lg shadow-lg max-w-2xl w-full">
+				<h2 className="text-xl font-bold mb-4">
+					Lactato y RPE de {athleteName}
+				</h2>
+				<form onSubmit={handleSubmit}>
+					{rows.length === 0 ? (
+						<p className="text-sm text-gray-500">
+							Esta evaluación no tiene resultados por periodo
+						</p>
+					) : (
+						<div className="max-h-80 overflow-y-auto">
+							<table className="w-full text-sm">
+								<thead>
+									<tr className="text-left text-gray-500 border-b">
+										<th className="py-1">Periodo</th>
+										<th>Velocidad</th>
+										<th>Lactato (mmol/L)</th>
+										<th>RPE (0-10)</th>
+									</tr>
+								</thead>
+								<tbody>
+									{rows.map((row) => (
+										<tr key={row.period} className="border-b">
+											<td className="py-1">{row.period}</td>
+											<td>{formatSpeed(row.speed)}</td>
+											<td>
+												<input
+													type="number"
+													step="0.1"
+													min="0"
+													max="30"
+													value={row.lactate}
+													onChange={(e) =>
+														updateRow(row.period, "lactate", e.target.value)
+													}
+													className="w-24 rounded-md border-gray-300 shadow-sm sm:text-sm"
+												/>
+											</td>
+											<td>
+												<input
+													type="number"
+													step="0.5"
+													min="0"
+													max="10"
+													value={row.rpe}
+													onChange={(e) =>
+														updateRow(row.period, "rpe", e.target.value)
+													}
+													className="w-24 rounded-md border-gray-300 shadow-sm sm:text-sm"
+												/>
+											</td>
+										</tr>
+									))}
+								</tbody>
+							</table>
+						</div>
+					)}
+					{analysis && (
+						<div className="grid grid-cols-3 gap-4 mt-4 text-sm">
+							<div>
+								<span className="text-gray-500">Umbral 4 mmol:</span>
+								<p className="font-medium">{formatSpeed(analysis.thresholds.fixed)}</p>
+							</div>
+							<div>
+								<span className="text-gray-500">Umbral Dmax:</span>
+								<p className="font-medium">
+									{formatSpeed(analysis.thresholds.dmax)}
+									{analysis.thresholds.dmax_lactate !== null &&
+										` (${analysis.thresholds.dmax_lactate} mmol/L)`}
+								</p>
+							</div>
+							<div>
+								<span className="text-gray-500">Lactato máx / RPE máx:</span>
+								<p className="font-medium">
+									{analysis.peak_lactate ?? "—"} / {analysis.max_rpe ?? "—"}
+								</p>
+							</div>
+						</div>
+					)}
+					<div className="flex justify-end space-x-4 mt-4">
+						<button
+							type="button"
+							onClick={onClose}
+							className="px-4 py-2 text-gray-600 hover:text-gray-800"
+							disabled={saving}
+						>
+							Cerrar
+						</button>
+						<button
+							type="submit"
+							className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:bg-blue-300"
+							disabled={saving || rows.length === 0}
+						>
+							{saving ? "Guardando..." : "Guardar"}
+						</button>
+					</div>
+				</form>
+			</div>
+		</div>
+	);
+};
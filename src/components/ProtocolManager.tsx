@@ -0,0 +1,167 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useState } from "react";
+import { toast } from "sonner";
+import type { Protocol, ProtocolParameters } from "../utils/testData";
+
+interface ProtocolManagerProps {
+	show: boolean;
+	onClose: () => void;
+	// Protocol to edit, a new one is created when missing
+	protocol: Protocol | null;
+	onSaved: (protocol: Protocol) => void;
+}
+
+const defaultParameters: ProtocolParameters = {
+	start_period: 1,
+	period_count: 30,
+	start_speed: 20,
+	speed_increment: 0.8,
+	laps_per_period: 4,
+	track_length: 200,
+	segments_per_lap: 4,
+};
+
+const parameterFields: {
+	key: keyof ProtocolParameters;
+	label: string;
+	step: number;
+}[] = [
+	{ key: "start_speed", label: "Velocidad inicial (km/h)", step: 0.1 },
+	{
+		key: "speed_increment",
+		label: "Incremento por periodo (km/h)",
+		step: 0.1,
+	},
+	{ key: "start_period", label: "Periodo inicial", step: 1 },
+	{ key: "period_count", label: "Número de periodos", step: 1 },
+	{ key: "laps_per_period", label: "Vueltas por periodo", step: 1 },
+	{ key: "track_length", label: "Longitud de pista (m)", step: 1 },
+	{ key: "segments_per_lap", label: "Segmentos por vuelta", step: 1 },
+];
+
+export const ProtocolManager: React.FC<ProtocolManagerProps> = ({
+	show,
+	onClose,
+	protocol,
+	onSaved,
+}) => {
+	const editing = protocol !== null && protocol.id !== "standard";
+	const [code, setCode] = useState(editing ? protocol.id : "");
+	const [name, setName] = useState(editing ? protocol.name : "");
+	const [parameters, setParameters] = useState<ProtocolParameters>(
+		protocol?.parameters ?? defaultParameters,
+	);
+	const [saving, setSaving] = useState(false);
+
+	if (!show) return null;
+
+	const handleSubmit = async (e: React.FormEvent) => {
+		e.preventDefault();
+		try {
+			setSaving(true);
+			const saved = await invoke<Protocol>(
+				editing ? "update_protocol" : "create_protocol",
+				{ code, name, parameters },
+			);
+			toast.success(
+				`Protocolo ${saved.name} guardado (versión ${saved.version})`,
+			);
+			onSaved(saved);
+			onClose();
+		} catch (error) {
+			console.error("Error saving protocol:", error);
+			toast.error(
+				typeof error === "string" ? error : "Error al guardar el protocolo",
+			);
+		} finally {
+			setSaving(false);
+		}
+	};
+
+	return (
+		<div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+			<div className="bg-white p-6 rounded-lg shadow-lg max-w-lg w-full">
+				<h2 className="text-xl font-bold mb-4">
+					{editing ? "Nueva versión del protocolo" : "Nuevo protocolo"}
+				</h2>
+				<form onSubmit={handleSubmit}>
+					<div className="grid grid-cols-2 gap-4 mb-4">
+						<div>
+							<label
+								htmlFor="protocolCode"
+								className="block text-sm font-medium text-gray-700 mb-1"
+							>
+								Código
+							</label>
+							<input
+								id="protocolCode"
+								value={code}
+								onChange={(e) => setCode(e.target.value)}
+								disabled={editing}
+								required
+								className="w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-blue-500 focus:border-blue-500"
+							/>
+						</div>
+						<div>
+							<label
+								htmlFor="protocolName"
+								className="block text-sm font-medium text-gray-700 mb-1"
+							>
+								Nombre
+							</label>
+							<input
+								id="protocolName"
+								value={name}
+								onChange={(e) => setName(e.target.value)}
+								required
+								className="w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-blue-500 focus:border-blue-500"
+							/>
+						</div>
+						{parameterFields.map(({ key, label, step }) => (
+							<div key={key}>
+								<label
+									htmlFor={key}
+									className="block text-sm font-medium text-gray-700 mb-1"
+								>
+									{label}
+								</label>
+								<input
+									id={key}
+									type="number"
+									step={step}
+									min={step}
+									value={parameters[key]}
+									onChange={(e) =>
+										setParameters({
+											...parameters,
+											[key]: Number(e.target.value),
+										})
+									}
+									required
+									className="w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-blue-500 focus:border-blue-500"
+								/>
+							</div>
+						))}
+					</div>
+					<div className="flex justify-end space-x-4">
+						<button
+							type="button"
+							onClick={onClose}
+							className="px-4 py-2 text-gray-600 hover:text-gray-800"
+							disabled={saving}
+						>
+							Cancelar
+						</button>
+						<button
+							type="submit"
+							className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:bg-blue-300"
+							disabled={saving}
+						>
+							{saving ? "Guardando..." : "Guardar"}
+						</button>
+					</div>
+				</form>
+			</div>
+		</div>
+	);
+};
@@ -0,0 +1,82 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+import { useStore } from "../store";
+import type { TestJournal } from "../types";
+import { formatTime } from "../utils/timing";
+
+// Offers to continue a test that was interrupted before its results were
+// saved, checked once when the app starts
+export const RecoverTestModal: React.FC = () => {
+	const { recoverTest, discardTest } = useStore();
+	const [journal, setJournal] = useState<TestJournal | null>(null);
+
+	useEffect(() => {
+		invoke<TestJournal | null>("get_test_journal")
+			.then(setJournal)
+			.catch((error) => {
+				console.error("Error fetching the test journal:", error);
+			});
+	}, []);
+
+	if (!journal) return null;
+
+	const athletes = journal.state?.athletes ?? [];
+	const activeAthletes = athletes.filter((athlete) => athlete.active);
+
+	const handleRecover = async () => {
+		setJournal(null);
+		await recoverTest();
+	};
+
+	const handleDiscard = () => {
+		setJournal(null);
+		discardTest();
+	};
+
+	return (
+		<div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+			<div className="bg-white p-6 rounded-lg shadow-lg max-w-md w-full">
+				<h2 className="text-xl font-bold mb-4">Test sin finalizar</h2>
+				<p className="text-gray-600 mb-4">
+					Se encontró un test iniciado el{" "}
+					{new Date(journal.started_at).toLocaleString()} cuyos resultados no
+					se guardaron.
+				</p>
+				<div className="grid grid-cols-2 gap-4 text-sm mb-6">
+					<div>
+						<span className="text-gray-500">Tiempo transcurrido:</span>
+						<p className="font-medium">{formatTime(journal.elapsed)}</p>
+					</div>
+					<div>
+						<span className="text-gray-500">Deportistas en pista:</span>
+						<p className="font-medium">
+							{activeAthletes.length} de {athletes.length}
+						</p>
+					</div>
+					<div className="col-span-2">
+						<span className="text-gray-500">Última actualización:</span>
+						<p className="font-medium">
+							{new Date(journal.updated_at).toLocaleString()}
+						</p>
+					</div>
+				</div>
+				<div className="flex justify-end space-x-4">
+					<button
+						type="button"
+						onClick={handleDiscard}
+						className="px-4 py-2 text-red-600 hover:text-red-800"
+					>
+						Descartar
+					</button>
+					<button
+						type="button"
+						onClick={handleRecover}
+						className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700"
+					>
+						Recuperar
+					</button>
+				</div>
+			</div>
+		</div>
+	);
+};
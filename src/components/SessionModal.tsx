@@ -0,0 +1,217 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useState } from "react";
+import { toast } from "sonner";
+import type { Session, TestConfig } from "../types";
+
+interface SessionModalProps {
+	show: boolean;
+	onClose: () => void;
+	config: TestConfig;
+	onCreated: (session: Session) => void;
+}
+
+const today = () => new Date().toISOString().slice(0, 10);
+
+const optionalNumber = (value: string) => (value ? Number(value) : null);
+
+export const SessionModal: React.FC<SessionModalProps> = ({
+	show,
+	onClose,
+	config,
+	onCreated,
+}) => {
+	const [date, setDate] = useState(today);
+	const [venue, setVenue] = useState("");
+	const [surface, setSurface] = useState("");
+	const [temperature, setTemperature] = useState("");
+	const [humidity, setHumidity] = useState("");
+	const [evaluator, setEvaluator] = useState("");
+	const [notes, setNotes] = useState("");
+	// The track set up for the test is the session's one by default
+	const [useTrack, setUseTrack] = useState(true);
+	const [saving, setSaving] = useState(false);
+
+	if (!show) return null;
+
+	const handleSubmit = async (e: React.FormEvent) => {
+		e.preventDefault();
+		const session: Session = {
+			date,
+			venue: venue || null,
+			surface: surface || null,
+			temperature: optionalNumber(temperature),
+			humidity: optionalNumber(humidity),
+			evaluator: evaluator || null,
+			recovery_time: config.recoveryTime,
+			notes: notes || null,
+			track_geometry: useTrack
+				? {
+						track_length: config.trackLength,
+						segments_per_lap: config.segmentsPerLap,
+					}
+				: null,
+		};
+		try {
+			setSaving(true);
+			const id = await invoke<number>("create_session", { session });
+			toast.success("Sesión creada correctamente");
+			onCreated({ ...session, id });
+			onClose();
+		} catch (error) {
+			console.error("Error creating session:", error);
+			toast.error(
+				typeof error === "string" ? error : "Error al crear la sesión",
+			);
+		} finally {
+			setSaving(false);
+		}
+	};
+
+	const inputClass =
+		"w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-blue-500 focus:border-blue-500";
+
+	return (
+		<div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+			<div className="bg-white p-6 rounded-lg shadow-lg max-w-md w-full">
+				<h2 className="text-xl font-bold mb-4">Nueva Sesión</h2>
+				<form onSubmit={handleSubmit} className="space-y-3">
+					<div>
+						<label
+							htmlFor="sessionDate"
+							className="block text-sm font-medium text-gray-700 mb-1"
+						>
+							Fecha
+						</label>
+						<input
+							id="sessionDate"
+							type="date"
+							value={date}
+							onChange={(e) => setDate(e.target.value)}
+							required
+							className={inputClass}
+						/>
+					</div>
+					<div className="grid grid-cols-2 gap-2">
+						<div>
+							<label
+								htmlFor="sessionVenue"
+								className="block text-sm font-medium text-gray-700 mb-1"
+							>
+								Lugar
+							</label>
+							<input
+								id="sessionVenue"
+								value={venue}
+								onChange={(e) => setVenue(e.target.value)}
+								className={inputClass}
+							/>
+						</div>
+						<div>
+							<label
+								htmlFor="sessionSurface"
+								className="block text-sm font-medium text-gray-700 mb-1"
+							>
+								Superficie
+							</label>
+							<input
+								id="sessionSurface"
+								value={surface}
+								onChange={(e) => setSurface(e.target.value)}
+								className={inputClass}
+							/>
+						</div>
+						<div>
+							<label
+								htmlFor="sessionTemperature"
+								className="block text-sm font-medium text-gray-700 mb-1"
+							>
+								Temperatura (°C)
+							</label>
+							<input
+								id="sessionTemperature"
+								type="number"
+								step={0.1}
+								value={temperature}
+								onChange={(e) => setTemperature(e.target.value)}
+								className={inputClass}
+							/>
+						</div>
+						<div>
+							<label
+								htmlFor="sessionHumidity"
+								className="block text-sm font-medium text-gray-700 mb-1"
+							>
+								Humedad (%)
+							</label>
+							<input
+								id="sessionHumidity"
+								type="number"
+								min={0}
+								max={100}
+								value={humidity}
+								onChange={(e) => setHumidity(e.target.value)}
+								className={inputClass}
+							/>
+						</div>
+					</div>
+					<div>
+						<label
+							htmlFor="sessionEvaluator"
+							className="block text-sm font-medium text-gray-700 mb-1"
+						>
+							Evaluador
+						</label>
+						<input
+							id="sessionEvaluator"
+							value={evaluator}
+							onChange={(e) => setEvaluator(e.target.value)}
+							className={inputClass}
+						/>
+					</div>
+					<div>
+						<label
+							htmlFor="sessionNotes"
+							className="block text-sm font-medium text-gray-700 mb-1"
+						>
+							Notas
+						</label>
+						<textarea
+							id="sessionNotes"
+							value={notes}
+							onChange={(e) => setNotes(e.target.value)}
+							className={inputClass}
+							rows={2}
+						/>
+					</div>
+					<label className="flex items-center space-x-2 text-sm text-gray-700">
+						<input
+							type="checkbox"
+							checked={useTrack}
+							onChange={(e) => setUseTrack(e.target.checked)}
+						/>
+						<span>
+							Pista de {config.trackLength} m con {config.segmentsPerLap} conos
+						</span>
+					</label>
+					<div className="flex justify-end space-x-4">
+						<button
+							type="button"
+							onClick={onClose}
+							className="px-4 py-2 text-gray-600 hover:text-gray-800"
+							disabled={saving}
+						>
+							Cancelar
+						</button>
+						<button
+							type="submit"
+							className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:bg-blue-300"
+							disabled={saving}
+						>
+							{saving ? "Guardando..." : "Crear"}
+						</button>
+					</div>
+				</form>
+			</div>
+		</div>
+	);
+};
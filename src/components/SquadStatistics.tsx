@@ -0,0 +1,180 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useState } from "react";
+import { toast } from "sonner";
+import type {
+	GroupFilter,
+	GroupStatistics,
+	SquadStatistics as SquadStatisticsData,
+	StatMetric,
+} from "../types";
+
+const METRICS: Record<StatMetric, { label: string; unit: string }> = {
+	final_period: { label: "Periodo final", unit: "" },
+	total_distance: { label: "Distancia", unit: "m" },
+	vo2max: { label: "VO2max", unit: "ml/kg/min" },
+};
+
+const emptyToNull = (value: string) => (value.trim() ? value.trim() : null);
+
+function GroupSection({ group }: { group: GroupStatistics }) {
+	return (
+		<div className="space-y-3">
+			<h4 className="font-semibold">
+				{group.category ?? "Todos"} · {group.evaluations} deportistas
+			</h4>
+			<table className="w-full text-sm">
+				<thead>
+					<tr className="text-left text-gray-500 border-b">
+						<th className="py-1">Métrica</th>
+						<th>Media</th>
+						<th>DE</th>
+						<th>Mediana</th>
+						<th>Mín</th>
+						<th>Máx</th>
+						<th>Distribución</th>
+					</tr>
+				</thead>
+				<tbody>
+					{group.summaries.map((summary) => (
+						<tr key={summary.metric} className="border-b">
+							<td className="py-1">
+								{METRICS[summary.metric].label}{" "}
+								{METRICS[summary.metric].unit &&
+									`(${METRICS[summary.metric].unit})`}
+							</td>
+							<td>{summary.mean}</td>
+							<td>{summary.sd ?? "—"}</td>
+							<td>{summary.median}</td>
+							<td>{summary.min}</td>
+							<td>{summary.max}</td>
+							<td className="text-xs text-gray-600">
+								{summary.histogram
+									.map((bin) => `${bin.from}–${bin.to}: ${bin.count}`)
+									.join(" · ")}
+							</td>
+						</tr>
+					))}
+				</tbody>
+			</table>
+			<div className="grid grid-cols-3 gap-4 text-sm">
+				{group.rankings
+					.filter((ranking) => ranking.entries.length > 0)
+					.map((ranking) => (
+						<div key={ranking.metric}>
+							<span className="text-gray-500">
+								Ranking de {METRICS[ranking.metric].label}
+							</span>
+							<ol>
+								{ranking.entries.map((entry) => (
+									<li key={entry.evaluation_id}>
+										{entry.rank}. {entry.athlete_name} — {entry.value}{" "}
+										{METRICS[ranking.metric].unit}
+									</li>
+								))}
+							</ol>
+						</div>
+					))}
+			</div>
+		</div>
+	);
+}
+
+export function SquadStatistics() {
+	const [category, setCategory] = useState("");
+	const [club, setClub] = useState("");
+	const [from, setFrom] = useState("");
+	const [to, setTo] = useState("");
+	const [loading, setLoading] = useState(false);
+	const [statistics, setStatistics] = useState<SquadStatisticsData | null>(
+		null,
+	);
+
+	const handleCompute = async () => {
+		const filter: GroupFilter = {
+			category: emptyToNull(category),
+			club: emptyToNull(club),
+			from: emptyToNull(from),
+			to: emptyToNull(to),
+		};
+		try {
+			setLoading(true);
+			setStatistics(
+				await invoke<SquadStatisticsData>("get_group_statistics", { filter }),
+			);
+		} catch (error) {
+			console.error("Error computing statistics:", error);
+			toast.error(`Error al calcular las estadísticas: ${error}`);
+		} finally {
+			setLoading(false);
+		}
+	};
+
+	return (
+		<div className="space-y-4">
+			<h3 className="text-lg font-semibold">Estadísticas del Grupo</h3>
+			<p className="text-sm text-gray-500">
+				Última evaluación válida de cada deportista, agrupados por su categoría
+				actual
+			</p>
+			<div className="grid grid-cols-5 gap-4 items-end">
+				<input
+					type="text"
+					value={category}
+					onChange={(e) => setCategory(e.target.value)}
+					placeholder="Categoría"
+					className="rounded-md border-gray-300 shadow-sm sm:text-sm"
+				/>
+				<input
+					type="text"
+					value={club}
+					onChange={(e) => setClub(e.target.value)}
+					placeholder="Club"
+					className="rounded-md border-gray-300 shadow-sm sm:text-sm"
+				/>
+				<input
+					type="date"
+					value={from}
+					onChange={(e) => setFrom(e.target.value)}
+					title="Desde"
+					className="rounded-md border-gray-300 shadow-sm sm:text-sm"
+				/>
+				<input
+					type="date"
+					value={to}
+					onChange={(e) => setTo(e.target.value)}
+					title="Hasta"
+					className="rounded-md border-gray-300 shadow-sm sm:text-sm"
+				/>
+				<button
+					type="button"
+					onClick={handleCompute}
+					disabled={loading}
+					className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:bg-blue-300 text-sm"
+				>
+					{loading ? "Calculando..." : "Calcular"}
+				</button>
+			</div>
+
+			{statistics &&
+				(statistics.overall.evaluations === 0 ? (
+					<p className="text-gray-500">
+						No hay evaluaciones válidas para este grupo
+					</p>
+				) : (
+					<div className="space-y-6">
+						<GroupSection group={statistics.overall} />
+						{statistics.by_category.length > 1 &&
+							statistics.by_category.map((group) => (
+								<GroupSection
+									key={group.category ?? ""}
+									group={{
+										...group,
+										category: group.category ?? "Sin categoría",
+									}}
+								/>
+							))}
+					</div>
+				))}
+		</div>
+	);
+}
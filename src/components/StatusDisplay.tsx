@@ -1,6 +1,6 @@
 import { useEffect, useRef, useState } from "react";
 import { useStore } from "../store";
-import { getPeriodData } from "../utils/testData";
+import { getActiveGeometry, getPeriodData } from "../utils/testData";
 import { formatTime } from "../utils/timing";
 
 interface StatusDisplayProps {
@@ -46,32 +46,27 @@ export const StatusDisplay: React.FC<StatusDisplayProps> = ({
 		prevTotalDistanceRef.current = totalDistance;
 	}, [totalDistance, isStartingNewPeriod]);
 
-	let distanceInPeriod = totalDistance % 800;
+	const { trackLength, lapsPerPeriod } = getActiveGeometry();
+	const periodDistance = trackLength * lapsPerPeriod;
+	let distanceInPeriod = totalDistance % periodDistance;
 	let currentLap = 1;
 
 	if (isRecovery) {
-		// During recovery time, always show 800m and lap 4
-		distanceInPeriod = 800;
-		currentLap = 4;
+		// During recovery time, always show the full period on its last lap
+		distanceInPeriod = periodDistance;
+		currentLap = lapsPerPeriod;
 	} else if (isStartingNewPeriod) {
 		// Special case: Just started a new period after recovery
 		// Show lap 1 and distance 0m until athlete starts moving
 		distanceInPeriod = 0;
 		currentLap = 1;
 	} else if (distanceInPeriod === 0 && totalDistance > 0) {
-		// Special case: Completed a full 800m (transition to recovery)
-		// Show lap 4 and distance 800m
-		distanceInPeriod = 800;
-		currentLap = 4;
+		// Special case: Completed a full period (transition to recovery)
+		distanceInPeriod = periodDistance;
+		currentLap = lapsPerPeriod;
 	} else {
 		// Normal lap calculation during a period
-		if (distanceInPeriod >= 200 && distanceInPeriod < 400) {
-			currentLap = 2;
-		} else if (distanceInPeriod >= 400 && distanceInPeriod < 600) {
-			currentLap = 3;
-		} else if (distanceInPeriod >= 600 && distanceInPeriod < 800) {
-			currentLap = 4;
-		}
+		currentLap = Math.floor(distanceInPeriod / trackLength) + 1;
 	}
 
 	return (
@@ -1,4 +1,5 @@
 import type { TrackPosition } from "../types";
+import { getActiveGeometry } from "../utils/testData";
 
 interface TrackProps {
 	position: TrackPosition;
@@ -31,7 +32,8 @@ const LightIndicator: React.FC<LightIndicatorProps> = ({
 );
 
 export const Track: React.FC<TrackProps> = ({ position }) => {
-	const segments = Array(4).fill(0); // 4 segments per lap (50m each)
+	const { segmentsPerLap, lapsPerPeriod } = getActiveGeometry();
+	const segments = Array(segmentsPerLap).fill(0);
 
 	// Calculate positions for a segment in the oval track
 	const getSegmentPosition = (segmentIdx: number, lapIdx: number) => {
@@ -39,7 +41,7 @@ export const Track: React.FC<TrackProps> = ({ position }) => {
 		const trackHeight = 90; // Height percentage of the track
 		const centerX = 50; // Center X percentage
 		const centerY = 50; // Center Y percentage
-		const angle = -(segmentIdx / 4) * Math.PI * 2 - Math.PI / 2; // Start from top, negative for counterclockwise
+		const angle = -(segmentIdx / segmentsPerLap) * Math.PI * 2 - Math.PI / 2; // Start from top, negative for counterclockwise
 
 		// Adjust radius based on lap (outer to inner)
 		const radiusX = trackWidth / 2 - lapIdx * 5; // Decrease radius for inner laps
@@ -72,10 +74,10 @@ export const Track: React.FC<TrackProps> = ({ position }) => {
 
 				{/* Light indicators for each segment */}
 				{segments.map((_, segmentIdx) =>
-					Array(4)
+					Array(lapsPerPeriod)
 						.fill(0)
 						.map((_, lapIdx) => {
-							const segmentNumber = lapIdx * 4 + segmentIdx;
+							const segmentNumber = lapIdx * segmentsPerLap + segmentIdx;
 							const uniqueKey = `light-${segmentNumber}-${lapIdx}-${segmentIdx}`;
 							const pos = getSegmentPosition(segmentIdx, lapIdx);
 							return (
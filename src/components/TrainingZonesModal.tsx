@@ -0,0 +1,180 @@
+import { invoke } from "@tauri-apps/api/core";
+import { save } from "@tauri-apps/plugin-dialog";
+import { useEffect, useState } from "react";
+import { toast } from "sonner";
+import type { TrainingPlan } from "../types";
+
+interface TrainingZonesModalProps {
+	athleteId: number | null;
+	athleteName: string;
+	onClose: () => void;
+}
+
+type ExportFormat = "csv" | "xlsx";
+
+export const TrainingZonesModal: React.FC<TrainingZonesModalProps> = ({
+	athleteId,
+	athleteName,
+	onClose,
+}) => {
+	const [plan, setPlan] = useState<TrainingPlan | null>(null);
+	const [trackLength, setTrackLength] = useState<number | null>(null);
+	const [segmentsPerLap, setSegmentsPerLap] = useState<number | null>(null);
+	const [exporting, setExporting] = useState(false);
+
+	// The track of the test is used until the coach picks another one
+	const geometry =
+		trackLength !== null && segmentsPerLap !== null
+			? { track_length: trackLength, segments_per_lap: segmentsPerLap }
+			: null;
+
+	useEffect(() => {
+		if (athleteId === null) {
+			setTrackLength(null);
+			setSegmentsPerLap(null);
+			return;
+		}
+		invoke<TrainingPlan>("get_training_zones", { athleteId, geometry })
+			.then((plan) => {
+				setPlan(plan);
+				setTrackLength(plan.geometry.track_length);
+				setSegmentsPerLap(plan.geometry.segments_per_lap);
+			})
+			.catch((error) => {
+				console.error("Error fetching training zones:", error);
+				toast.error(`${error}`);
+			});
+	}, [athleteId, geometry?.track_length, geometry?.segments_per_lap]);
+
+	if (athleteId === null) return null;
+
+	const handleExport = async (format: ExportFormat) => {
+		try {
+			setExporting(true);
+			const filePath = await save({
+				defaultPath: `zonas_${athleteName.toLowerCase().replace(/\s+/g, "_")}.${format}`,
+				filters: [{ name: format.toUpperCase(), extensions: [format] }],
+			});
+			if (!filePath) {
+				return; // User cancelled
+			}
+			await invoke(
+				format === "csv"
+					? "export_training_zones"
+					: "export_training_zones_to_xlsx",
+				{ athleteIds: [athleteId], geometry, path: filePath },
+			);
+			toast.success("Zonas de entrenamiento exportadas exitosamente");
+		} catch (error) {
+			console.error("Error exporting training zones:", error);
+			toast.error("Error al exportar las zonas de entrenamiento");
+		} finally {
+			setExporting(false);
+		}
+	};
+
+	return (
+		<div className="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+			<div className="bg-white p-6 rounded-lg shadow-lg max-w-3xl w-full">
+				<h2 className="text-xl font-bold mb-4">
+					Zonas de entrenamiento de {athleteName}
+				</h2>
+				{plan && (
+					<>
+						<div className="grid grid-cols-3 gap-4 text-sm mb-4 items-end">
+							<div>
+								<span className="text-gray-500">VAM:</span>
+								<p className="font-medium">
+									{plan.mas} km/h (
+									{new Date(plan.date).toLocaleDateString()})
+								</p>
+							</div>
+							<label className="block">
+								<span className="text-gray-500">Pista (m)</span>
+								<input
+									type="number"
+									min="1"
+									value={trackLength ?? ""}
+									onChange={(e) =>
+										setTrackLength(Number.parseFloat(e.target.value) || null)
+									}
+									className="mt-1 block w-full rounded-md border-gray-300 shadow-sm sm:text-sm"
+								/>
+							</label>
+							<label className="block">
+								<span className="text-gray-500">Segmentos por vuelta</span>
+								<input
+									type="number"
+									min="1"
+									value={segmentsPerLap ?? ""}
+									onChange={(e) =>
+										setSegmentsPerLap(Number.parseInt(e.target.value) || null)
+									}
+									className="mt-1 block w-full rounded-md border-gray-300 shadow-sm sm:text-sm"
+								/>
+							</label>
+						</div>
+						<table className="w-full text-sm">
+							<thead>
+								<tr className="text-left text-gray-500 border-b">
+									<th className="py-1">Zona</th>
+									<th>% VAM</th>
+									<th>Velocidad (km/h)</th>
+									<th>Vuelta (s)</th>
+									<th>Segmento (s)</th>
+								</tr>
+							</thead>
+							<tbody>
+								{plan.targets.map((target) => (
+									<tr key={target.zone.name} className="border-b">
+										<td className="py-1">{target.zone.name}</td>
+										<td>
+											{target.zone.min_percent}–{target.zone.max_percent}
+										</td>
+										<td>
+											{target.speed_min}–{target.speed_max}
+										</td>
+										<td>
+											{target.lap_time_min}–{target.lap_time_max}
+										</td>
+										<td>
+											{target.segment_time_min}–{target.segment_time_max}
+										</td>
+									</tr>
+								))}
+							</tbody>
+						</table>
+					</>
+				)}
+				<div className="flex justify-end gap-2 mt-4">
+					<button
+						type="button"
+						onClick={() => handleExport("csv")}
+						disabled={!plan || exporting}
+						className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:bg-blue-300 text-sm"
+					>
+						Exportar CSV
+					</button>
+					<button
+						type="button"
+						onClick={() => handleExport("xlsx")}
+						disabled={!plan || exporting}
+						className="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700 disabled:bg-blue-300 text-sm"
+					>
+						Exportar Excel
+					</button>
+					<button
+						type="button"
+						onClick={() => {
+							setPlan(null);
+							onClose();
+						}}
+						className="px-4 py-2 text-gray-600 hover:text-gray-800"
+					>
+						Cerrar
+					</button>
+				</div>
+			</div>
+		</div>
+	);
+};
@@ -0,0 +1,19 @@
+import { listen } from "@tauri-apps/api/event";
+import { useEffect } from "react";
+import { useStore } from "../store";
+import type { TimerEvent } from "../types";
+
+// Follows the test clock run by the backend
+export function useTestTimer() {
+	const applyTimerEvent = useStore((state) => state.applyTimerEvent);
+
+	useEffect(() => {
+		const unlisten = listen<TimerEvent>("timer-event", (event) => {
+			applyTimerEvent(event.payload);
+		});
+
+		return () => {
+			unlisten.then((unlistenFn) => unlistenFn());
+		};
+	}, [applyTimerEvent]);
+}
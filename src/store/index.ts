@@ -1,37 +1,211 @@
 import { create } from "zustand";
 import { invoke } from "@tauri-apps/api/core";
 import { toast } from "sonner";
-import type { Athlete, TestConfig, TrackPosition } from "../types";
-import { AudioService } from "../utils/audio";
-import { TimerService } from "../services/TimerService";
-
-// Singleton instances for services
-let audioServiceInstance: AudioService | null = null;
-let timerServiceInstance: TimerService | null = null;
-
-// Get services without React hooks
-export function getServices() {
-	if (!audioServiceInstance) {
-		audioServiceInstance = new AudioService();
-		audioServiceInstance.preloadAll().catch((error) => {
-			console.error("Error loading audio files:", error);
-		});
+import type {
+	Anthropometrics,
+	Athlete,
+	EliminationRules,
+	EvaluationStatus,
+	JournalState,
+	PeriodResult,
+	Session,
+	TestConfig,
+	TestConfiguration,
+	TestJournal,
+	TimerCue,
+	TimerEvent,
+	TimerSnapshot,
+	TrackPosition,
+	Vo2maxEquation,
+	WarningRecord,
+} from "../types";
+import {
+	getActiveGeometry,
+	getActivePeriods,
+	getPeriodData,
+	loadProtocol,
+} from "../utils/testData";
+
+// Completed periods plus the period in progress, if the athlete covered
+// any distance in it
+function buildPeriodResults(athlete: Athlete, period: number): PeriodResult[] {
+	const lastDistance =
+		athlete.periodResults[athlete.periodResults.length - 1]
+			?.cumulative_distance ?? 0;
+	const periodData = getPeriodData(period);
+	if (!periodData || athlete.totalDistance <= lastDistance) {
+		return athlete.periodResults;
 	}
+	return [
+		...athlete.periodResults,
+		{
+			period,
+			speed: periodData.speed,
+			lap_time: periodData.lapTime,
+			cumulative_distance: athlete.totalDistance,
+			recorded_at: new Date().toISOString(),
+			completed: false,
+		},
+	];
+}
 
-	if (!timerServiceInstance) {
-		timerServiceInstance = new TimerService();
+// Seconds skated in the period in progress, the backend computes the
+// distance from it. Nothing while recovering or right after completing it.
+function lastPeriodElapsed(state: AppState, athlete: Athlete): number {
+	const lastCompleted =
+		athlete.completedPeriods[athlete.completedPeriods.length - 1];
+	if (state.isRecovery || lastCompleted === state.position.period) {
+		return 0;
 	}
+	return state.workTime;
+}
+
+function buildTestConfiguration(config: TestConfig): TestConfiguration {
+	return {
+		recovery_time: config.recoveryTime,
+		start_period: config.currentPeriod,
+		protocol_id: config.protocolId,
+		protocol_version: config.protocolVersion,
+		track_length: config.trackLength,
+		segments_per_lap: config.segmentsPerLap,
+		vo2max_equation: config.vo2maxEquation,
+		elimination_rules: config.eliminationRules,
+	};
+}
+
+// Athlete as stored by the backend, empty profile fields are sent as null so
+// they do not overwrite the stored ones. The notes taken in the test belong to
+// the evaluation, the athlete's own notes are left as they are.
+function buildAthleteRecord(athlete: Athlete) {
+	return {
+		name: athlete.name,
+		age: athlete.age,
+		weight: athlete.weight,
+		height: athlete.height,
+		observations: null,
+		birth_date: athlete.birthDate || null,
+		sex: athlete.sex ?? null,
+		discipline: athlete.discipline ?? null,
+		category: athlete.category || null,
+		club: athlete.club || null,
+		license_number: athlete.licenseNumber || null,
+	};
+}
+
+function buildMeasurements(athlete: Athlete): Anthropometrics {
+	return {
+		weight: athlete.weight,
+		height: athlete.height,
+		body_fat: athlete.bodyFat || null,
+		leg_length: athlete.legLength || null,
+	};
+}
+
+// Nothing to stop when the test already ended
+function stopTimer() {
+	invoke("stop_test_timer").catch(() => {});
+}
+
+// The journal is written on the first tick after a change, so that its clock
+// time covers every cue already applied, and every few seconds in between
+const JOURNAL_INTERVAL = 5000;
+let journalChanged = false;
+let lastJournalAt = 0;
+
+function saveJournal(state: AppState) {
+	const journal: JournalState = {
+		config: state.config,
+		athletes: state.athletes,
+	};
+	journalChanged = false;
+	lastJournalAt = Date.now();
+	invoke("update_test_journal", {
+		elapsed: state.clockElapsed,
+		state: journal,
+	}).catch((error) => {
+		console.error("Error saving the test journal:", error);
+	});
+}
+
+function discardJournal() {
+	journalChanged = false;
+	invoke("discard_test_journal").catch((error) => {
+		console.error("Error discarding the test journal:", error);
+	});
+}
 
+// Clock values shown by the UI. The period only moves forward, a stopped
+// clock keeps the position where the test ended.
+function timerState(snapshot: TimerSnapshot): Partial<AppState> {
 	return {
-		audioService: audioServiceInstance,
-		timerService: timerServiceInstance,
+		clockElapsed: snapshot.elapsed,
+		workTime: snapshot.work_time,
+		currentRecoveryTime: snapshot.recovery_remaining,
+		totalTime: snapshot.total_time,
+		prepCountdown: snapshot.phase === "preparation" ? snapshot.countdown : null,
+		isRecovery: snapshot.phase === "recovery",
+		position: {
+			period: snapshot.period,
+			lap: snapshot.lap,
+			segment: snapshot.segment,
+			elapsedTime: snapshot.work_time,
+			expectedSegment: snapshot.expected_segment,
+		},
 	};
 }
 
+// Distance of the active athletes after each segment, and the result of the
+// period when it is completed
+function applyCue(state: AppState, cue: TimerCue): Partial<AppState> {
+	switch (cue.kind) {
+		case "segment": {
+			const { segmentLength } = getActiveGeometry();
+			return {
+				athletes: state.athletes.map((athlete) =>
+					athlete.active
+						? { ...athlete, totalDistance: athlete.totalDistance + segmentLength }
+						: athlete,
+				),
+			};
+		}
+		case "period_completed":
+			return {
+				athletes: state.athletes.map((athlete) =>
+					athlete.active
+						? {
+								...athlete,
+								completedPeriods: [...athlete.completedPeriods, cue.period],
+								periodResults: [
+									...athlete.periodResults,
+									{
+										period: cue.period,
+										speed: cue.speed,
+										lap_time: cue.lap_time,
+										cumulative_distance: athlete.totalDistance,
+										recorded_at: new Date().toISOString(),
+										completed: true,
+									},
+								],
+							}
+						: athlete,
+				),
+			};
+		case "finished":
+			return { showFinishModal: true };
+	}
+}
+
 // Initial state values
 const initialConfig: TestConfig = {
+	sessionId: null,
 	recoveryTime: 45,
 	currentPeriod: 2,
+	protocolId: "standard",
+	protocolVersion: 1,
+	trackLength: 200,
+	segmentsPerLap: 4,
+	vo2maxEquation: { kind: "leger_mercier" },
+	eliminationRules: { consecutive_warnings: 2, max_warnings: null },
 	athleteCount: 1,
 	isRunning: false,
 	isPaused: false,
@@ -54,6 +228,7 @@ const initialAthlete: Athlete = {
 	height: 0,
 	active: true,
 	completedPeriods: [],
+	periodResults: [],
 	totalDistance: 0,
 };
 
@@ -65,11 +240,15 @@ interface AppState {
 	athletes: Athlete[];
 	isRecovery: boolean;
 	workTime: number;
+	// Time of the backend clock, including the preparation
+	clockElapsed: number;
 	currentRecoveryTime: number;
 	totalTime: number;
 	prepCountdown: number | null;
 	showFinishModal: boolean;
 	isStarting: boolean;
+	// The periods come from the backend, no test starts before they load
+	protocolLoaded: boolean;
 
 	// Actions
 	setConfig: (config: Partial<TestConfig>) => void;
@@ -86,13 +265,29 @@ interface AppState {
 	setShowFinishModal: (show: boolean) => void;
 
 	// Business logic
+	loadInitialProtocol: () => Promise<void>;
+	applyTimerEvent: (event: TimerEvent) => void;
+	recoverTest: () => Promise<void>;
+	discardTest: () => void;
 	startTest: () => Promise<void>;
-	pauseTest: () => void;
+	pauseTest: () => Promise<void>;
 	resetApp: () => void;
 	finishTest: () => Promise<void>;
-	finishAthlete: (athleteId: number) => Promise<void>;
+	finishAthlete: (
+		athleteId: number,
+		status?: EvaluationStatus,
+	) => Promise<void>;
+	recordWarning: (athleteId: number) => Promise<void>;
 	updatePeriod: (period: number) => void;
+	selectSession: (session: Session | null) => Promise<void>;
+	selectProtocol: (protocolId: string, version?: number) => Promise<void>;
+	updateTrackGeometry: (
+		trackLength: number,
+		segmentsPerLap: number,
+	) => Promise<void>;
 	updateRecoveryTime: (time: number) => void;
+	updateVo2maxEquation: (equation: Vo2maxEquation) => void;
+	updateEliminationRules: (rules: EliminationRules) => void;
 	updateAthleteCount: (count: number) => void;
 }
 
@@ -103,11 +298,13 @@ export const useStore = create<AppState>((set, get) => ({
 	athletes: [initialAthlete],
 	isRecovery: false,
 	workTime: 0,
+	clockElapsed: 0,
 	currentRecoveryTime: 0,
 	totalTime: 0,
 	prepCountdown: null,
 	showFinishModal: false,
 	isStarting: false,
+	protocolLoaded: false,
 
 	// Basic state setters
 	setConfig: (configUpdate) =>
@@ -143,11 +340,78 @@ export const useStore = create<AppState>((set, get) => ({
 	setShowFinishModal: (showFinishModal) => set({ showFinishModal }),
 
 	// Business logic
+	applyTimerEvent: (event) => {
+		switch (event.type) {
+			case "tick":
+				set(timerState(event));
+				if (
+					journalChanged ||
+					event.paused ||
+					Date.now() - lastJournalAt >= JOURNAL_INTERVAL
+				) {
+					saveJournal(get());
+				}
+				break;
+			case "stopped":
+				// Whoever stopped the clock already set the state it needs
+				set({ prepCountdown: null });
+				break;
+			case "cue":
+				set((state) => applyCue(state, event));
+				journalChanged = true;
+				break;
+		}
+	},
+
+	loadInitialProtocol: async () => {
+		try {
+			await loadProtocol();
+			set({ protocolLoaded: true });
+		} catch (error) {
+			console.error("Error loading protocol:", error);
+			toast.error("Error al cargar el protocolo, no se puede empezar el test");
+		}
+	},
+
+	// Restores the journaled test paused, the clock continues on resume
+	recoverTest: async () => {
+		try {
+			const [snapshot, journal] = await invoke<[TimerSnapshot, TestJournal]>(
+				"recover_test",
+			);
+			// Segment distances come from the active protocol
+			await loadProtocol(journal.protocol_id, journal.protocol_version, journal.geometry);
+			set((state) => ({
+				...timerState(snapshot),
+				protocolLoaded: true,
+				config: {
+					...state.config,
+					...journal.state?.config,
+					isRunning: false,
+					isPaused: true,
+					isFinished: false,
+				},
+				athletes: journal.state?.athletes ?? state.athletes,
+			}));
+			toast.success("Test recuperado, pulse Reanudar para continuar");
+		} catch (error) {
+			console.error("Error recovering the test:", error);
+			toast.error(`Error al recuperar el test: ${error}`);
+		}
+	},
+
+	discardTest: () => {
+		discardJournal();
+	},
+
 	startTest: async () => {
 		const state = get();
-		const { audioService, timerService } = getServices();
 
 		if (state.isStarting) return;
+		if (!state.protocolLoaded) {
+			toast.error("El protocolo todavía no se ha cargado");
+			return;
+		}
 		set({ isStarting: true });
 
 		// Verificar datos de atletas
@@ -156,7 +420,7 @@ export const useStore = create<AppState>((set, get) => ({
 			.filter(
 				(athlete) =>
 					!athlete.name.trim() ||
-					athlete.age <= 0 ||
+					(athlete.age <= 0 && !athlete.birthDate) ||
 					athlete.weight <= 0 ||
 					athlete.height <= 0,
 			);
@@ -170,33 +434,26 @@ export const useStore = create<AppState>((set, get) => ({
 		}
 
 		try {
-			if (state.config.isPaused) {
-				set((state) => ({
-					config: { ...state.config, isRunning: true, isPaused: false },
-				}));
-				timerService?.start();
-			} else {
-				// Inicio nuevo
-				if (!state.isRecovery) {
-					// Secuencia de inicio normal
-					set({ prepCountdown: 15 });
-					await audioService?.playCountdown();
-
-					// Cuenta regresiva visual
-					for (let i = 15; i > 0; i--) {
-						await new Promise((resolve) => setTimeout(resolve, 1000));
-						set({ prepCountdown: i - 1 });
-					}
-
-					set({ prepCountdown: null });
-					await audioService?.announceWorkStartPeriod(state.position.period);
-				}
-
-				set((state) => ({
-					config: { ...state.config, isRunning: true, isPaused: false },
-				}));
-				timerService?.start();
-			}
+			// The backend runs the clock, the countdown and the announcements
+			const snapshot = state.config.isPaused
+				? await invoke<TimerSnapshot>("resume_test_timer")
+				: await invoke<TimerSnapshot>("start_test_timer", {
+						protocolId: state.config.protocolId,
+						version: state.config.protocolVersion,
+						geometry: {
+							track_length: state.config.trackLength,
+							segments_per_lap: state.config.segmentsPerLap,
+						},
+						settings: {
+							start_period: state.position.period,
+							recovery_time: state.config.recoveryTime,
+						},
+					});
+			set((state) => ({
+				...timerState(snapshot),
+				config: { ...state.config, isRunning: true, isPaused: false },
+			}));
+			saveJournal(get());
 		} catch (error) {
 			console.error("Error al iniciar la prueba:", error);
 			toast.error("Error al iniciar la prueba");
@@ -205,26 +462,51 @@ export const useStore = create<AppState>((set, get) => ({
 		}
 	},
 
-	pauseTest: () => {
-		const { audioService, timerService } = getServices();
-
-		set((state) => ({
-			config: { ...state.config, isRunning: false, isPaused: true },
-		}));
-		timerService?.pause();
-		audioService?.announceTestPaused();
+	pauseTest: async () => {
+		try {
+			const snapshot = await invoke<TimerSnapshot>("pause_test_timer");
+			set((state) => ({
+				...timerState(snapshot),
+				config: { ...state.config, isRunning: false, isPaused: true },
+			}));
+		} catch (error) {
+			console.error("Error al pausar la prueba:", error);
+			toast.error("Error al pausar la prueba");
+		}
 	},
 
 	resetApp: () => {
-		const { timerService } = getServices();
-		timerService?.reset();
-
+		stopTimer();
+		discardJournal();
+
+		// Keep the session and the selected protocol, starting again from its
+		// first period
+		const {
+			sessionId,
+			protocolId,
+			protocolVersion,
+			trackLength,
+			segmentsPerLap,
+			vo2maxEquation,
+		} = get().config;
+		const firstPeriod =
+			getActivePeriods()[0]?.period ?? initialConfig.currentPeriod;
 		set({
-			config: initialConfig,
-			position: initialPosition,
+			config: {
+				...initialConfig,
+				sessionId,
+				protocolId,
+				protocolVersion,
+				trackLength,
+				segmentsPerLap,
+				vo2maxEquation,
+				currentPeriod: firstPeriod,
+			},
+			position: { ...initialPosition, period: firstPeriod },
 			athletes: [initialAthlete],
 			isRecovery: false,
 			workTime: 0,
+			clockElapsed: 0,
 			totalTime: 0,
 			currentRecoveryTime: 0,
 			prepCountdown: null,
@@ -234,6 +516,7 @@ export const useStore = create<AppState>((set, get) => ({
 	},
 
 	finishTest: async () => {
+		stopTimer();
 		const state = get();
 
 		set((state) => ({
@@ -249,7 +532,7 @@ export const useStore = create<AppState>((set, get) => ({
 			const invalidAthletes = activeAthletes.filter(
 				(athlete) =>
 					!athlete.name.trim() ||
-					athlete.age <= 0 ||
+					(athlete.age <= 0 && !athlete.birthDate) ||
 					athlete.weight <= 0 ||
 					athlete.height <= 0,
 			);
@@ -260,18 +543,28 @@ export const useStore = create<AppState>((set, get) => ({
 			}
 
 			// Prepare batch data
-			const evaluationsData = activeAthletes.map((athlete) => [
-				athlete,
-				JSON.stringify(athlete.completedPeriods),
-				Math.floor(state.totalTime),
-				athlete.totalDistance,
-				"completed",
-			]);
+			// The local id is only a slot index, not a database id
+			const evaluationsData = activeAthletes.map((athlete) => ({
+				athlete: buildAthleteRecord(athlete),
+				completed_periods: JSON.stringify(athlete.completedPeriods),
+				total_time: Math.floor(state.totalTime),
+				total_distance: athlete.totalDistance,
+				last_period_elapsed: lastPeriodElapsed(state, athlete),
+				measurements: buildMeasurements(athlete),
+				status: "completed",
+				status_reason: null,
+				observations: athlete.observations || null,
+				period_results: buildPeriodResults(athlete, state.position.period),
+				warnings: athlete.warnings ?? [],
+			}));
 
 			// Single batch save
 			await invoke("save_batch_evaluations", {
 				evaluations: evaluationsData,
+				sessionId: state.config.sessionId,
+				config: buildTestConfiguration(state.config),
 			});
+			discardJournal();
 
 			toast.success("Evaluaciones guardadas correctamente");
 		} catch (error) {
@@ -284,7 +577,7 @@ export const useStore = create<AppState>((set, get) => ({
 		}
 	},
 
-	finishAthlete: async (athleteId) => {
+	finishAthlete: async (athleteId, status = "completed") => {
 		const state = get();
 		const athlete = state.athletes.find((a) => a.id === athleteId);
 		if (!athlete) return;
@@ -295,17 +588,18 @@ export const useStore = create<AppState>((set, get) => ({
 
 			// Save the evaluation data for the finished athlete
 			await invoke("save_evaluation_data", {
-				athlete: {
-					name: athlete.name,
-					age: athlete.age,
-					weight: athlete.weight,
-					height: athlete.height,
-					observations: athlete.observations || "",
-				},
+				athlete: buildAthleteRecord(athlete),
 				completedPeriods: JSON.stringify(athlete.completedPeriods),
 				totalTime: roundedTotalTime,
 				totalDistance: athlete.totalDistance,
-				status: "completed",
+				lastPeriodElapsed: lastPeriodElapsed(state, athlete),
+				measurements: buildMeasurements(athlete),
+				status,
+				observations: athlete.observations || null,
+				sessionId: state.config.sessionId,
+				config: buildTestConfiguration(state.config),
+				periodResults: buildPeriodResults(athlete, state.position.period),
+				warnings: athlete.warnings ?? [],
 			});
 
 			// Update athlete state after successful save
@@ -314,6 +608,12 @@ export const useStore = create<AppState>((set, get) => ({
 					a.id === athleteId ? { ...a, active: false } : a,
 				),
 			}));
+			// Journaled now if the clock is paused, on the next tick otherwise
+			if (get().config.isPaused) {
+				saveJournal(get());
+			} else {
+				journalChanged = true;
+			}
 
 			toast.success(`Evaluación de ${athlete.name} guardada correctamente`);
 
@@ -322,6 +622,8 @@ export const useStore = create<AppState>((set, get) => ({
 				(a) => a.id !== athleteId && a.active,
 			).length;
 			if (remainingActiveAthletes === 0) {
+				stopTimer();
+				discardJournal();
 				set((state) => ({
 					config: { ...state.config, isFinished: true, isRunning: false },
 				}));
@@ -332,6 +634,43 @@ export const useStore = create<AppState>((set, get) => ({
 		}
 	},
 
+	// Warns the athlete for the last beep, finishing the athlete's test when
+	// the elimination rules are met
+	recordWarning: async (athleteId) => {
+		const state = get();
+		const athlete = state.athletes.find((a) => a.id === athleteId);
+		if (!athlete?.active) return;
+
+		try {
+			const record = await invoke<WarningRecord>("record_warning", {
+				warnings: athlete.warnings ?? [],
+				rules: state.config.eliminationRules,
+			});
+			set((state) => ({
+				athletes: state.athletes.map((a) =>
+					a.id === athleteId ? { ...a, warnings: record.warnings } : a,
+				),
+			}));
+			if (get().config.isPaused) {
+				saveJournal(get());
+			} else {
+				journalChanged = true;
+			}
+
+			if (record.status.eliminated) {
+				toast.warning(`${athlete.name} eliminado por avisos`);
+				await get().finishAthlete(athleteId, "eliminated_by_warnings");
+			} else {
+				toast.warning(
+					`Aviso ${record.status.total} para ${athlete.name} (periodo ${record.warning.period}, segmento ${record.warning.segment})`,
+				);
+			}
+		} catch (error) {
+			console.error(`Error recording a warning for ${athlete.name}:`, error);
+			toast.error(`Error al registrar el aviso: ${error}`);
+		}
+	},
+
 	updatePeriod: (period) => {
 		set((state) => ({
 			config: { ...state.config, currentPeriod: period },
@@ -339,6 +678,81 @@ export const useStore = create<AppState>((set, get) => ({
 		}));
 	},
 
+	// Tests of a session are run on its track
+	selectSession: async (session) => {
+		set((state) => ({
+			config: { ...state.config, sessionId: session?.id ?? null },
+		}));
+		if (session?.track_geometry) {
+			await get().updateTrackGeometry(
+				session.track_geometry.track_length,
+				session.track_geometry.segments_per_lap,
+			);
+		}
+	},
+
+	selectProtocol: async (protocolId, version) => {
+		// Within a session the track stays the same
+		const { sessionId, trackLength, segmentsPerLap } = get().config;
+		const geometry = sessionId
+			? { track_length: trackLength, segments_per_lap: segmentsPerLap }
+			: undefined;
+		try {
+			const protocol = await loadProtocol(protocolId, version, geometry);
+			const firstPeriod = protocol.periods[0]?.period ?? 1;
+			set((state) => ({
+				protocolLoaded: true,
+				config: {
+					...state.config,
+					protocolId: protocol.id,
+					protocolVersion: protocol.version,
+					trackLength: protocol.track_length,
+					segmentsPerLap: protocol.segments_per_lap,
+					currentPeriod: firstPeriod,
+				},
+				position: { ...state.position, period: firstPeriod },
+			}));
+		} catch (error) {
+			console.error("Error loading protocol:", error);
+			toast.error("Error al cargar el protocolo");
+		}
+	},
+
+	updateTrackGeometry: async (trackLength, segmentsPerLap) => {
+		const { protocolId, protocolVersion } = get().config;
+		try {
+			const protocol = await loadProtocol(protocolId, protocolVersion, {
+				track_length: trackLength,
+				segments_per_lap: segmentsPerLap,
+			});
+			set((state) => ({
+				protocolLoaded: true,
+				config: {
+					...state.config,
+					trackLength: protocol.track_length,
+					segmentsPerLap: protocol.segments_per_lap,
+				},
+			}));
+		} catch (error) {
+			console.error("Error updating track geometry:", error);
+			toast.error(
+				typeof error === "string" ? error : "Error al cambiar la pista",
+			);
+		}
+	},
+
+	updateVo2maxEquation: (equation) => {
+		set((state) => ({
+			config: { ...state.config, vo2maxEquation: equation },
+		}));
+	},
+
+	updateEliminationRules: (rules) => {
+		set((state) => ({
+			config: { ...state.config, eliminationRules: rules },
+		}));
+	},
+
 	updateRecoveryTime: (time) => {
 		set((state) => ({
 			config: { ...state.config, recoveryTime: time },
@@ -366,6 +780,7 @@ export const useStore = create<AppState>((set, get) => ({
 							height: 0,
 							active: true,
 							completedPeriods: [],
+							periodResults: [],
 							totalDistance: 0,
 						})),
 				],
@@ -1,3 +1,9 @@
+import type { TrackGeometry } from "../utils/testData";
+
+export type Sex = "female" | "male";
+
+export type Discipline = "inline_speed" | "quad" | "artistic" | "hockey";
+
 export interface Athlete {
 	id?: number;
 	name: string;
@@ -6,13 +12,140 @@ export interface Athlete {
 	height: number;
 	active: boolean;
 	completedPeriods: number[];
+	periodResults: PeriodResult[];
+	// Notes about the test in progress, saved with the evaluation
 	observations?: string;
 	totalDistance: number;
+	// Beeps missed in the test in progress
+	warnings?: Warning[];
+	// YYYY-MM-DD, the backend derives the age from it
+	birthDate?: string;
+	sex?: Sex;
+	discipline?: Discipline;
+	category?: string;
+	club?: string;
+	licenseNumber?: string;
+	// Percentage and cm, measured before the test
+	bodyFat?: number;
+	legLength?: number;
+}
+
+// Body measurements recorded with each evaluation
+export interface Anthropometrics {
+	weight: number;
+	height: number;
+	body_fat: number | null;
+	leg_length: number | null;
+}
+
+export type EvaluationStatus =
+	| "completed"
+	| "voluntary_stop"
+	| "eliminated_by_warnings"
+	| "injury"
+	| "equipment_failure"
+	| "disqualified"
+	| "cancelled";
+
+// A beep on which the athlete had not reached the cone of `segment` (from 1),
+// `elapsed` is the clock time of the test in seconds
+export interface Warning {
+	period: number;
+	segment: number;
+	elapsed: number;
+	recorded_at: string;
+}
+
+// Warnings in a row, or in the whole test, that eliminate an athlete
+export interface EliminationRules {
+	consecutive_warnings: number;
+	max_warnings: number | null;
+}
+
+export interface WarningStatus {
+	total: number;
+	consecutive: number;
+	eliminated: boolean;
+}
+
+export interface WarningRecord {
+	warning: Warning;
+	warnings: Warning[];
+	status: WarningStatus;
+}
+
+export interface PeriodResult {
+	period: number;
+	speed: number;
+	lap_time: number;
+	cumulative_distance: number;
+	recorded_at: string;
+	completed: boolean;
+}
+
+// Regression estimating VO2max (ml/kg/min) from the maximal aerobic speed
+export type Vo2maxEquation =
+	| { kind: "leger_mercier" }
+	| { kind: "linear"; intercept: number; slope: number };
+
+// Speeds in km/h, VO2max in ml/kg/min
+export interface AerobicEstimate {
+	final_speed: number;
+	mas: number;
+	vo2max: number;
+}
+
+export type NormMetric = "total_distance" | "mas" | "vo2max";
+
+export type Rating = "very_poor" | "poor" | "average" | "good" | "excellent";
+
+// Where a result falls within the matching normative table
+export interface Classification {
+	metric: NormMetric;
+	value: number;
+	percentile: number;
+	rating: Rating;
+	norm_id: number | null;
+}
+
+// Configuration saved with each evaluation
+export interface TestConfiguration {
+	recovery_time: number | null;
+	start_period: number | null;
+	protocol_id: string;
+	protocol_version: number;
+	track_length: number;
+	segments_per_lap: number;
+	vo2max_equation: Vo2maxEquation;
+	elimination_rules: EliminationRules | null;
+}
+
+// A group of evaluations run together, on one track when it is known
+export interface Session {
+	id?: number | null;
+	// YYYY-MM-DD
+	date: string;
+	venue: string | null;
+	surface: string | null;
+	temperature: number | null;
+	humidity: number | null;
+	evaluator: string | null;
+	recovery_time: number | null;
+	notes: string | null;
+	track_geometry: TrackGeometry | null;
 }
 
 export interface TestConfig {
+	// Session the evaluations are saved in, if any
+	sessionId: number | null;
 	recoveryTime: number;
 	currentPeriod: number;
+	protocolId: string;
+	protocolVersion: number;
+	trackLength: number;
+	segmentsPerLap: number;
+	vo2maxEquation: Vo2maxEquation;
+	eliminationRules: EliminationRules;
 	athleteCount: number;
 	isRunning: boolean;
 	isPaused: boolean;
@@ -26,3 +159,227 @@ export interface TrackPosition {
 	elapsedTime: number;
 	expectedSegment: number;
 }
+
+// Evolution of an athlete's results across valid tests
+export interface ProgressPoint {
+	evaluation_id: number;
+	date: string;
+	total_distance: number;
+	final_speed: number | null;
+	distance_delta: number | null;
+	speed_delta: number | null;
+	// Percent change since the first test
+	distance_change: number | null;
+	speed_change: number | null;
+}
+
+export interface Trend {
+	slope_per_month: number;
+	baseline: number;
+	r_squared: number | null;
+}
+
+export interface AthleteProgress {
+	athlete_id: number;
+	points: ProgressPoint[];
+	personal_best: ProgressPoint | null;
+	distance_trend: Trend | null;
+	speed_trend: Trend | null;
+}
+
+// Team statistics of a session or a filtered group
+export type StatMetric = "final_period" | "total_distance" | "vo2max";
+
+export interface GroupFilter {
+	session_id?: number | null;
+	category?: string | null;
+	club?: string | null;
+	sex?: Sex | null;
+	discipline?: Discipline | null;
+	// YYYY-MM-DD, inclusive
+	from?: string | null;
+	to?: string | null;
+}
+
+export interface HistogramBin {
+	from: number;
+	to: number;
+	count: number;
+}
+
+export interface Summary {
+	metric: StatMetric;
+	count: number;
+	mean: number;
+	sd: number | null;
+	median: number;
+	min: number;
+	max: number;
+	histogram: HistogramBin[];
+}
+
+export interface RankEntry {
+	rank: number;
+	evaluation_id: number;
+	athlete_id: number;
+	athlete_name: string;
+	value: number;
+}
+
+export interface Ranking {
+	metric: StatMetric;
+	entries: RankEntry[];
+}
+
+export interface GroupStatistics {
+	category: string | null;
+	evaluations: number;
+	summaries: Summary[];
+	rankings: Ranking[];
+}
+
+export interface SquadStatistics {
+	overall: GroupStatistics;
+	by_category: GroupStatistics[];
+}
+
+// Intensity band as a percentage of the maximal aerobic speed
+export interface TrainingZone {
+	name: string;
+	min_percent: number;
+	max_percent: number;
+}
+
+// Speeds in km/h, times in seconds. The shortest times match the top of
+// the zone.
+export interface ZoneTarget {
+	zone: TrainingZone;
+	speed_min: number;
+	speed_max: number;
+	lap_time_min: number;
+	lap_time_max: number;
+	segment_time_min: number;
+	segment_time_max: number;
+}
+
+export interface TrainingPlan {
+	athlete_id: number;
+	athlete_name: string;
+	evaluation_id: number;
+	date: string;
+	mas: number;
+	geometry: TrackGeometry;
+	targets: ZoneTarget[];
+}
+
+// Heart rate recording aligned to the start of the test (seconds)
+export interface HeartRateSample {
+	elapsed: number;
+	heart_rate: number;
+}
+
+export interface HeartRateRecording {
+	evaluation_id: number;
+	source: string;
+	format: "csv" | "tcx" | "fit";
+	start_offset: number;
+	imported_at: string;
+	samples: HeartRateSample[];
+}
+
+export interface PeriodWindow {
+	period: number;
+	start: number;
+	end: number;
+}
+
+export interface HeartRateSummary {
+	max: number;
+	mean: number;
+	end: number | null;
+	periods: { period: number; mean: number; max: number }[];
+	// Drop from the final heart rate `after` seconds after the test
+	recovery: { after: number; heart_rate: number; drop: number }[];
+}
+
+export interface HeartRateAnalysis {
+	recording: HeartRateRecording;
+	periods: PeriodWindow[];
+	test_end: number | null;
+	summary: HeartRateSummary | null;
+}
+
+// Blood lactate (mmol/L) and RPE (Borg CR-10) taken after a period
+export interface PeriodMeasurement {
+	period: number;
+	lactate: number | null;
+	rpe: number | null;
+}
+
+export interface PeriodEntry extends PeriodMeasurement {
+	// km/h, null when the evaluation has no result for the period
+	speed: number | null;
+}
+
+// Speeds (km/h) of the lactate thresholds
+export interface LactateThresholds {
+	fixed: number | null;
+	dmax: number | null;
+	dmax_lactate: number | null;
+}
+
+export interface LactateAnalysis {
+	evaluation_id: number;
+	periods: PeriodEntry[];
+	peak_lactate: number | null;
+	max_rpe: number | null;
+	thresholds: LactateThresholds;
+}
+
+// Test clock run by the backend, see the "timer-event" channel
+export type TimerPhase = "preparation" | "work" | "recovery" | "finished";
+
+export interface TimerSnapshot {
+	phase: TimerPhase;
+	// Period being run, or the next one during preparation and recovery
+	period: number;
+	paused: boolean;
+	elapsed: number;
+	total_time: number;
+	work_time: number;
+	recovery_remaining: number;
+	countdown: number | null;
+	lap: number;
+	segment: number;
+	expected_segment: number;
+}
+
+export type TimerCue =
+	| { kind: "segment"; period: number; segment: number }
+	| { kind: "period_completed"; period: number; speed: number; lap_time: number }
+	| { kind: "finished" };
+
+export type TimerEvent =
+	| ({ type: "tick" } & TimerSnapshot)
+	| ({ type: "stopped" } & TimerSnapshot)
+	| ({ type: "cue" } & TimerCue);
+
+// UI state saved while a test runs
+export interface JournalState {
+	config: TestConfig;
+	athletes: Athlete[];
+}
+
+// Test in progress journaled by the backend, recoverable after a crash
+export interface TestJournal {
+	protocol_id: string;
+	protocol_version: number;
+	geometry: { track_length: number; segments_per_lap: number };
+	start_period: number;
+	recovery_time: number;
+	started_at: string;
+	updated_at: string;
+	// Clock time, in seconds, when `state` was saved
+	elapsed: number;
+	state: JournalState | null;
+}
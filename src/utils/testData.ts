@@ -1,3 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
 export interface PeriodData {
 	period: number;
 	speed: number;
@@ -7,281 +9,90 @@ export interface PeriodData {
 	distance: number;
 }
 
-export const periodData: PeriodData[] = [
-	{
-		period: 2,
-		speed: 20.8,
-		lapTime: 34.61,
-		partialTime: 8.65,
-		totalTime: 138.4,
-		distance: 800,
-	},
-	{
-		period: 3,
-		speed: 21.6,
-		lapTime: 33.33,
-		partialTime: 8.33,
-		totalTime: 133.28,
-		distance: 1600,
-	},
-	{
-		period: 4,
-		speed: 22.4,
-		lapTime: 32.14,
-		partialTime: 8.03,
-		totalTime: 128.48,
-		distance: 2400,
-	},
-	{
-		period: 5,
-		speed: 23.2,
-		lapTime: 31.03,
-		partialTime: 7.75,
-		totalTime: 124,
-		distance: 3200,
-	},
-	{
-		period: 6,
-		speed: 24.0,
-		lapTime: 30.0,
-		partialTime: 7.5,
-		totalTime: 120,
-		distance: 4000,
-	},
-	{
-		period: 7,
-		speed: 24.8,
-		lapTime: 29.03,
-		partialTime: 7.25,
-		totalTime: 116,
-		distance: 4800,
-	},
-	{
-		period: 8,
-		speed: 25.6,
-		lapTime: 28.12,
-		partialTime: 7.03,
-		totalTime: 112.48,
-		distance: 5600,
-	},
-	{
-		period: 9,
-		speed: 26.4,
-		lapTime: 27.27,
-		partialTime: 6.81,
-		totalTime: 108.96,
-		distance: 6400,
-	},
-	{
-		period: 10,
-		speed: 27.2,
-		lapTime: 26.47,
-		partialTime: 6.61,
-		totalTime: 105.76,
-		distance: 7200,
-	},
-	{
-		period: 11,
-		speed: 28.0,
-		lapTime: 25.71,
-		partialTime: 6.42,
-		totalTime: 102.72,
-		distance: 8000,
-	},
-	{
-		period: 12,
-		speed: 28.8,
-		lapTime: 25.0,
-		partialTime: 6.25,
-		totalTime: 100,
-		distance: 8800,
-	},
-	{
-		period: 13,
-		speed: 29.6,
-		lapTime: 24.32,
-		partialTime: 6.08,
-		totalTime: 97.28,
-		distance: 9600,
-	},
-	{
-		period: 14,
-		speed: 30.4,
-		lapTime: 23.68,
-		partialTime: 5.92,
-		totalTime: 94.72,
-		distance: 10400,
-	},
-	{
-		period: 15,
-		speed: 31.2,
-		lapTime: 23.07,
-		partialTime: 5.76,
-		totalTime: 92.16,
-		distance: 11200,
-	},
-	{
-		period: 16,
-		speed: 32.0,
-		lapTime: 22.5,
-		partialTime: 5.62,
-		totalTime: 89.92,
-		distance: 12000,
-	},
-	{
-		period: 17,
-		speed: 32.8,
-		lapTime: 21.95,
-		partialTime: 5.48,
-		totalTime: 87.68,
-		distance: 12800,
-	},
-	{
-		period: 18,
-		speed: 33.6,
-		lapTime: 21.42,
-		partialTime: 5.35,
-		totalTime: 85.6,
-		distance: 13600,
-	},
-	{
-		period: 19,
-		speed: 34.4,
-		lapTime: 20.93,
-		partialTime: 5.23,
-		totalTime: 83.68,
-		distance: 14400,
-	},
-	{
-		period: 20,
-		speed: 35.2,
-		lapTime: 20.45,
-		partialTime: 5.11,
-		totalTime: 81.76,
-		distance: 15200,
-	},
-	{
-		period: 21,
-		speed: 36.0,
-		lapTime: 20.0,
-		partialTime: 5.0,
-		totalTime: 80,
-		distance: 16000,
-	},
-	{
-		period: 22,
-		speed: 36.8,
-		lapTime: 19.56,
-		partialTime: 4.89,
-		totalTime: 78.24,
-		distance: 16800,
-	},
-	{
-		period: 23,
-		speed: 37.6,
-		lapTime: 19.14,
-		partialTime: 4.79,
-		totalTime: 76.64,
-		distance: 17600,
-	},
-	{
-		period: 24,
-		speed: 38.4,
-		lapTime: 18.75,
-		partialTime: 4.69,
-		totalTime: 75.04,
-		distance: 18400,
-	},
-	{
-		period: 25,
-		speed: 39.2,
-		lapTime: 18.36,
-		partialTime: 4.59,
-		totalTime: 73.44,
-		distance: 19200,
-	},
-	{
-		period: 26,
-		speed: 40.0,
-		lapTime: 18.0,
-		partialTime: 4.5,
-		totalTime: 72,
-		distance: 20000,
-	},
-	{
-		period: 27,
-		speed: 40.8,
-		lapTime: 17.64,
-		partialTime: 4.41,
-		totalTime: 70.56,
-		distance: 20800,
-	},
-	{
-		period: 28,
-		speed: 41.6,
-		lapTime: 17.3,
-		partialTime: 4.33,
-		totalTime: 69.28,
-		distance: 21600,
-	},
-	{
-		period: 29,
-		speed: 42.4,
-		lapTime: 16.98,
-		partialTime: 4.24,
-		totalTime: 67.92,
-		distance: 22400,
-	},
-	{
-		period: 30,
-		speed: 43.2,
-		lapTime: 16.66,
-		partialTime: 4.17,
-		totalTime: 66.72,
-		distance: 23200,
-	},
-	{
-		period: 31,
-		speed: 44.0,
-		lapTime: 16.36,
-		partialTime: 4.09,
-		totalTime: 65.44,
-		distance: 24000,
-	},
-	{
-		period: 32,
-		speed: 44.8,
-		lapTime: 16.07,
-		partialTime: 4.02,
-		totalTime: 64.32,
-		distance: 24800,
-	},
-	{
-		period: 33,
-		speed: 45.6,
-		lapTime: 15.79,
-		partialTime: 3.95,
-		totalTime: 63.2,
-		distance: 25600,
-	},
-	{
-		period: 34,
-		speed: 46.4,
-		lapTime: 15.51,
-		partialTime: 3.88,
-		totalTime: 62.08,
-		distance: 26400,
-	},
-	{
-		period: 35,
-		speed: 47.2,
-		lapTime: 15.25,
-		partialTime: 3.81,
-		totalTime: 61,
-		distance: 27200,
-	},
-];
+interface ProtocolPeriod {
+	period: number;
+	speed: number;
+	lap_time: number;
+	partial_time: number;
+	total_time: number;
+	distance: number;
+}
+
+export interface TrackGeometry {
+	track_length: number;
+	segments_per_lap: number;
+}
+
+export interface ProtocolParameters {
+	start_period: number;
+	period_count: number;
+	start_speed: number;
+	speed_increment: number;
+	laps_per_period: number;
+	track_length: number;
+	segments_per_lap: number;
+}
+
+export interface Protocol {
+	id: string;
+	version: number;
+	name: string;
+	track_length: number;
+	segments_per_lap: number;
+	laps_per_period: number;
+	parameters: ProtocolParameters | null;
+	periods: ProtocolPeriod[];
+}
+
+export interface ActiveGeometry {
+	trackLength: number;
+	segmentsPerLap: number;
+	lapsPerPeriod: number;
+	segmentLength: number;
+}
+
+// Empty until the backend table is loaded
+let activePeriods: PeriodData[] = [];
+let activeGeometry: ActiveGeometry = {
+	trackLength: 200,
+	segmentsPerLap: 4,
+	lapsPerPeriod: 4,
+	segmentLength: 50,
+};
+
+// The backend owns the protocol tables. With a geometry the protocol times
+// are recomputed for that track.
+export const loadProtocol = async (
+	protocolId?: string,
+	version?: number,
+	geometry?: TrackGeometry,
+): Promise<Protocol> => {
+	const protocol = await invoke<Protocol>("get_protocol", {
+		protocolId,
+		version,
+		geometry,
+	});
+	activeGeometry = {
+		trackLength: protocol.track_length,
+		segmentsPerLap: protocol.segments_per_lap,
+		lapsPerPeriod: protocol.laps_per_period,
+		segmentLength: protocol.track_length / protocol.segments_per_lap,
+	};
+	activePeriods = protocol.periods.map((p) => ({
+		period: p.period,
+		speed: p.speed,
+		lapTime: p.lap_time,
+		partialTime: p.partial_time,
+		totalTime: p.total_time,
+		distance: p.distance,
+	}));
+	return protocol;
+};
+
+export const getActivePeriods = (): PeriodData[] => activePeriods;
+
+export const getActiveGeometry = (): ActiveGeometry => activeGeometry;
 
 export const getPeriodData = (period: number): PeriodData | undefined => {
-	return periodData.find((data) => data.period === period);
+	return activePeriods.find((data) => data.period === period);
 };
@@ -1,4 +1,4 @@
-import { getPeriodData } from "./testData";
+import { getActiveGeometry, getPeriodData } from "./testData";
 
 export const calculateIntervalTime = (period: number): number => {
 	const periodData = getPeriodData(period);
@@ -47,8 +47,9 @@ export const calculateDistance = (
 	// Calculate segments completed in partial lap
 	const partialSegments = Math.floor(remainingTime / periodData.partialTime);
 
-	// Each lap is 200m (4 segments of 50m each)
-	const totalDistance = completeLaps * 200 + partialSegments * 50;
+	const { trackLength, segmentLength } = getActiveGeometry();
+	const totalDistance =
+		completeLaps * trackLength + partialSegments * segmentLength;
 
 	return totalDistance;
 };